@@ -0,0 +1,314 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use chrono::{DateTime, Duration, Utc};
+use winstructs::ntfs::mft_reference::MftReference;
+
+use crate::open_files::OpenFiles;
+use crate::{CommonUsnRecord, FileAttributeValue, PathResolver, UsnReason, UsnReasonValue};
+#[cfg(feature = "formatters")]
+use crate::serialize::reference_to_string;
+use super::{Analyzer, RecordRef, EXECUTABLE_EXTENSIONS, extension_of};
+
+/// reasons, which show that a named stream has been written
+const NAMED_DATA_CHANGES: u32 = UsnReasonValue::USN_REASON_NAMED_DATA_OVERWRITE as u32
+    | UsnReasonValue::USN_REASON_NAMED_DATA_EXTEND as u32
+    | UsnReasonValue::USN_REASON_NAMED_DATA_TRUNCATION as u32;
+
+const STREAM_CHANGES: u32 = UsnReasonValue::USN_REASON_STREAM_CHANGE as u32 | NAMED_DATA_CHANGES;
+
+/// reasons, which show that a file has been created, written or got a new name,
+/// like a download does right before its Zone.Identifier stream is added
+const CONTENT_CHANGES: u32 = UsnReasonValue::USN_REASON_FILE_CREATE as u32
+    | UsnReasonValue::USN_REASON_DATA_OVERWRITE as u32
+    | UsnReasonValue::USN_REASON_DATA_EXTEND as u32
+    | UsnReasonValue::USN_REASON_DATA_TRUNCATION as u32
+    | UsnReasonValue::USN_REASON_RENAME_NEW_NAME as u32;
+
+pub struct AdsSettings {
+    /// maximum time between writing a file and adding its Zone.Identifier stream
+    pub mark_of_the_web_delay: Duration,
+
+    /// extensions of files, which should keep their Zone.Identifier stream
+    pub executable_extensions: Vec<String>,
+}
+
+impl Default for AdsSettings {
+    fn default() -> Self {
+        Self {
+            mark_of_the_web_delay: Duration::seconds(60),
+            executable_extensions: EXECUTABLE_EXTENSIONS.iter().map(|e| e.to_string()).collect(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum StreamEventKind {
+    /// a stream has been added right after the file had been written, like
+    /// the Zone.Identifier stream of a download
+    MarkOfTheWeb,
+
+    /// a stream has been removed from a file, which got its mark of the web before
+    MarkOfTheWebRemoved,
+
+    /// a stream has been added to a file or a directory
+    Added,
+
+    /// an existing stream has been written
+    Written,
+
+    /// a stream has been removed
+    Removed,
+}
+
+impl StreamEventKind {
+    /// returns `true` for activity, which is common on every system
+    pub fn is_benign(&self) -> bool {
+        matches!(self, Self::MarkOfTheWeb)
+    }
+}
+
+impl fmt::Display for StreamEventKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::MarkOfTheWeb => write!(f, "mark of the web"),
+            Self::MarkOfTheWebRemoved => write!(f, "mark of the web removed"),
+            Self::Added => write!(f, "stream added"),
+            Self::Written => write!(f, "stream written"),
+            Self::Removed => write!(f, "stream removed"),
+        }
+    }
+}
+
+/// the activity in the alternate data streams of a file between opening and closing it
+#[derive(Clone, Debug)]
+pub struct StreamEvent {
+    pub kind: StreamEventKind,
+
+    /// all reasons, which have been set until the file has been closed
+    pub reasons: UsnReason,
+
+    /// the first record, which shows activity in an alternate data stream
+    pub record: RecordRef,
+}
+
+impl StreamEvent {
+    #[cfg(feature = "formatters")]
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "kind": self.kind.to_string(),
+            "benign": self.kind.is_benign(),
+            "reasons": self.reasons.to_string(),
+            "record": self.record.to_json(),
+        })
+    }
+}
+
+/// the activity in the alternate data streams of a single file
+#[derive(Clone, Debug)]
+pub struct StreamActivity {
+    pub file_reference: MftReference,
+
+    /// the latest parent and name of the file
+    pub parent_reference: MftReference,
+    pub name: String,
+    pub path: Option<String>,
+    pub is_directory: bool,
+    pub is_executable: bool,
+    pub events: Vec<StreamEvent>,
+}
+
+impl StreamActivity {
+    /// returns `true` if there is any activity, which is not benign
+    pub fn is_unusual(&self) -> bool {
+        self.events.iter().any(|e| !e.kind.is_benign())
+    }
+
+    /// returns `true` if the file lost its mark of the web
+    pub fn lost_mark_of_the_web(&self) -> bool {
+        self.events.iter().any(|e| e.kind == StreamEventKind::MarkOfTheWebRemoved)
+    }
+
+    #[cfg(feature = "formatters")]
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "file_reference": reference_to_string(&self.file_reference),
+            "parent_reference": reference_to_string(&self.parent_reference),
+            "name": self.name,
+            "path": self.path,
+            "is_directory": self.is_directory,
+            "is_executable": self.is_executable,
+            "unusual": self.is_unusual(),
+            "lost_mark_of_the_web": self.lost_mark_of_the_web(),
+            "events": self.events.iter().map(StreamEvent::to_json).collect::<Vec<_>>(),
+        })
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct AdsReport {
+    /// all files with activity in alternate data streams, in the order of their first activity
+    pub files: Vec<StreamActivity>,
+}
+
+impl AdsReport {
+    /// returns all files with activity, which is not benign
+    pub fn unusual(&self) -> impl Iterator<Item=&StreamActivity> {
+        self.files.iter().filter(|f| f.is_unusual())
+    }
+
+    /// returns all executables, which lost their mark of the web after they had been downloaded
+    pub fn stripped_executables(&self) -> impl Iterator<Item=&StreamActivity> {
+        self.files.iter().filter(|f| f.is_executable && f.lost_mark_of_the_web())
+    }
+
+    #[cfg(feature = "formatters")]
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "files": self.files.iter().map(StreamActivity::to_json).collect::<Vec<_>>(),
+            "stripped_executables": self.stripped_executables()
+                .map(|f| reference_to_string(&f.file_reference)).collect::<Vec<_>>(),
+        })
+    }
+}
+
+/// a file, which is open while it has activity in its alternate data streams
+struct OpenStream {
+    first: RecordRef,
+    reasons: u32,
+    is_directory: bool,
+}
+
+/// pulls the activity in alternate data streams out of the journal.
+///
+/// The journal does not contain the names of streams, so streams are told
+/// apart by the pattern of their records: a stream which is added right after
+/// a file had been written is considered to be the Zone.Identifier stream
+/// (mark of the web) of a download. Every other stream activity is unusual.
+/// If a file loses a stream after it got its mark of the web, the mark of the
+/// web is considered to be removed.
+pub struct AdsAnalyzer {
+    settings: AdsSettings,
+    open_files: OpenFiles,
+    resolver: PathResolver,
+    open_streams: HashMap<MftReference, OpenStream>,
+    last_content_change: HashMap<MftReference, DateTime<Utc>>,
+
+    /// files, which got their mark of the web
+    marked: HashSet<MftReference>,
+    files: Vec<StreamActivity>,
+    file_index: HashMap<MftReference, usize>,
+}
+
+impl Analyzer for AdsAnalyzer {
+    fn add(&mut self, record: &CommonUsnRecord) {
+        let data = &record.data;
+        let file_reference = *data.file_reference();
+        let reason = data.reason();
+        let new_reasons = self.open_files.new_reasons(record);
+        self.resolver.learn(record);
+
+        if new_reasons.value() & CONTENT_CHANGES != 0 {
+            self.last_content_change.insert(file_reference, *data.timestamp());
+        }
+        if reason.value() & STREAM_CHANGES != 0 {
+            let stream = self.open_streams.entry(file_reference).or_insert_with(|| OpenStream {
+                first: RecordRef::from(record),
+                reasons: 0,
+                is_directory: false,
+            });
+            stream.reasons = reason.value();
+            stream.is_directory = data.file_attributes().has_flag(FileAttributeValue::FILE_ATTRIBUTE_DIRECTORY);
+        }
+        if reason.has_flag(UsnReasonValue::USN_REASON_CLOSE) {
+            if let Some(stream) = self.open_streams.remove(&file_reference) {
+                self.close_stream(stream);
+            }
+        }
+        if new_reasons.has_flag(UsnReasonValue::USN_REASON_FILE_DELETE) {
+            self.last_content_change.remove(&file_reference);
+            self.marked.remove(&file_reference);
+        }
+        if let Some(index) = self.file_index.get(&file_reference) {
+            let file = &mut self.files[*index];
+            file.parent_reference = *data.parent_reference();
+            file.name = data.filename().to_owned();
+        }
+    }
+}
+
+impl AdsAnalyzer {
+    pub fn new(settings: AdsSettings) -> Self {
+        Self {
+            settings,
+            open_files: OpenFiles::default(),
+            resolver: PathResolver::default(),
+            open_streams: HashMap::new(),
+            last_content_change: HashMap::new(),
+            marked: HashSet::new(),
+            files: Vec::new(),
+            file_index: HashMap::new(),
+        }
+    }
+
+    pub fn finish(mut self) -> AdsReport {
+        /* files which have not been closed before the end of the journal */
+        let mut open_streams: Vec<_> = std::mem::take(&mut self.open_streams).into_values().collect();
+        open_streams.sort_by_key(|s| s.first.usn);
+        for stream in open_streams {
+            self.close_stream(stream);
+        }
+
+        let resolver = &self.resolver;
+        let executable_extensions = &self.settings.executable_extensions;
+        for file in self.files.iter_mut() {
+            file.path = resolver.path_of(&file.parent_reference, &file.name);
+            file.is_executable = !file.is_directory && extension_of(&file.name)
+                .is_some_and(|e| executable_extensions.iter().any(|x| x.eq_ignore_ascii_case(&e)));
+        }
+        AdsReport { files: self.files }
+    }
+
+    fn close_stream(&mut self, stream: OpenStream) {
+        let file_reference = stream.first.file_reference;
+        let stream_change = stream.reasons & UsnReasonValue::USN_REASON_STREAM_CHANGE as u32 != 0;
+        let named_data = stream.reasons & NAMED_DATA_CHANGES != 0;
+        let kind = match (stream_change, named_data) {
+            (true, true) => {
+                let after_write = self.last_content_change.get(&file_reference)
+                    .is_some_and(|t| stream.first.timestamp - *t <= self.settings.mark_of_the_web_delay);
+                if after_write && !stream.is_directory {
+                    StreamEventKind::MarkOfTheWeb
+                } else {
+                    StreamEventKind::Added
+                }
+            }
+            (true, false) if self.marked.contains(&file_reference) => StreamEventKind::MarkOfTheWebRemoved,
+            (true, false) => StreamEventKind::Removed,
+            (false, _) => StreamEventKind::Written,
+        };
+        match kind {
+            StreamEventKind::MarkOfTheWeb => { self.marked.insert(file_reference); }
+            StreamEventKind::MarkOfTheWebRemoved => { self.marked.remove(&file_reference); }
+            _ => (),
+        }
+
+        let files = &mut self.files;
+        let index = *self.file_index.entry(file_reference).or_insert_with(|| {
+            files.push(StreamActivity {
+                file_reference,
+                parent_reference: stream.first.parent_reference,
+                name: stream.first.name.clone(),
+                path: None,
+                is_directory: stream.is_directory,
+                is_executable: false,
+                events: Vec::new(),
+            });
+            files.len() - 1
+        });
+        self.files[index].events.push(StreamEvent {
+            kind,
+            reasons: UsnReason::from(stream.reasons),
+            record: stream.first,
+        });
+    }
+}
@@ -0,0 +1,299 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+use chrono::{DateTime, Duration, Utc};
+use winstructs::ntfs::mft_reference::MftReference;
+
+use crate::open_files::OpenFiles;
+use crate::rename::RenameTracker;
+use crate::{CommonUsnRecord, UsnJournalMax, UsnReasonValue};
+#[cfg(feature = "formatters")]
+use crate::serialize::timestamp_to_string;
+use super::{Analyzer, RecordRef, EXECUTABLE_EXTENSIONS, extension_of, window_start};
+
+
+/// reasons, which show that a file has been created or its content has been changed
+const CONTENT_CHANGES: u32 = UsnReasonValue::USN_REASON_FILE_CREATE as u32
+    | UsnReasonValue::USN_REASON_DATA_OVERWRITE as u32
+    | UsnReasonValue::USN_REASON_DATA_EXTEND as u32
+    | UsnReasonValue::USN_REASON_DATA_TRUNCATION as u32;
+
+pub struct AntiForensicsSettings {
+    /// maximum time between writing an executable and changing its timestamps
+    pub timestomp_delay: Duration,
+
+    /// length of the time windows, in which overwritten and deleted files are counted
+    pub wipe_window: Duration,
+
+    /// minimum number of files, which must be overwritten and deleted in one window
+    pub min_wiped_files: usize,
+
+    /// journals which contain less bytes of records are considered to be small
+    pub small_journal: u64,
+
+    /// journals which have been created less than this before their last record are considered to be recent
+    pub recent_journal: Duration,
+}
+
+impl Default for AntiForensicsSettings {
+    fn default() -> Self {
+        Self {
+            timestomp_delay: Duration::seconds(10),
+            wipe_window: Duration::seconds(60),
+            min_wiped_files: 10,
+            small_journal: 0x100000,
+            recent_journal: Duration::days(1),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AntiForensicsKind {
+    /// the timestamps of an executable have been changed right after it had been written
+    Timestomping,
+
+    /// a file has been renamed to names like `AAAA.AAA` before it was deleted, like `sdelete` does
+    SecureDeletion,
+
+    /// many files have been overwritten and deleted afterwards
+    MassWipe,
+
+    /// the journal has been deleted and created again
+    JournalCleared,
+}
+
+impl fmt::Display for AntiForensicsKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Timestomping => write!(f, "timestomping"),
+            Self::SecureDeletion => write!(f, "secure deletion"),
+            Self::MassWipe => write!(f, "mass wipe"),
+            Self::JournalCleared => write!(f, "journal cleared"),
+        }
+    }
+}
+
+/// something which looks like an attempt to remove traces. The USN of every
+/// supporting record is its offset in `$UsnJrnl:$J`.
+#[derive(Clone, Debug)]
+pub struct AntiForensicsFinding {
+    pub kind: AntiForensicsKind,
+    pub timestamp: DateTime<Utc>,
+    pub description: String,
+    pub records: Vec<RecordRef>,
+}
+
+impl AntiForensicsFinding {
+    #[cfg(feature = "formatters")]
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "kind": self.kind.to_string(),
+            "timestamp": timestamp_to_string(&self.timestamp),
+            "description": self.description,
+            "offsets": self.records.iter().map(|r| r.usn).collect::<Vec<_>>(),
+            "records": self.records.iter().map(RecordRef::to_json).collect::<Vec<_>>(),
+        })
+    }
+}
+
+/// a file which has been deleted after it had been overwritten
+struct WipedFile {
+    overwritten: RecordRef,
+    deleted: RecordRef,
+}
+
+/// looks for timestomping, secure deletion, wipers and a cleared journal.
+///
+/// If `$UsnJrnl:$Max` is available, it should be passed with
+/// [`AntiForensicsAnalyzer::with_journal_max`]: its journal id tells when the
+/// journal has been created. Without it, a journal is considered to be
+/// cleared if it is small and has never discarded any record.
+pub struct AntiForensicsAnalyzer {
+    settings: AntiForensicsSettings,
+    journal_max: Option<UsnJournalMax>,
+    open_files: OpenFiles,
+    renames: RenameTracker,
+    findings: Vec<AntiForensicsFinding>,
+
+    /// the latest record, which created or wrote an executable
+    written_executables: HashMap<MftReference, RecordRef>,
+
+    /// the first record, which overwrote a file
+    overwritten: HashMap<MftReference, RecordRef>,
+
+    /// renames to names like `AAAA.AAA`, together with the name before the first of them
+    wiper_renames: HashMap<MftReference, (String, Vec<RecordRef>)>,
+    wipe_window: Option<(DateTime<Utc>, Vec<WipedFile>)>,
+
+    first_record: Option<RecordRef>,
+    last_record: Option<RecordRef>,
+}
+
+impl Analyzer for AntiForensicsAnalyzer {
+    fn add(&mut self, record: &CommonUsnRecord) {
+        let data = &record.data;
+        let file_reference = *data.file_reference();
+        let new_reasons = self.open_files.new_reasons(record);
+        let record_ref = RecordRef::from(record);
+        if self.first_record.is_none() {
+            self.first_record = Some(record_ref.clone());
+        }
+        self.last_record = Some(record_ref.clone());
+
+        let is_executable = extension_of(data.filename())
+            .is_some_and(|e| EXECUTABLE_EXTENSIONS.contains(&e.as_str()));
+        if new_reasons.has_flag(UsnReasonValue::USN_REASON_BASIC_INFO_CHANGE) && is_executable {
+            if let Some(written) = self.written_executables.get(&file_reference) {
+                if record_ref.timestamp - written.timestamp <= self.settings.timestomp_delay {
+                    self.findings.push(AntiForensicsFinding {
+                        kind: AntiForensicsKind::Timestomping,
+                        timestamp: record_ref.timestamp,
+                        description: format!("the timestamps of '{}' have been changed {} seconds after it had been written",
+                            data.filename(), (record_ref.timestamp - written.timestamp).num_seconds()),
+                        records: vec![written.clone(), record_ref.clone()],
+                    });
+                }
+            }
+        }
+        if data.reason().value() & CONTENT_CHANGES != 0 && is_executable {
+            self.written_executables.insert(file_reference, record_ref.clone());
+        }
+
+        if data.reason().has_flag(UsnReasonValue::USN_REASON_DATA_OVERWRITE) {
+            self.overwritten.entry(file_reference).or_insert_with(|| record_ref.clone());
+        }
+        if let Some(rename) = self.renames.add(record) {
+            if is_wiper_name(&rename.new_name) {
+                let original_name = rename.old_name.unwrap_or(rename.new_name);
+                self.wiper_renames.entry(file_reference)
+                    .or_insert_with(|| (original_name, Vec::new()))
+                    .1.push(record_ref.clone());
+            }
+        }
+
+        if new_reasons.has_flag(UsnReasonValue::USN_REASON_FILE_DELETE) {
+            self.written_executables.remove(&file_reference);
+            let overwritten = self.overwritten.remove(&file_reference);
+            if let Some((original_name, renames)) = self.wiper_renames.remove(&file_reference) {
+                let mut records: Vec<_> = overwritten.into_iter().collect();
+                let description = format!("'{}' has been renamed {} times to names like '{}' before it was deleted",
+                    original_name, renames.len(), renames[0].name);
+                records.extend(renames);
+                records.push(record_ref);
+                self.findings.push(AntiForensicsFinding {
+                    kind: AntiForensicsKind::SecureDeletion,
+                    timestamp: *data.timestamp(),
+                    description,
+                    records,
+                });
+            } else if let Some(overwritten) = overwritten {
+                self.add_wiped_file(WipedFile { overwritten, deleted: record_ref });
+            }
+        }
+    }
+}
+
+impl AntiForensicsAnalyzer {
+    pub fn new(settings: AntiForensicsSettings) -> Self {
+        Self {
+            settings,
+            journal_max: None,
+            open_files: OpenFiles::default(),
+            renames: RenameTracker::default(),
+            findings: Vec::new(),
+            written_executables: HashMap::new(),
+            overwritten: HashMap::new(),
+            wiper_renames: HashMap::new(),
+            wipe_window: None,
+            first_record: None,
+            last_record: None,
+        }
+    }
+
+    /// uses the content of `$UsnJrnl:$Max` to tell if the journal has been cleared
+    pub fn with_journal_max(mut self, journal_max: UsnJournalMax) -> Self {
+        self.journal_max = Some(journal_max);
+        self
+    }
+
+    /// returns all findings, ordered by the USN of their first record
+    pub fn finish(mut self) -> Vec<AntiForensicsFinding> {
+        self.evaluate_wipe_window();
+        if let Some(finding) = self.journal_cleared() {
+            self.findings.push(finding);
+        }
+        self.findings.sort_by_key(|f| f.records.first().map(|r| r.usn));
+        self.findings
+    }
+
+    fn add_wiped_file(&mut self, file: WipedFile) {
+        let start = window_start(&file.deleted.timestamp, self.settings.wipe_window);
+        if self.wipe_window.as_ref().is_some_and(|(s, _)| *s < start) {
+            self.evaluate_wipe_window();
+        }
+        self.wipe_window.get_or_insert_with(|| (start, Vec::new())).1.push(file);
+    }
+
+    fn evaluate_wipe_window(&mut self) {
+        if let Some((start, files)) = self.wipe_window.take() {
+            if files.len() >= self.settings.min_wiped_files {
+                let description = format!("{} files have been overwritten and deleted within {} seconds",
+                    files.len(), self.settings.wipe_window.num_seconds());
+                let mut records: Vec<_> = files.into_iter().flat_map(|f| vec![f.overwritten, f.deleted]).collect();
+                records.sort_by_key(|r| r.usn);
+                self.findings.push(AntiForensicsFinding {
+                    kind: AntiForensicsKind::MassWipe,
+                    timestamp: start,
+                    description,
+                    records,
+                });
+            }
+        }
+    }
+
+    fn journal_cleared(&self) -> Option<AntiForensicsFinding> {
+        let first = self.first_record.as_ref()?;
+        let last = self.last_record.as_ref()?;
+        let size = u64::try_from(last.usn).unwrap_or(0);
+        let (lowest_valid_usn, created) = match &self.journal_max {
+            Some(journal_max) => (journal_max.lowest_valid_usn, journal_max.created()),
+            None => (first.usn, None),
+        };
+        if size >= self.settings.small_journal || lowest_valid_usn >= self.settings.small_journal as i64 {
+            return None;
+        }
+
+        let kib = size / 1024 + 1;
+        let description = match created {
+            Some(created) if last.timestamp - created < self.settings.recent_journal => format!(
+                "the journal has been created at {} and contains only {} KiB of records",
+                created.format("%Y-%m-%d %H:%M:%S UTC"), kib),
+            Some(_) => return None,
+            None => format!("the journal starts at USN {} and contains only {} KiB of records", first.usn, kib),
+        };
+        Some(AntiForensicsFinding {
+            kind: AntiForensicsKind::JournalCleared,
+            timestamp: created.unwrap_or(first.timestamp),
+            description,
+            records: vec![first.clone()],
+        })
+    }
+}
+
+/// returns `true` for names like `AAAAAAAA.AAA`, which consist of a single repeated character
+fn is_wiper_name(name: &str) -> bool {
+    let mut chars = name.chars().filter(|c| *c != '.');
+    match chars.next() {
+        Some(first) if first.is_ascii_alphanumeric() => {
+            let mut count = 1;
+            for c in chars {
+                if c != first {
+                    return false;
+                }
+                count += 1;
+            }
+            count >= 3
+        }
+        _ => false,
+    }
+}
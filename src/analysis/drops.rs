@@ -0,0 +1,243 @@
+use std::fmt;
+use winstructs::ntfs::mft_reference::MftReference;
+
+use crate::open_files::OpenFiles;
+use crate::path_resolver::join_path;
+use crate::rename::RenameTracker;
+use crate::{CommonUsnRecord, PathResolver, UsnReasonValue};
+#[cfg(feature = "formatters")]
+use crate::serialize::timestamp_to_string;
+use super::{Analyzer, RecordRef, EXECUTABLE_EXTENSIONS, extension_of};
+
+/// extensions and locations, which are watched for dropped executables and scripts.
+///
+/// A location is a sequence of directory names like `AppData/Local/Temp`,
+/// which may appear anywhere in the path of a directory. Names are compared
+/// case-insensitively, and both `/` and `\` may be used as separator.
+///
+/// Watchlists can be read from TOML or YAML files. Missing fields keep their
+/// default values:
+///
+/// ```toml
+/// extensions = ["exe", "dll", "ps1"]
+/// locations = ["Temp", "Downloads", "Users/Public"]
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "watchlists", derive(serde::Deserialize))]
+#[cfg_attr(feature = "watchlists", serde(default, deny_unknown_fields))]
+pub struct DropWatchlist {
+    pub extensions: Vec<String>,
+    pub locations: Vec<String>,
+}
+
+impl Default for DropWatchlist {
+    fn default() -> Self {
+        let to_strings = |values: &[&str]| values.iter().map(|v| v.to_string()).collect();
+        Self {
+            extensions: to_strings(EXECUTABLE_EXTENSIONS),
+            locations: to_strings(&["Temp", "Downloads", "AppData", "ProgramData", "Users/Public"]),
+        }
+    }
+}
+
+impl DropWatchlist {
+    #[cfg(feature = "watchlists")]
+    pub fn from_toml(text: &str) -> Result<Self, String> {
+        toml::from_str(text).map_err(|why| format!("invalid watchlist: {}", why))
+    }
+
+    #[cfg(feature = "watchlists")]
+    pub fn from_yaml(text: &str) -> Result<Self, String> {
+        serde_yaml::from_str(text).map_err(|why| format!("invalid watchlist: {}", why))
+    }
+
+    /// returns `true` if files named `name` are watched
+    pub fn is_watched_name(&self, name: &str) -> bool {
+        extension_of(name).is_some_and(|extension| {
+            self.extensions.iter().any(|e| e.trim_start_matches('.').eq_ignore_ascii_case(&extension))
+        })
+    }
+
+    /// returns the first location, which is part of the directory path `directory`
+    pub fn location_of(&self, directory: &str) -> Option<&str> {
+        let directory = components(directory);
+        self.locations.iter()
+            .find(|location| {
+                let location = components(location);
+                !location.is_empty() && directory.windows(location.len()).any(|w| w == location.as_slice())
+            })
+            .map(String::as_str)
+    }
+}
+
+/// splits `path` into lowercase directory names
+fn components(path: &str) -> Vec<String> {
+    path.split(['/', '\\'].as_ref())
+        .filter(|c| !c.is_empty() && *c != "?")
+        .map(str::to_lowercase)
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DropKind {
+    /// a watched file has been created in a watched location
+    Created,
+
+    /// a file in a watched location has been renamed to a watched name
+    Renamed,
+
+    /// a watched file has been moved into a watched location
+    Moved,
+}
+
+impl fmt::Display for DropKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Created => write!(f, "created"),
+            Self::Renamed => write!(f, "renamed"),
+            Self::Moved => write!(f, "moved"),
+        }
+    }
+}
+
+/// an executable or a script, which has been dropped into a watched location
+#[derive(Clone, Debug)]
+pub struct DropAlert {
+    pub kind: DropKind,
+
+    /// the location of the watchlist, which contains the file
+    pub location: String,
+
+    /// the path of the directory, which contains the file. It starts with `?`
+    /// if it cannot be resolved up to the root directory.
+    pub directory: String,
+
+    /// the name of the file before it has been renamed or moved
+    pub previous_name: Option<String>,
+
+    /// the record, which created the file or contains its new name
+    pub record: RecordRef,
+}
+
+impl DropAlert {
+    /// returns the path of the dropped file
+    pub fn path(&self) -> String {
+        join_path(&self.directory, &self.record.name)
+    }
+
+    #[cfg(feature = "formatters")]
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "kind": self.kind.to_string(),
+            "timestamp": timestamp_to_string(&self.record.timestamp),
+            "path": self.path(),
+            "location": self.location,
+            "extension": extension_of(&self.record.name),
+            "previous_name": self.previous_name,
+            "usn": self.record.usn,
+            "record": self.record.to_json(),
+        })
+    }
+}
+
+/// a watched file, whose location is checked as soon as all paths are known
+struct Candidate {
+    record: RecordRef,
+    previous_name: Option<String>,
+    previous_parent: Option<MftReference>,
+    renamed: bool,
+}
+
+/// looks for executables and scripts, which have been created in or moved into
+/// user-writable locations like `Temp` or `Downloads`.
+///
+/// Locations are checked after all records have been added, so that the paths
+/// of the directories can be resolved with everything the journal knows. If a
+/// path cannot be resolved up to the root directory, the known part of it is
+/// compared with the watchlist.
+pub struct DropAnalyzer {
+    watchlist: DropWatchlist,
+    open_files: OpenFiles,
+    renames: RenameTracker,
+    resolver: PathResolver,
+    candidates: Vec<Candidate>,
+}
+
+impl Analyzer for DropAnalyzer {
+    fn add(&mut self, record: &CommonUsnRecord) {
+        self.resolver.learn(record);
+        let new_reasons = self.open_files.new_reasons(record);
+        let rename = self.renames.add(record);
+        if !self.watchlist.is_watched_name(record.data.filename()) {
+            return;
+        }
+
+        if new_reasons.has_flag(UsnReasonValue::USN_REASON_FILE_CREATE) {
+            self.candidates.push(Candidate {
+                record: RecordRef::from(record),
+                previous_name: None,
+                previous_parent: None,
+                renamed: false,
+            });
+        } else if let Some(rename) = rename {
+            self.candidates.push(Candidate {
+                record: RecordRef::from(record),
+                previous_name: rename.old_name,
+                previous_parent: rename.old_parent,
+                renamed: true,
+            });
+        }
+    }
+}
+
+impl DropAnalyzer {
+    pub fn new(watchlist: DropWatchlist) -> Self {
+        Self {
+            watchlist,
+            open_files: OpenFiles::default(),
+            renames: RenameTracker::default(),
+            resolver: PathResolver::default(),
+            candidates: Vec::new(),
+        }
+    }
+
+    /// returns all alerts, ordered by USN
+    pub fn finish(self) -> Vec<DropAlert> {
+        let watchlist = &self.watchlist;
+        let resolver = &self.resolver;
+        let mut alerts = Vec::new();
+        for candidate in self.candidates {
+            let directory = resolver.partial_directory_path(&candidate.record.parent_reference);
+            let location = match watchlist.location_of(&directory) {
+                Some(location) => location.to_owned(),
+                None => continue,
+            };
+
+            let kind = if !candidate.renamed {
+                DropKind::Created
+            } else {
+                let was_watched_name = candidate.previous_name.as_deref()
+                    .is_some_and(|name| watchlist.is_watched_name(name));
+                let was_watched_location = match candidate.previous_parent {
+                    Some(parent) if parent != candidate.record.parent_reference =>
+                        watchlist.location_of(&resolver.partial_directory_path(&parent)).is_some(),
+                    _ => true,
+                };
+                match (was_watched_name, was_watched_location) {
+                    (true, true) => continue,
+                    (true, false) => DropKind::Moved,
+                    (false, _) => DropKind::Renamed,
+                }
+            };
+
+            alerts.push(DropAlert {
+                kind,
+                location,
+                directory,
+                previous_name: candidate.previous_name,
+                record: candidate.record,
+            });
+        }
+        alerts
+    }
+}
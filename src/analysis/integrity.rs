@@ -0,0 +1,252 @@
+use std::fmt;
+use chrono::Duration;
+
+use crate::{CommonUsnRecord, UsnReaderError, PAGE_SIZE};
+use super::{Analyzer, RecordRef};
+
+pub struct IntegritySettings {
+    /// timestamps may go back by this amount without being reported
+    pub max_time_regression: Duration,
+
+    /// periods without any record, which are longer than this, are reported
+    pub max_time_gap: Duration,
+}
+
+impl Default for IntegritySettings {
+    fn default() -> Self {
+        Self {
+            max_time_regression: Duration::seconds(1),
+            max_time_gap: Duration::days(1),
+        }
+    }
+}
+
+/// something which should not happen in an untouched journal
+#[derive(Clone, Debug)]
+pub enum IntegrityAnomaly {
+    /// the timestamp of a record is older than the timestamp of its predecessor,
+    /// which hints at a clock change or at manipulation
+    TimestampRegression {
+        previous: RecordRef,
+        record: RecordRef,
+    },
+
+    /// there is no record for a long time
+    TimeGap {
+        previous: RecordRef,
+        record: RecordRef,
+    },
+
+    /// the USN of a record is neither the end of its predecessor nor the beginning of the next page
+    UsnGap {
+        previous: RecordRef,
+        record: RecordRef,
+        expected_usn: i64,
+    },
+
+    /// the USN of a record is not higher than the USN of its predecessor
+    UsnRegression {
+        previous: RecordRef,
+        record: RecordRef,
+    },
+
+    /// the USN of a record does not match its offset in the stream
+    OffsetMismatch {
+        record: RecordRef,
+        offset: u64,
+        expected_usn: i64,
+    },
+}
+
+impl IntegrityAnomaly {
+    /// returns the record, which is affected by this anomaly
+    pub fn record(&self) -> &RecordRef {
+        match self {
+            Self::TimestampRegression { record, .. } |
+            Self::TimeGap { record, .. } |
+            Self::UsnGap { record, .. } |
+            Self::UsnRegression { record, .. } |
+            Self::OffsetMismatch { record, .. } => record,
+        }
+    }
+
+    /// returns a short name of the kind of this anomaly
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::TimestampRegression { .. } => "timestamp_regression",
+            Self::TimeGap { .. } => "time_gap",
+            Self::UsnGap { .. } => "usn_gap",
+            Self::UsnRegression { .. } => "usn_regression",
+            Self::OffsetMismatch { .. } => "offset_mismatch",
+        }
+    }
+
+    #[cfg(feature = "formatters")]
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut json = serde_json::json!({
+            "kind": self.kind(),
+            "description": self.to_string(),
+            "record": self.record().to_json(),
+        });
+        match self {
+            Self::TimestampRegression { previous, record } |
+            Self::TimeGap { previous, record } => {
+                json["previous"] = previous.to_json();
+                json["seconds"] = (record.timestamp - previous.timestamp).num_seconds().into();
+            }
+            Self::UsnGap { previous, expected_usn, .. } => {
+                json["previous"] = previous.to_json();
+                json["expected_usn"] = (*expected_usn).into();
+            }
+            Self::UsnRegression { previous, .. } => {
+                json["previous"] = previous.to_json();
+            }
+            Self::OffsetMismatch { offset, expected_usn, .. } => {
+                json["offset"] = (*offset).into();
+                json["expected_usn"] = (*expected_usn).into();
+            }
+        }
+        json
+    }
+}
+
+impl fmt::Display for IntegrityAnomaly {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::TimestampRegression { previous, record } => write!(f,
+                "the timestamp of USN {} is {} seconds older than the timestamp of USN {}",
+                record.usn, (previous.timestamp - record.timestamp).num_seconds(), previous.usn),
+            Self::TimeGap { previous, record } => write!(f,
+                "there is no record for {} seconds between USN {} and USN {}",
+                (record.timestamp - previous.timestamp).num_seconds(), previous.usn, record.usn),
+            Self::UsnGap { previous, record, expected_usn } => write!(f,
+                "USN {} follows USN {}, but USN {} was expected",
+                record.usn, previous.usn, expected_usn),
+            Self::UsnRegression { previous, record } => write!(f,
+                "USN {} follows the higher USN {}",
+                record.usn, previous.usn),
+            Self::OffsetMismatch { record, offset, expected_usn } => write!(f,
+                "the record at offset {} has USN {} instead of USN {}",
+                offset, record.usn, expected_usn),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct IntegrityReport {
+    pub records: usize,
+    pub unreadable_records: usize,
+
+    /// the difference between the USN and the offset of every record. This is
+    /// zero for a complete `$UsnJrnl:$J`, and can be any value for excerpts.
+    pub usn_offset: Option<i64>,
+    pub first_usn: Option<i64>,
+    pub last_usn: Option<i64>,
+    pub anomalies: Vec<IntegrityAnomaly>,
+}
+
+impl IntegrityReport {
+    #[cfg(feature = "formatters")]
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "records": self.records,
+            "unreadable_records": self.unreadable_records,
+            "usn_offset": self.usn_offset,
+            "first_usn": self.first_usn,
+            "last_usn": self.last_usn,
+            "anomalies": self.anomalies.iter().map(IntegrityAnomaly::to_json).collect::<Vec<_>>(),
+        })
+    }
+}
+
+/// checks that USNs strictly increase and match the offsets of the records,
+/// and that timestamps do not go back.
+///
+/// USNs are the offsets of the records in `$UsnJrnl:$J`. Because records
+/// never cross a page boundary, the USN of a record is either the end of its
+/// predecessor, or the beginning of the next page.
+pub struct IntegrityValidator {
+    settings: IntegritySettings,
+    previous: Option<(RecordRef, u32)>,
+    report: IntegrityReport,
+}
+
+impl Analyzer for IntegrityValidator {
+    fn add(&mut self, record: &CommonUsnRecord) {
+        let record_ref = RecordRef::from(record);
+        let offset = record.header.starting_position();
+        let usn_offset = *self.report.usn_offset.get_or_insert(record_ref.usn - offset as i64);
+        self.report.records += 1;
+        self.report.first_usn.get_or_insert(record_ref.usn);
+        self.report.last_usn = Some(record_ref.usn);
+
+        let expected_usn = offset as i64 + usn_offset;
+        if record_ref.usn != expected_usn {
+            self.report.anomalies.push(IntegrityAnomaly::OffsetMismatch {
+                record: record_ref.clone(),
+                offset,
+                expected_usn,
+            });
+        }
+
+        if let Some((previous, previous_length)) = self.previous.take() {
+            self.check_usn(&previous, previous_length, &record_ref);
+            self.check_timestamp(&previous, &record_ref);
+        }
+        self.previous = Some((record_ref, record.header.RecordLength));
+    }
+}
+
+impl IntegrityValidator {
+    pub fn new(settings: IntegritySettings) -> Self {
+        Self {
+            settings,
+            previous: None,
+            report: IntegrityReport::default(),
+        }
+    }
+
+    /// counts a record which could not be read
+    pub fn add_error(&mut self, _error: &UsnReaderError) {
+        self.report.unreadable_records += 1;
+    }
+
+    pub fn finish(self) -> IntegrityReport {
+        self.report
+    }
+
+    fn check_usn(&mut self, previous: &RecordRef, previous_length: u32, record: &RecordRef) {
+        if record.usn <= previous.usn {
+            self.report.anomalies.push(IntegrityAnomaly::UsnRegression {
+                previous: previous.clone(),
+                record: record.clone(),
+            });
+            return;
+        }
+
+        let expected_usn = previous.usn + previous_length as i64;
+        let next_page = (expected_usn + PAGE_SIZE as i64 - 1) & !(PAGE_SIZE as i64 - 1);
+        if record.usn != expected_usn && record.usn != next_page {
+            self.report.anomalies.push(IntegrityAnomaly::UsnGap {
+                previous: previous.clone(),
+                record: record.clone(),
+                expected_usn,
+            });
+        }
+    }
+
+    fn check_timestamp(&mut self, previous: &RecordRef, record: &RecordRef) {
+        let elapsed = record.timestamp - previous.timestamp;
+        if -elapsed > self.settings.max_time_regression {
+            self.report.anomalies.push(IntegrityAnomaly::TimestampRegression {
+                previous: previous.clone(),
+                record: record.clone(),
+            });
+        } else if elapsed > self.settings.max_time_gap {
+            self.report.anomalies.push(IntegrityAnomaly::TimeGap {
+                previous: previous.clone(),
+                record: record.clone(),
+            });
+        }
+    }
+}
@@ -0,0 +1,107 @@
+//! analyzers, which look for suspicious patterns in a journal.
+//!
+//! Every analyzer implements [`Analyzer`] and is fed with all records of a
+//! journal, in the order of their USN. Afterwards, it creates a report:
+//!
+//! ```rust,no_run
+//! use std::path::PathBuf;
+//! use usnjrnl::UsnJrnlReader;
+//! use usnjrnl::analysis::{Analyzer, RansomwareAnalyzer, RansomwareSettings};
+//!
+//! let mut analyzer = RansomwareAnalyzer::new(RansomwareSettings::default());
+//! for record in UsnJrnlReader::from(&PathBuf::from("$UsnJrnl:$J")).unwrap().into_iter().flatten() {
+//!     analyzer.add(&record);
+//! }
+//! let report = analyzer.finish();
+//! if let Some(start) = report.suspected_start {
+//!     println!("ransomware activity started at {}", start);
+//! }
+//! ```
+
+use chrono::{DateTime, Duration, Utc};
+use winstructs::ntfs::mft_reference::MftReference;
+
+use crate::CommonUsnRecord;
+#[cfg(feature = "formatters")]
+use crate::serialize::{reference_to_string, timestamp_to_string};
+
+mod ads;
+pub use ads::{AdsAnalyzer, AdsReport, AdsSettings, StreamActivity, StreamEvent, StreamEventKind};
+
+mod antiforensics;
+pub use antiforensics::{AntiForensicsAnalyzer, AntiForensicsFinding, AntiForensicsKind, AntiForensicsSettings};
+
+mod drops;
+pub use drops::{DropAlert, DropAnalyzer, DropKind, DropWatchlist};
+
+mod integrity;
+pub use integrity::{IntegrityAnomaly, IntegrityReport, IntegritySettings, IntegrityValidator};
+
+mod ransomware;
+pub use ransomware::{AffectedDirectory, RansomwareAnalyzer, RansomwareIndicator, RansomwareReport, RansomwareSettings, ScoredWindow};
+
+/// returns the start of the time window of length `size`, which contains
+/// `timestamp`. Windows are aligned to the Unix epoch and at least one second long.
+pub fn window_start(timestamp: &DateTime<Utc>, size: Duration) -> DateTime<Utc> {
+    let seconds = timestamp.timestamp();
+    DateTime::from_timestamp(seconds - seconds.rem_euclid(size.num_seconds().max(1)), 0)
+        .unwrap_or(DateTime::<Utc>::MIN_UTC)
+}
+
+pub trait Analyzer {
+    /// adds `record`. Records must be added in the order of their USN.
+    fn add(&mut self, record: &CommonUsnRecord);
+}
+
+/// the essential fields of a record, which supports a finding.
+///
+/// The USN of a record is also its offset in the `$UsnJrnl:$J` stream.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecordRef {
+    pub usn: i64,
+    pub timestamp: DateTime<Utc>,
+    pub file_reference: MftReference,
+    pub parent_reference: MftReference,
+    pub name: String,
+}
+
+impl From<&CommonUsnRecord> for RecordRef {
+    fn from(record: &CommonUsnRecord) -> Self {
+        let data = &record.data;
+        Self {
+            usn: data.usn(),
+            timestamp: *data.timestamp(),
+            file_reference: *data.file_reference(),
+            parent_reference: *data.parent_reference(),
+            name: data.filename().to_owned(),
+        }
+    }
+}
+
+impl RecordRef {
+    #[cfg(feature = "formatters")]
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "usn": self.usn,
+            "timestamp": timestamp_to_string(&self.timestamp),
+            "file_reference": reference_to_string(&self.file_reference),
+            "parent_reference": reference_to_string(&self.parent_reference),
+            "name": self.name,
+        })
+    }
+}
+
+/// extensions of executables and scripts, which all analyzers and the report
+/// consider to be executable content
+pub const EXECUTABLE_EXTENSIONS: &[&str] = &[
+    "exe", "dll", "sys", "scr", "com", "cpl", "ocx", "drv", "msi",
+    "ps1", "bat", "cmd", "vbs", "js", "hta", "jar", "lnk",
+];
+
+/// returns the extension of `name` in lowercase, without the leading dot
+pub fn extension_of(name: &str) -> Option<String> {
+    match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() && !extension.is_empty() => Some(extension.to_lowercase()),
+        _ => None,
+    }
+}
@@ -0,0 +1,420 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use chrono::{DateTime, Duration, Utc};
+use winstructs::ntfs::mft_reference::MftReference;
+
+use crate::filter::ntfs_upcase;
+use crate::rename::{Rename, RenameTracker};
+use crate::{CommonUsnRecord, PathResolver, UsnReasonValue};
+#[cfg(feature = "formatters")]
+use crate::serialize::{reference_to_string, timestamp_to_string};
+use super::{Analyzer, RecordRef, extension_of, window_start};
+
+/// extensions of files, which are typically used as ransom notes
+const NOTE_EXTENSIONS: &[&str] = &["txt", "html", "htm", "hta", "url", "rtf"];
+
+/// reasons, which show that the content of the unnamed data stream has been changed
+const DATA_CHANGES: u32 = UsnReasonValue::USN_REASON_DATA_OVERWRITE as u32
+    | UsnReasonValue::USN_REASON_DATA_EXTEND as u32
+    | UsnReasonValue::USN_REASON_DATA_TRUNCATION as u32;
+
+pub struct RansomwareSettings {
+    /// length of the time windows, which are scored independently
+    pub window: Duration,
+
+    /// minimum number of files, which must show the same pattern in one window
+    pub min_files: usize,
+
+    /// minimum number of directories, which must be affected in one window
+    pub min_directories: usize,
+
+    /// minimum score of a suspicious window, between 0 and 100
+    pub threshold: u32,
+}
+
+impl Default for RansomwareSettings {
+    fn default() -> Self {
+        Self {
+            window: Duration::minutes(5),
+            min_files: 20,
+            min_directories: 3,
+            threshold: 50,
+        }
+    }
+}
+
+/// a heuristic, which matched in a time window
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RansomwareIndicator {
+    /// the content of many existing files has been changed
+    MassWrites { files: usize },
+
+    /// many files got the same new extension, by renaming or by replacing them
+    CommonExtension { extension: String, files: usize },
+
+    /// many files have been deleted after a copy with an additional extension had been created
+    ReplacedFiles { files: usize },
+
+    /// the affected files are spread over many directories
+    ManyDirectories { directories: usize },
+
+    /// files with the same name have been created in many directories
+    RansomNotes { names: Vec<String> },
+}
+
+impl RansomwareIndicator {
+    /// returns how much this indicator contributes to the score of a window
+    pub fn weight(&self) -> u32 {
+        match self {
+            Self::MassWrites { .. } => 20,
+            Self::CommonExtension { .. } => 30,
+            Self::ReplacedFiles { .. } => 30,
+            Self::ManyDirectories { .. } => 10,
+            Self::RansomNotes { .. } => 20,
+        }
+    }
+}
+
+impl fmt::Display for RansomwareIndicator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::MassWrites { files } => write!(f, "{} existing files have been written", files),
+            Self::CommonExtension { extension, files } => write!(f, "{} files got the extension '.{}'", files, extension),
+            Self::ReplacedFiles { files } => write!(f, "{} files have been replaced by a copy with an additional extension", files),
+            Self::ManyDirectories { directories } => write!(f, "{} directories are affected", directories),
+            Self::RansomNotes { names } => write!(f, "possible ransom notes: {}", names.join(", ")),
+        }
+    }
+}
+
+/// a time window, whose score reached the threshold
+#[derive(Clone, Debug)]
+pub struct ScoredWindow {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub score: u32,
+    pub indicators: Vec<RansomwareIndicator>,
+
+    /// the record of the affected file with the lowest USN
+    pub first_affected: Option<RecordRef>,
+}
+
+#[derive(Clone, Debug)]
+pub struct AffectedDirectory {
+    pub reference: MftReference,
+    pub path: Option<String>,
+    pub files: usize,
+}
+
+/// the result of a [`RansomwareAnalyzer`]. All fields except `windows` summarize the suspicious windows.
+#[derive(Clone, Debug, Default)]
+pub struct RansomwareReport {
+    pub windows: Vec<ScoredWindow>,
+    pub suspected_start: Option<DateTime<Utc>>,
+    pub first_affected: Option<RecordRef>,
+
+    /// extensions, which have been appended to or replaced the extension of affected files
+    pub new_extensions: Vec<(String, usize)>,
+
+    /// the original extensions of the affected files
+    pub affected_extensions: Vec<(String, usize)>,
+    pub directories: Vec<AffectedDirectory>,
+    pub notes: Vec<String>,
+}
+
+impl RansomwareReport {
+    pub fn is_suspicious(&self) -> bool {
+        !self.windows.is_empty()
+    }
+
+    #[cfg(feature = "formatters")]
+    pub fn to_json(&self) -> serde_json::Value {
+        let counts = |counts: &[(String, usize)]| counts.iter()
+            .map(|(extension, files)| serde_json::json!({"extension": extension, "files": files}))
+            .collect::<Vec<_>>();
+        serde_json::json!({
+            "suspicious": self.is_suspicious(),
+            "suspected_start": self.suspected_start.as_ref().map(timestamp_to_string),
+            "first_affected": self.first_affected.as_ref().map(RecordRef::to_json),
+            "new_extensions": counts(&self.new_extensions),
+            "affected_extensions": counts(&self.affected_extensions),
+            "directories": self.directories.iter().map(|d| serde_json::json!({
+                "reference": reference_to_string(&d.reference),
+                "path": d.path,
+                "files": d.files,
+            })).collect::<Vec<_>>(),
+            "notes": self.notes,
+            "windows": self.windows.iter().map(|w| serde_json::json!({
+                "start": timestamp_to_string(&w.start),
+                "end": timestamp_to_string(&w.end),
+                "score": w.score,
+                "indicators": w.indicators.iter().map(|i| i.to_string()).collect::<Vec<_>>(),
+                "first_affected": w.first_affected.as_ref().map(RecordRef::to_json),
+            })).collect::<Vec<_>>(),
+        })
+    }
+}
+
+/// a file, which has been encrypted, together with its name before the encryption
+struct AffectedFile {
+    record: RecordRef,
+    original_name: String,
+}
+
+/// everything of a time window, which is needed to score it
+struct Window {
+    start: DateTime<Utc>,
+    written: HashMap<MftReference, RecordRef>,
+    created: HashMap<MftReference, RecordRef>,
+    deleted: HashMap<MftReference, RecordRef>,
+    renamed: Vec<Rename>,
+}
+
+impl Window {
+    fn new(start: DateTime<Utc>) -> Self {
+        Self {
+            start,
+            written: HashMap::new(),
+            created: HashMap::new(),
+            deleted: HashMap::new(),
+            renamed: Vec::new(),
+        }
+    }
+}
+
+/// scores time windows on the patterns which ransomware leaves in the journal.
+///
+/// The journal does not tell which process changed a file, so all
+/// heuristics only use the names, directories and reasons of the records:
+/// many existing files being written, many files getting the same new
+/// extension, files being replaced by a copy with an additional extension,
+/// and files with the same name being created in many directories.
+pub struct RansomwareAnalyzer {
+    settings: RansomwareSettings,
+    renames: RenameTracker,
+    resolver: PathResolver,
+    window: Option<Window>,
+    report: RansomwareReport,
+    new_extensions: HashMap<String, usize>,
+    affected_extensions: HashMap<String, usize>,
+    directories: HashMap<MftReference, usize>,
+    notes: BTreeMap<String, String>,
+}
+
+impl Analyzer for RansomwareAnalyzer {
+    fn add(&mut self, record: &CommonUsnRecord) {
+        let data = &record.data;
+        self.resolver.learn(record);
+
+        let start = window_start(data.timestamp(), self.settings.window);
+        match &self.window {
+            Some(window) if window.start < start => {
+                let window = self.window.replace(Window::new(start)).unwrap();
+                self.evaluate(window);
+            }
+            Some(_) => (),
+            None => self.window = Some(Window::new(start)),
+        }
+        let window = self.window.as_mut().unwrap();
+
+        let file_reference = *data.file_reference();
+        let reason = data.reason();
+        if reason.value() & DATA_CHANGES != 0 {
+            window.written.entry(file_reference).or_insert_with(|| record.into());
+        }
+        if reason.has_flag(UsnReasonValue::USN_REASON_FILE_CREATE) {
+            window.created.entry(file_reference).or_insert_with(|| record.into());
+        }
+        if reason.has_flag(UsnReasonValue::USN_REASON_FILE_DELETE) {
+            window.deleted.entry(file_reference).or_insert_with(|| record.into());
+        }
+        if let Some(rename) = self.renames.add(record) {
+            window.renamed.push(rename);
+        }
+    }
+}
+
+impl RansomwareAnalyzer {
+    pub fn new(settings: RansomwareSettings) -> Self {
+        Self {
+            settings,
+            renames: RenameTracker::default(),
+            resolver: PathResolver::default(),
+            window: None,
+            report: RansomwareReport::default(),
+            new_extensions: HashMap::new(),
+            affected_extensions: HashMap::new(),
+            directories: HashMap::new(),
+            notes: BTreeMap::new(),
+        }
+    }
+
+    /// scores the last window and returns the report
+    pub fn finish(mut self) -> RansomwareReport {
+        if let Some(window) = self.window.take() {
+            self.evaluate(window);
+        }
+
+        let mut report = self.report;
+        report.first_affected = report.windows.iter().find_map(|w| w.first_affected.clone());
+        report.suspected_start = report.windows.first()
+            .map(|w| w.first_affected.as_ref().map_or(w.start, |r| r.timestamp));
+        report.new_extensions = sorted_counts(self.new_extensions);
+        report.affected_extensions = sorted_counts(self.affected_extensions);
+        let resolver = &self.resolver;
+        let mut directories: Vec<_> = self.directories.into_iter()
+            .map(|(reference, files)| AffectedDirectory {
+                reference,
+                path: resolver.directory_path(&reference),
+                files,
+            })
+            .collect();
+        directories.sort_by(|a, b| b.files.cmp(&a.files).then_with(|| a.reference.entry.cmp(&b.reference.entry)));
+        report.directories = directories;
+        report.notes = self.notes.into_values().collect();
+        report
+    }
+
+    fn evaluate(&mut self, window: Window) {
+        let min_files = self.settings.min_files;
+        let mut indicators = Vec::new();
+        let mut affected: HashMap<MftReference, AffectedFile> = HashMap::new();
+        let mut new_extensions: HashMap<String, HashSet<MftReference>> = HashMap::new();
+
+        /* files which have been renamed to another extension */
+        let mut renamed_extensions: HashMap<String, Vec<&Rename>> = HashMap::new();
+        for rename in &window.renamed {
+            if let Some(extension) = extension_of(&rename.new_name) {
+                if rename.old_name.as_deref().and_then(extension_of).as_ref() != Some(&extension) {
+                    renamed_extensions.entry(extension).or_default().push(rename);
+                }
+            }
+        }
+        for (extension, renames) in renamed_extensions {
+            if renames.len() < min_files {
+                continue;
+            }
+            for rename in renames {
+                new_extensions.entry(extension.clone()).or_default().insert(rename.file_reference);
+                affected.entry(rename.file_reference).or_insert_with(|| AffectedFile {
+                    record: RecordRef {
+                        usn: rename.usn,
+                        timestamp: rename.timestamp,
+                        file_reference: rename.file_reference,
+                        parent_reference: rename.old_parent.unwrap_or(rename.new_parent),
+                        name: rename.new_name.clone(),
+                    },
+                    original_name: rename.old_name.clone().unwrap_or_else(|| rename.new_name.clone()),
+                });
+            }
+        }
+
+        /* files which have been replaced by a new file with an additional extension */
+        let deleted: HashMap<_, _> = window.deleted.values()
+            .map(|r| ((r.parent_reference, ntfs_upcase(&r.name)), r))
+            .collect();
+        let replaced: Vec<_> = window.created.values()
+            .filter_map(|created| {
+                let (stem, extension) = created.name.rsplit_once('.')?;
+                let original = deleted.get(&(created.parent_reference, ntfs_upcase(stem)))?;
+                Some((created, *original, extension.to_lowercase()))
+            })
+            .collect();
+        if replaced.len() >= min_files {
+            indicators.push(RansomwareIndicator::ReplacedFiles { files: replaced.len() });
+            for (created, original, extension) in replaced {
+                new_extensions.entry(extension).or_default().insert(original.file_reference);
+                let record = if created.usn < original.usn { created } else { original };
+                affected.entry(original.file_reference).or_insert_with(|| AffectedFile {
+                    record: record.clone(),
+                    original_name: original.name.clone(),
+                });
+            }
+        }
+
+        if let Some((extension, files)) = new_extensions.iter().max_by(|a, b| a.1.len().cmp(&b.1.len()).then_with(|| b.0.cmp(a.0))) {
+            if files.len() >= min_files {
+                indicators.insert(0, RansomwareIndicator::CommonExtension { extension: extension.clone(), files: files.len() });
+            }
+        }
+
+        /* files which have been written, but not created in this window */
+        let overwritten: Vec<_> = window.written.values()
+            .filter(|r| !window.created.contains_key(&r.file_reference))
+            .collect();
+        if overwritten.len() >= min_files {
+            indicators.insert(0, RansomwareIndicator::MassWrites { files: overwritten.len() });
+            for record in overwritten {
+                affected.entry(record.file_reference).or_insert_with(|| AffectedFile {
+                    record: record.clone(),
+                    original_name: record.name.clone(),
+                });
+            }
+        }
+
+        /* the encryption of a file may have started before it was renamed */
+        for (file_reference, file) in affected.iter_mut() {
+            if let Some(written) = window.written.get(file_reference) {
+                if written.usn < file.record.usn {
+                    file.record = written.clone();
+                }
+            }
+        }
+
+        /* files with the same name, which have been created in many directories */
+        let mut note_candidates: HashMap<String, (&str, HashSet<MftReference>)> = HashMap::new();
+        for created in window.created.values() {
+            if extension_of(&created.name).is_some_and(|e| NOTE_EXTENSIONS.contains(&e.as_str())) {
+                note_candidates.entry(ntfs_upcase(&created.name))
+                    .or_insert_with(|| (&created.name, HashSet::new()))
+                    .1.insert(created.parent_reference);
+            }
+        }
+        let mut notes: Vec<_> = note_candidates.into_iter()
+            .filter(|(_, (_, directories))| directories.len() >= self.settings.min_directories)
+            .map(|(key, (name, _))| (key, name.to_owned()))
+            .collect();
+        notes.sort();
+
+        let directories: HashSet<_> = affected.values().map(|a| a.record.parent_reference).collect();
+        if !affected.is_empty() && directories.len() >= self.settings.min_directories {
+            indicators.push(RansomwareIndicator::ManyDirectories { directories: directories.len() });
+        }
+        if !notes.is_empty() {
+            indicators.push(RansomwareIndicator::RansomNotes { names: notes.iter().map(|(_, name)| name.clone()).collect() });
+        }
+
+        let score = indicators.iter().map(|i| i.weight()).sum::<u32>().min(100);
+        if score == 0 || score < self.settings.threshold {
+            return;
+        }
+
+        for (extension, files) in new_extensions {
+            *self.new_extensions.entry(extension).or_insert(0) += files.len();
+        }
+        for file in affected.values() {
+            if let Some(extension) = extension_of(&file.original_name) {
+                if !self.new_extensions.contains_key(&extension) {
+                    *self.affected_extensions.entry(extension).or_insert(0) += 1;
+                }
+            }
+            *self.directories.entry(file.record.parent_reference).or_insert(0) += 1;
+        }
+        self.notes.extend(notes);
+
+        self.report.windows.push(ScoredWindow {
+            start: window.start,
+            end: window.start + self.settings.window,
+            score,
+            indicators,
+            first_affected: affected.into_values().map(|a| a.record).min_by_key(|r| r.usn),
+        });
+    }
+}
+
+/// returns the counts in descending order, and keys with the same count in alphabetical order
+fn sorted_counts(counts: HashMap<String, usize>) -> Vec<(String, usize)> {
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts
+}
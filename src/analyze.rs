@@ -0,0 +1,406 @@
+use anyhow::{anyhow, Result};
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use usnjrnl::{UsnJournalMax, UsnJrnlReader};
+use usnjrnl::analysis::*;
+use usnjrnl::rules::{Rule, RuleAlerts, RuleEngine};
+use usnjrnl::serialize::{reference_to_string, timestamp_to_string};
+
+use crate::{journal_file_arg, parse_seconds};
+
+/// creates the `analyze` subcommand, which has a subcommand for every analyzer
+pub fn analyze_subcommand<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("analyze")
+        .about("looks for suspicious patterns in the journal")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(
+            SubCommand::with_name("ads")
+                .about("reports activity in alternate data streams, like the mark of the web being added or removed")
+                .arg(journal_file_arg())
+                .arg(json_arg())
+                .arg(
+                    Arg::with_name("UNUSUAL")
+                        .long("unusual")
+                        .help("show only files with stream activity, which is not benign")
+                ).arg(
+                    Arg::with_name("MOTW_DELAY")
+                        .long("motw-delay")
+                        .help("maximum number of seconds between writing a file and adding its Zone.Identifier stream")
+                        .takes_value(true)
+                        .default_value("60")
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("anti-forensics")
+                .about("looks for timestomping of executables, secure deletion, wipers and a cleared journal")
+                .arg(journal_file_arg())
+                .arg(json_arg())
+                .arg(
+                    Arg::with_name("MAX_FILE")
+                        .long("max")
+                        .help("path to the $UsnJrnl:$Max file, which tells when the journal has been created")
+                        .takes_value(true)
+                ).arg(
+                    Arg::with_name("TIMESTOMP_DELAY")
+                        .long("timestomp-delay")
+                        .help("maximum number of seconds between writing an executable and changing its timestamps")
+                        .takes_value(true)
+                        .default_value("10")
+                ).arg(
+                    Arg::with_name("MIN_WIPED_FILES")
+                        .long("min-wiped-files")
+                        .help("minimum number of files, which must be overwritten and deleted within a minute")
+                        .takes_value(true)
+                        .default_value("10")
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("drops")
+                .about("looks for executables and scripts, which have been dropped into user-writable locations")
+                .arg(journal_file_arg())
+                .arg(json_arg())
+                .arg(
+                    Arg::with_name("WATCHLIST")
+                        .long("watchlist")
+                        .help("TOML or YAML file with the watched extensions and locations")
+                        .takes_value(true)
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("integrity")
+                .about("checks that USNs strictly increase and match the offsets of the records, and that timestamps do not go back")
+                .arg(journal_file_arg())
+                .arg(json_arg())
+                .arg(
+                    Arg::with_name("MAX_TIME_REGRESSION")
+                        .long("max-time-regression")
+                        .help("number of seconds, by which timestamps may go back without being reported")
+                        .takes_value(true)
+                        .default_value("1")
+                ).arg(
+                    Arg::with_name("MAX_TIME_GAP")
+                        .long("max-time-gap")
+                        .help("periods without any record, which are longer than this number of seconds, are reported")
+                        .takes_value(true)
+                        .default_value("86400")
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("rules")
+                .about("evaluates detection rules from YAML files and prints every alert as soon as it is raised")
+                .arg(journal_file_arg())
+                .arg(
+                    Arg::with_name("JSON")
+                        .long("json")
+                        .help("print every alert as a single line of JSON instead of plain text")
+                ).arg(
+                    Arg::with_name("RULES")
+                        .long("rules")
+                        .help("YAML file with detection rules; can be given multiple times")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .required(true)
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("ransomware")
+                .about("scores time windows on patterns of ransomware: mass writes, common new extensions, replaced files and ransom notes")
+                .arg(journal_file_arg())
+                .arg(json_arg())
+                .arg(
+                    Arg::with_name("WINDOW")
+                        .long("window")
+                        .help("length of the time windows in seconds")
+                        .takes_value(true)
+                        .default_value("300")
+                ).arg(
+                    Arg::with_name("MIN_FILES")
+                        .long("min-files")
+                        .help("minimum number of files, which must show the same pattern in one window")
+                        .takes_value(true)
+                        .default_value("20")
+                ).arg(
+                    Arg::with_name("MIN_DIRECTORIES")
+                        .long("min-directories")
+                        .help("minimum number of directories, which must be affected in one window")
+                        .takes_value(true)
+                        .default_value("3")
+                ).arg(
+                    Arg::with_name("THRESHOLD")
+                        .long("threshold")
+                        .help("minimum score of a suspicious window, between 0 and 100")
+                        .takes_value(true)
+                        .default_value("50")
+                )
+        )
+}
+
+pub fn analyze(matches: &ArgMatches) -> Result<()> {
+    match matches.subcommand() {
+        ("ads", Some(matches)) => analyze_ads(matches),
+        ("anti-forensics", Some(matches)) => analyze_anti_forensics(matches),
+        ("drops", Some(matches)) => analyze_drops(matches),
+        ("integrity", Some(matches)) => analyze_integrity(matches),
+        ("ransomware", Some(matches)) => analyze_ransomware(matches),
+        ("rules", Some(matches)) => analyze_rules(matches),
+        _ => unreachable!("clap requires a subcommand"),
+    }
+}
+
+fn json_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("JSON")
+        .long("json")
+        .help("print the result as JSON instead of plain text")
+}
+
+/// feeds all records of the journal in `matches` into `analyzer`
+fn run_analyzer(matches: &ArgMatches, analyzer: &mut dyn Analyzer) -> Result<()> {
+    let filename = PathBuf::from(matches.value_of("USNJRNL_FILE").unwrap());
+    for entry in UsnJrnlReader::from(&filename)? {
+        match entry {
+            Ok(record) => analyzer.add(&record),
+            Err(why) => log::error!("{}", why),
+        }
+    }
+    Ok(())
+}
+
+fn analyze_ads(matches: &ArgMatches) -> Result<()> {
+    let settings = AdsSettings {
+        mark_of_the_web_delay: parse_seconds(matches.value_of("MOTW_DELAY").unwrap())?,
+        ..Default::default()
+    };
+    let mut analyzer = AdsAnalyzer::new(settings);
+    run_analyzer(matches, &mut analyzer)?;
+    let mut report = analyzer.finish();
+    if matches.is_present("UNUSUAL") {
+        report.files.retain(StreamActivity::is_unusual);
+    }
+
+    let mut output = BufWriter::new(std::io::stdout());
+    if matches.is_present("JSON") {
+        writeln!(output, "{}", serde_json::to_string_pretty(&report.to_json())?)?;
+    } else if report.files.is_empty() {
+        writeln!(output, "no activity in alternate data streams found")?;
+    } else {
+        let describe = |file: &StreamActivity| format!("{} ({})",
+            file.path.as_deref().unwrap_or(&file.name), reference_to_string(&file.file_reference));
+        let stripped: Vec<_> = report.stripped_executables().collect();
+        if !stripped.is_empty() {
+            writeln!(output, "executables which lost their mark of the web:")?;
+            for file in stripped {
+                writeln!(output, "  {}", describe(file))?;
+            }
+            writeln!(output)?;
+        }
+        for file in &report.files {
+            writeln!(output, "{}", describe(file))?;
+            for event in &file.events {
+                writeln!(output, "    {}  {} (USN {})", timestamp_to_string(&event.record.timestamp), event.kind, event.record.usn)?;
+            }
+        }
+    }
+    output.flush()?;
+    Ok(())
+}
+
+fn analyze_anti_forensics(matches: &ArgMatches) -> Result<()> {
+    let settings = AntiForensicsSettings {
+        timestomp_delay: parse_seconds(matches.value_of("TIMESTOMP_DELAY").unwrap())?,
+        min_wiped_files: matches.value_of("MIN_WIPED_FILES").unwrap().parse()?,
+        ..Default::default()
+    };
+    let mut analyzer = AntiForensicsAnalyzer::new(settings);
+    if let Some(max_file) = matches.value_of("MAX_FILE") {
+        analyzer = analyzer.with_journal_max(UsnJournalMax::from_path(&PathBuf::from(max_file))?);
+    }
+    run_analyzer(matches, &mut analyzer)?;
+    let findings = analyzer.finish();
+
+    let mut output = BufWriter::new(std::io::stdout());
+    if matches.is_present("JSON") {
+        let findings: Vec<_> = findings.iter().map(AntiForensicsFinding::to_json).collect();
+        writeln!(output, "{}", serde_json::to_string_pretty(&findings)?)?;
+    } else if findings.is_empty() {
+        writeln!(output, "no signs of anti-forensics found")?;
+    } else {
+        for finding in &findings {
+            writeln!(output, "{}  {}: {}", timestamp_to_string(&finding.timestamp), finding.kind, finding.description)?;
+            let offsets: Vec<_> = finding.records.iter().map(|r| r.usn.to_string()).collect();
+            writeln!(output, "    records at offsets {}", offsets.join(", "))?;
+        }
+    }
+    output.flush()?;
+    Ok(())
+}
+
+fn analyze_drops(matches: &ArgMatches) -> Result<()> {
+    let watchlist = match matches.value_of("WATCHLIST") {
+        None => DropWatchlist::default(),
+        Some(watchlist_file) => {
+            let text = std::fs::read_to_string(watchlist_file)?;
+            let watchlist = if watchlist_file.to_lowercase().ends_with(".toml") {
+                DropWatchlist::from_toml(&text)
+            } else {
+                DropWatchlist::from_yaml(&text)
+            };
+            watchlist.map_err(|why| anyhow!("{}: {}", watchlist_file, why))?
+        }
+    };
+    let mut analyzer = DropAnalyzer::new(watchlist);
+    run_analyzer(matches, &mut analyzer)?;
+    let alerts = analyzer.finish();
+
+    let mut output = BufWriter::new(std::io::stdout());
+    if matches.is_present("JSON") {
+        let alerts: Vec<_> = alerts.iter().map(DropAlert::to_json).collect();
+        writeln!(output, "{}", serde_json::to_string_pretty(&alerts)?)?;
+    } else if alerts.is_empty() {
+        writeln!(output, "no dropped executables found")?;
+    } else {
+        for alert in &alerts {
+            write!(output, "{}  {}: {} (USN {}", timestamp_to_string(&alert.record.timestamp), alert.kind, alert.path(), alert.record.usn)?;
+            if let Some(previous_name) = &alert.previous_name {
+                write!(output, ", previously '{}'", previous_name)?;
+            }
+            writeln!(output, ")")?;
+        }
+    }
+    output.flush()?;
+    Ok(())
+}
+
+fn analyze_integrity(matches: &ArgMatches) -> Result<()> {
+    let settings = IntegritySettings {
+        max_time_regression: parse_seconds(matches.value_of("MAX_TIME_REGRESSION").unwrap())?,
+        max_time_gap: parse_seconds(matches.value_of("MAX_TIME_GAP").unwrap())?,
+    };
+    let mut validator = IntegrityValidator::new(settings);
+    let filename = PathBuf::from(matches.value_of("USNJRNL_FILE").unwrap());
+    for entry in UsnJrnlReader::from(&filename)? {
+        match entry {
+            Ok(record) => validator.add(&record),
+            Err(why) => {
+                log::error!("{}", why);
+                validator.add_error(&why);
+            }
+        }
+    }
+    let report = validator.finish();
+
+    let mut output = BufWriter::new(std::io::stdout());
+    if matches.is_present("JSON") {
+        writeln!(output, "{}", serde_json::to_string_pretty(&report.to_json())?)?;
+    } else {
+        let usn = |u: Option<i64>| u.map_or("-".to_owned(), |u| u.to_string());
+        writeln!(output, "records:            {}", report.records)?;
+        writeln!(output, "unreadable records: {}", report.unreadable_records)?;
+        writeln!(output, "first USN:          {}", usn(report.first_usn))?;
+        writeln!(output, "last USN:           {}", usn(report.last_usn))?;
+        writeln!(output, "USN - offset:       {}", usn(report.usn_offset))?;
+        writeln!(output, "anomalies:          {}", report.anomalies.len())?;
+        for anomaly in &report.anomalies {
+            writeln!(output, "  {}  {}", timestamp_to_string(&anomaly.record().timestamp), anomaly)?;
+        }
+    }
+    output.flush()?;
+    Ok(())
+}
+
+fn analyze_ransomware(matches: &ArgMatches) -> Result<()> {
+    let settings = RansomwareSettings {
+        window: parse_seconds(matches.value_of("WINDOW").unwrap())?,
+        min_files: matches.value_of("MIN_FILES").unwrap().parse()?,
+        min_directories: matches.value_of("MIN_DIRECTORIES").unwrap().parse()?,
+        threshold: matches.value_of("THRESHOLD").unwrap().parse()?,
+    };
+    let mut analyzer = RansomwareAnalyzer::new(settings);
+    run_analyzer(matches, &mut analyzer)?;
+    let report = analyzer.finish();
+
+    let mut output = BufWriter::new(std::io::stdout());
+    if matches.is_present("JSON") {
+        writeln!(output, "{}", serde_json::to_string_pretty(&report.to_json())?)?;
+    } else {
+        write_ransomware_report(&mut output, &report)?;
+    }
+    output.flush()?;
+    Ok(())
+}
+
+fn analyze_rules(matches: &ArgMatches) -> Result<()> {
+    let mut rules = Vec::new();
+    for rules_file in matches.values_of("RULES").unwrap() {
+        let text = std::fs::read_to_string(rules_file)?;
+        rules.extend(Rule::from_yaml(&text).map_err(|why| anyhow!("{}: {}", rules_file, why))?);
+    }
+    Rule::check_unique_ids(&rules).map_err(|why| anyhow!(why))?;
+
+    let filename = PathBuf::from(matches.value_of("USNJRNL_FILE").unwrap());
+    let reader = UsnJrnlReader::from(&filename)?;
+    let mut output = BufWriter::new(std::io::stdout());
+    for alert in RuleAlerts::new(RuleEngine::new(rules), reader.into_iter()) {
+        let alert = match alert {
+            Ok(alert) => alert,
+            Err(why) => {
+                log::error!("{}", why);
+                continue;
+            }
+        };
+        if matches.is_present("JSON") {
+            writeln!(output, "{}", alert.to_json())?;
+        } else {
+            let usns: Vec<_> = alert.records.iter().map(|r| r.usn.to_string()).collect();
+            writeln!(output, "{}  [{}] {} (USN {})", timestamp_to_string(&alert.timestamp), alert.rule_id,
+                alert.title.as_deref().unwrap_or_else(|| &alert.records.last().unwrap().name), usns.join(", "))?;
+        }
+    }
+    output.flush()?;
+    Ok(())
+}
+
+fn write_ransomware_report<W: Write>(out: &mut W, report: &RansomwareReport) -> std::io::Result<()> {
+    if !report.is_suspicious() {
+        return writeln!(out, "no signs of ransomware found");
+    }
+
+    writeln!(out, "suspicious windows: {}", report.windows.len())?;
+    if let Some(start) = &report.suspected_start {
+        writeln!(out, "suspected start:    {}", timestamp_to_string(start))?;
+    }
+    if let Some(record) = &report.first_affected {
+        writeln!(out, "first affected:     {} (USN {}, MFT entry {})",
+            record.name, record.usn, reference_to_string(&record.file_reference))?;
+    }
+
+    writeln!(out, "\nwindows:")?;
+    for window in &report.windows {
+        writeln!(out, "  {} - {}, score {}", timestamp_to_string(&window.start), timestamp_to_string(&window.end), window.score)?;
+        for indicator in &window.indicators {
+            writeln!(out, "    {}", indicator)?;
+        }
+    }
+
+    writeln!(out, "\nnew extensions:")?;
+    for (extension, files) in &report.new_extensions {
+        writeln!(out, "  {:>10}  {}", files, extension)?;
+    }
+    writeln!(out, "\naffected extensions:")?;
+    for (extension, files) in &report.affected_extensions {
+        writeln!(out, "  {:>10}  {}", files, extension)?;
+    }
+    writeln!(out, "\naffected directories:")?;
+    for directory in &report.directories {
+        let path = directory.path.as_deref().unwrap_or("(unknown)");
+        writeln!(out, "  {:>10}  {} ({})", directory.files, path, reference_to_string(&directory.reference))?;
+    }
+    if !report.notes.is_empty() {
+        writeln!(out, "\npossible ransom notes:")?;
+        for note in &report.notes {
+            writeln!(out, "  {}", note)?;
+        }
+    }
+    Ok(())
+}
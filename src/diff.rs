@@ -0,0 +1,141 @@
+use anyhow::Result;
+use clap::{App, Arg, ArgMatches, SubCommand};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use usnjrnl::{CommonUsnRecord, JournalDiff, JournalSpan, UsnJournalMax, UsnJrnlReader};
+use usnjrnl::analysis::RecordRef;
+use usnjrnl::serialize::{reference_to_string, timestamp_to_string};
+
+/// creates the `diff` subcommand, which compares two snapshots of the journal of the same volume
+pub fn diff_subcommand<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("diff")
+        .about("compares two snapshots of the journal of the same volume and shows what happened in between")
+        .arg(
+            Arg::with_name("USNJRNL_FILE_A")
+                .help("path to the older $UsnJrnl:$J file")
+                .required(true)
+                .takes_value(true)
+        ).arg(
+            Arg::with_name("USNJRNL_FILE_B")
+                .help("path to the newer $UsnJrnl:$J file")
+                .required(true)
+                .takes_value(true)
+        ).arg(
+            Arg::with_name("MAX_A")
+                .long("max-a")
+                .help("path to the $UsnJrnl:$Max file of the older snapshot, to detect a journal which has been created again")
+                .takes_value(true)
+        ).arg(
+            Arg::with_name("MAX_B")
+                .long("max-b")
+                .help("path to the $UsnJrnl:$Max file of the newer snapshot")
+                .takes_value(true)
+        ).arg(
+            Arg::with_name("JSON")
+                .long("json")
+                .help("print the result as JSON instead of plain text")
+        )
+}
+
+pub fn diff(matches: &ArgMatches) -> Result<()> {
+    let filename_b = PathBuf::from(matches.value_of("USNJRNL_FILE_B").unwrap());
+    let max_a = matches.value_of("MAX_A").map(|m| UsnJournalMax::from_path(&PathBuf::from(m))).transpose()?;
+    let max_b = matches.value_of("MAX_B").map(|m| UsnJournalMax::from_path(&PathBuf::from(m))).transpose()?;
+    let diff = JournalDiff::compare_journals(
+        read_records(&PathBuf::from(matches.value_of("USNJRNL_FILE_A").unwrap()))?, max_a.as_ref(),
+        read_records(&filename_b)?, max_b.as_ref());
+
+    let mut output = BufWriter::new(std::io::stdout());
+    if matches.is_present("JSON") {
+        writeln!(output, "{}", serde_json::to_string_pretty(&diff.to_json())?)?;
+    } else {
+        write_diff(&mut output, &diff, &filename_b)?;
+    }
+    output.flush()?;
+    Ok(())
+}
+
+/// returns all records of a journal, logging the records which cannot be read
+fn read_records(filename: &PathBuf) -> Result<impl Iterator<Item=CommonUsnRecord>> {
+    Ok(UsnJrnlReader::from(filename)?.into_iter().filter_map(|entry| match entry {
+        Ok(record) => Some(record),
+        Err(why) => {
+            log::error!("{}", why);
+            None
+        }
+    }))
+}
+
+fn describe_span(span: &JournalSpan) -> String {
+    match (&span.first, &span.last) {
+        (Some(first), Some(last)) => format!("{} records, USN {} - {}, {} - {}",
+            span.records, first.usn, last.usn, timestamp_to_string(&first.timestamp), timestamp_to_string(&last.timestamp)),
+        _ => "no records".to_owned(),
+    }
+}
+
+fn describe_record(record: &RecordRef) -> String {
+    format!("{}  {} ({}, USN {})", timestamp_to_string(&record.timestamp), record.name,
+        reference_to_string(&record.file_reference), record.usn)
+}
+
+fn write_diff<W: Write>(out: &mut W, diff: &JournalDiff, filename_b: &Path) -> std::io::Result<()> {
+    writeln!(out, "journal A:          {}", describe_span(&diff.a))?;
+    writeln!(out, "journal B:          {}", describe_span(&diff.b))?;
+    if let (Some(a), Some(b)) = (diff.journal_id_a, diff.journal_id_b) {
+        writeln!(out, "journal ids:        0x{:016x} / 0x{:016x}", a, b)?;
+    }
+    writeln!(out)?;
+
+    if diff.journal_recreated() {
+        writeln!(out, "the journal has been deleted and created again between both snapshots, so all records of B are new")?;
+    } else if diff.has_gap() {
+        writeln!(out, "B starts after the end of A, so records between both snapshots have been lost")?;
+    } else if !diff.overwritten.is_empty() {
+        writeln!(out, "B starts within A, the oldest {} records of A have been overwritten", diff.overwritten.records)?;
+    }
+    writeln!(out, "overwritten in B:   {}", describe_span(&diff.overwritten))?;
+    writeln!(out, "common records:     {}", diff.common_records)?;
+    writeln!(out, "new records in B:   {}", describe_span(&diff.new_records))?;
+    if let Some(first) = &diff.new_records.first {
+        writeln!(out, "                    (show them with '--start-usn {} {}')", first.usn, filename_b.display())?;
+    }
+
+    if !diff.is_consistent() {
+        writeln!(out)?;
+        writeln!(out, "the common records of both journals do not match:")?;
+        for (a, b) in &diff.differing_records {
+            writeln!(out, "  differs:      {}", describe_record(a))?;
+            writeln!(out, "                {}", describe_record(b))?;
+        }
+        for record in &diff.missing_in_a {
+            writeln!(out, "  missing in A: {}", describe_record(record))?;
+        }
+        for record in &diff.missing_in_b {
+            writeln!(out, "  missing in B: {}", describe_record(record))?;
+        }
+        if !diff.newer_in_a.is_empty() {
+            writeln!(out, "  A has newer records than B: {}", describe_span(&diff.newer_in_a))?;
+        }
+    }
+
+    for (title, records) in [("created files", &diff.created), ("deleted files", &diff.deleted)] {
+        if !records.is_empty() {
+            writeln!(out)?;
+            writeln!(out, "{}:", title)?;
+            for record in records {
+                writeln!(out, "  {}", describe_record(record))?;
+            }
+        }
+    }
+    if !diff.renamed.is_empty() {
+        writeln!(out)?;
+        writeln!(out, "renamed files:")?;
+        for rename in &diff.renamed {
+            writeln!(out, "  {}  {} -> {} ({}, USN {})", timestamp_to_string(&rename.timestamp),
+                rename.old_name.as_deref().unwrap_or("?"), rename.new_name,
+                reference_to_string(&rename.file_reference), rename.usn)?;
+        }
+    }
+    Ok(())
+}
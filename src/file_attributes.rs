@@ -0,0 +1,148 @@
+use std::str;
+use std::fmt;
+use strum::IntoEnumIterator;
+use strum_macros::EnumIter;
+
+/// file attributes of a file or directory, as returned by the `GetFileAttributes` function
+///
+/// <https://docs.microsoft.com/en-us/windows/win32/fileio/file-attribute-constants>
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct FileAttributes {
+    value: u32,
+}
+
+impl From<u32> for FileAttributes {
+    fn from(value: u32) -> Self {
+        Self {
+            value
+        }
+    }
+}
+
+impl FileAttributes {
+    pub fn has_flag(&self, flag: FileAttributeValue) -> bool {
+        (self.value & flag as u32) != 0
+    }
+
+    /// returns all flags which are set, in the order of their declaration
+    pub fn flags(&self) -> impl Iterator<Item=FileAttributeValue> + '_ {
+        FileAttributeValue::iter().filter(move |x| self.has_flag(*x))
+    }
+
+    pub fn value(&self) -> u32 {
+        self.value
+    }
+}
+
+impl fmt::Debug for FileAttributes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl fmt::Display for FileAttributes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let longflags = f.sign_plus();
+        let flags:Vec<String> = self.flags()
+            .map(|x| if ! longflags {x.short_name()} else {x.to_string()})
+            .collect();
+        write!(f, "{}", flags.join("+"))
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[derive(EnumIter, strum_macros::Display, Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u32)]
+pub enum FileAttributeValue {
+    /// A file that is read-only.
+    FILE_ATTRIBUTE_READONLY = 0x00000001,
+
+    /// The file or directory is hidden. It is not included in an ordinary
+    /// directory listing.
+    FILE_ATTRIBUTE_HIDDEN = 0x00000002,
+
+    /// A file or directory that the operating system uses a part of, or uses
+    /// exclusively.
+    FILE_ATTRIBUTE_SYSTEM = 0x00000004,
+
+    /// The handle that identifies a directory.
+    FILE_ATTRIBUTE_DIRECTORY = 0x00000010,
+
+    /// A file or directory that is an archive file or directory. Applications
+    /// typically use this attribute to mark files for backup or removal.
+    FILE_ATTRIBUTE_ARCHIVE = 0x00000020,
+
+    /// This value is reserved for system use.
+    FILE_ATTRIBUTE_DEVICE = 0x00000040,
+
+    /// A file that does not have other attributes set.
+    FILE_ATTRIBUTE_NORMAL = 0x00000080,
+
+    /// A file that is being used for temporary storage.
+    FILE_ATTRIBUTE_TEMPORARY = 0x00000100,
+
+    /// A file that is a sparse file.
+    FILE_ATTRIBUTE_SPARSE_FILE = 0x00000200,
+
+    /// A file or directory that has an associated reparse point, or a file that
+    /// is a symbolic link.
+    FILE_ATTRIBUTE_REPARSE_POINT = 0x00000400,
+
+    /// A file or directory that is compressed.
+    FILE_ATTRIBUTE_COMPRESSED = 0x00000800,
+
+    /// The data of a file is not available immediately, because it has been
+    /// physically moved to offline storage.
+    FILE_ATTRIBUTE_OFFLINE = 0x00001000,
+
+    /// The file or directory is not to be indexed by the content indexing
+    /// service.
+    FILE_ATTRIBUTE_NOT_CONTENT_INDEXED = 0x00002000,
+
+    /// A file or directory that is encrypted.
+    FILE_ATTRIBUTE_ENCRYPTED = 0x00004000,
+
+    /// The directory or user data stream is configured with integrity (only
+    /// supported on ReFS volumes).
+    FILE_ATTRIBUTE_INTEGRITY_STREAM = 0x00008000,
+
+    /// This value is reserved for system use.
+    FILE_ATTRIBUTE_VIRTUAL = 0x00010000,
+
+    /// The user data stream not to be read by the background data integrity
+    /// scanner (AKA scrubber).
+    FILE_ATTRIBUTE_NO_SCRUB_DATA = 0x00020000,
+
+    /// The file or directory has no physical representation on the local
+    /// system; the item is virtual.
+    FILE_ATTRIBUTE_RECALL_ON_OPEN = 0x00040000,
+
+    /// The file or directory should be kept fully present locally even when not
+    /// being actively accessed.
+    FILE_ATTRIBUTE_PINNED = 0x00080000,
+
+    /// The file or directory should not be kept fully present locally except
+    /// when being actively accessed.
+    FILE_ATTRIBUTE_UNPINNED = 0x00100000,
+
+    /// The file or directory is not fully present locally.
+    FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS = 0x00400000,
+}
+
+impl FileAttributeValue {
+    /// returns the name of the flag without the `FILE_ATTRIBUTE_` prefix
+    pub fn short_name(&self) -> String {
+        str::from_utf8(&self.to_string().into_bytes()[15..]).unwrap().to_owned()
+    }
+}
+
+impl str::FromStr for FileAttributeValue {
+    type Err = String;
+
+    /// parses the name of a flag, with or without the `FILE_ATTRIBUTE_` prefix and ignoring case
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Self::iter()
+            .find(|x| x.short_name().eq_ignore_ascii_case(name) || x.to_string().eq_ignore_ascii_case(name))
+            .ok_or_else(|| format!("invalid file attribute: '{}'", name))
+    }
+}
@@ -0,0 +1,83 @@
+use crate::{CommonUsnRecord, FileAttributeValue, UsnReasonValue};
+
+use super::RecordFilter;
+
+/// specifies if any or all of a set of flags must be set
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MatchMode {
+    Any,
+    All,
+}
+
+impl MatchMode {
+    fn matches<T: Copy>(&self, flags: &[T], has_flag: impl Fn(T) -> bool) -> bool {
+        match self {
+            Self::Any => flags.iter().any(|f| has_flag(*f)),
+            Self::All => flags.iter().all(|f| has_flag(*f)),
+        }
+    }
+}
+
+/// matches records based on their reason flags
+pub struct ReasonFilter {
+    flags: Vec<UsnReasonValue>,
+    mode: MatchMode,
+}
+
+impl ReasonFilter {
+    pub fn new(flags: &[UsnReasonValue], mode: MatchMode) -> Self {
+        Self {
+            flags: flags.to_vec(),
+            mode,
+        }
+    }
+
+    /// matches records which have at least one of `flags`
+    pub fn any(flags: &[UsnReasonValue]) -> Self {
+        Self::new(flags, MatchMode::Any)
+    }
+
+    /// matches records which have all of `flags`
+    pub fn all(flags: &[UsnReasonValue]) -> Self {
+        Self::new(flags, MatchMode::All)
+    }
+}
+
+impl RecordFilter for ReasonFilter {
+    fn matches(&self, record: &CommonUsnRecord) -> bool {
+        let reason = record.data.reason();
+        self.mode.matches(&self.flags, |f| reason.has_flag(f))
+    }
+}
+
+/// matches records based on the attributes of the file
+pub struct AttributeFilter {
+    flags: Vec<FileAttributeValue>,
+    mode: MatchMode,
+}
+
+impl AttributeFilter {
+    pub fn new(flags: &[FileAttributeValue], mode: MatchMode) -> Self {
+        Self {
+            flags: flags.to_vec(),
+            mode,
+        }
+    }
+
+    /// matches records of files which have at least one of `flags`
+    pub fn any(flags: &[FileAttributeValue]) -> Self {
+        Self::new(flags, MatchMode::Any)
+    }
+
+    /// matches records of files which have all of `flags`
+    pub fn all(flags: &[FileAttributeValue]) -> Self {
+        Self::new(flags, MatchMode::All)
+    }
+}
+
+impl RecordFilter for AttributeFilter {
+    fn matches(&self, record: &CommonUsnRecord) -> bool {
+        let attributes = record.data.file_attributes();
+        self.mode.matches(&self.flags, |f| attributes.has_flag(f))
+    }
+}
@@ -0,0 +1,111 @@
+//! predicates, which decide if a record should be processed.
+//!
+//! Filters can be combined with [`AllOf`], [`AnyOf`] and [`Not`]:
+//!
+//! ```
+//! use usnjrnl::UsnReasonValue::*;
+//! use usnjrnl::FileAttributeValue::*;
+//! use usnjrnl::filter::{AllOf, AttributeFilter, Not, ReasonFilter};
+//!
+//! let filter = AllOf::default()
+//!     .with(ReasonFilter::any(&[USN_REASON_FILE_DELETE, USN_REASON_RENAME_OLD_NAME]))
+//!     .with(Not::new(AttributeFilter::any(&[FILE_ATTRIBUTE_DIRECTORY])));
+//! ```
+
+use crate::CommonUsnRecord;
+
+mod flags;
+pub use flags::{AttributeFilter, MatchMode, ReasonFilter};
+
+mod name;
+pub use name::{NameGlob, NameList, NameRegex, ntfs_upcase};
+
+mod reference;
+pub use reference::{EntryReference, FileReferenceFilter, ParentFilter};
+
+mod query;
+pub use query::{Query, QueryError};
+
+pub trait RecordFilter {
+    /// returns `true` if the record should be processed
+    fn matches(&self, record: &CommonUsnRecord) -> bool;
+}
+
+impl<F> RecordFilter for F where F: Fn(&CommonUsnRecord) -> bool {
+    fn matches(&self, record: &CommonUsnRecord) -> bool {
+        self(record)
+    }
+}
+
+/// matches if all contained filters match, which is also true if there is no filter
+#[derive(Default)]
+pub struct AllOf {
+    filters: Vec<Box<dyn RecordFilter>>,
+}
+
+impl AllOf {
+    pub fn with<F: RecordFilter + 'static>(mut self, filter: F) -> Self {
+        self.push(filter);
+        self
+    }
+
+    pub fn push<F: RecordFilter + 'static>(&mut self, filter: F) {
+        self.filters.push(Box::new(filter));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+}
+
+impl RecordFilter for AllOf {
+    fn matches(&self, record: &CommonUsnRecord) -> bool {
+        self.filters.iter().all(|f| f.matches(record))
+    }
+}
+
+/// matches if at least one of the contained filters matches
+#[derive(Default)]
+pub struct AnyOf {
+    filters: Vec<Box<dyn RecordFilter>>,
+}
+
+impl AnyOf {
+    pub fn with<F: RecordFilter + 'static>(mut self, filter: F) -> Self {
+        self.push(filter);
+        self
+    }
+
+    pub fn push<F: RecordFilter + 'static>(&mut self, filter: F) {
+        self.filters.push(Box::new(filter));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+}
+
+impl RecordFilter for AnyOf {
+    fn matches(&self, record: &CommonUsnRecord) -> bool {
+        self.filters.iter().any(|f| f.matches(record))
+    }
+}
+
+/// inverts another filter
+pub struct Not {
+    filter: Box<dyn RecordFilter>,
+}
+
+impl Not {
+    pub fn new<F: RecordFilter + 'static>(filter: F) -> Self {
+        Self {
+            filter: Box::new(filter),
+        }
+    }
+}
+
+impl RecordFilter for Not {
+    fn matches(&self, record: &CommonUsnRecord) -> bool {
+        !self.filter.matches(record)
+    }
+}
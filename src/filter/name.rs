@@ -0,0 +1,155 @@
+use aho_corasick::{AhoCorasick, Anchored, Input, MatchKind, StartKind};
+use regex::{Regex, RegexBuilder, RegexSet};
+use crate::CommonUsnRecord;
+
+use super::RecordFilter;
+
+/// converts a name to upper case like NTFS does when it compares names: every
+/// UTF-16 code unit is mapped to exactly one code unit, so there is no
+/// special casing like `ß` to `SS`, and characters outside of the basic
+/// multilingual plane are not changed at all.
+pub fn ntfs_upcase(name: &str) -> String {
+    name.chars().map(|c| {
+        if c as u32 > 0xffff {
+            return c;
+        }
+        let mut upper = c.to_uppercase();
+        match (upper.next(), upper.next()) {
+            (Some(u), None) if u as u32 <= 0xffff => u,
+            _ => c,
+        }
+    }).collect()
+}
+
+/// converts a Windows wildcard pattern, which supports `*` and `?`, into an anchored regular expression
+fn glob_to_regex(pattern: &str) -> String {
+    let mut regex = String::from("^");
+    for c in ntfs_upcase(pattern).chars() {
+        match c {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    regex
+}
+
+/// matches file names against a wildcard pattern like `*.ps1` or
+/// `mimikatz*`, ignoring case
+pub struct NameGlob {
+    regex: Regex,
+}
+
+impl NameGlob {
+    pub fn new(pattern: &str) -> Result<Self, regex::Error> {
+        Ok(Self {
+            regex: RegexBuilder::new(&glob_to_regex(pattern)).dot_matches_new_line(true).build()?,
+        })
+    }
+
+    pub fn is_match(&self, name: &str) -> bool {
+        self.regex.is_match(&ntfs_upcase(name))
+    }
+}
+
+impl RecordFilter for NameGlob {
+    fn matches(&self, record: &CommonUsnRecord) -> bool {
+        self.is_match(record.data.filename())
+    }
+}
+
+/// matches file names against a regular expression, ignoring case. The
+/// expression is not anchored, so `\.(locked|encrypted)$` matches the end of a name.
+///
+/// Unlike [`NameGlob`] and [`NameList`], which compare names like NTFS does
+/// (see [`ntfs_upcase`]), case is ignored by Unicode simple case folding. So
+/// a few names, which are different for NTFS, match the same expression:
+/// `k` also matches the Kelvin sign `K`, and letters outside the Basic
+/// Multilingual Plane match their other case.
+pub struct NameRegex {
+    regex: Regex,
+}
+
+impl NameRegex {
+    pub fn new(pattern: &str) -> Result<Self, regex::Error> {
+        Ok(Self {
+            regex: RegexBuilder::new(pattern).case_insensitive(true).build()?,
+        })
+    }
+
+    pub fn is_match(&self, name: &str) -> bool {
+        self.regex.is_match(name)
+    }
+}
+
+impl RecordFilter for NameRegex {
+    fn matches(&self, record: &CommonUsnRecord) -> bool {
+        self.is_match(record.data.filename())
+    }
+}
+
+/// matches file names against a large list of names, e.g. from an IOC feed.
+///
+/// Every entry is either a complete file name or a wildcard pattern. All
+/// names are checked in a single pass, using an Aho-Corasick automaton for
+/// the complete names and a regex set for the patterns. Case is ignored.
+pub struct NameList {
+    names: AhoCorasick,
+    patterns: RegexSet,
+}
+
+impl NameList {
+    /// creates a list from `entries`, ignoring empty entries and entries starting with `#`
+    pub fn new<I, S>(entries: I) -> Result<Self, regex::Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut names = Vec::new();
+        let mut patterns = Vec::new();
+        for entry in entries {
+            let entry = entry.as_ref().trim();
+            if entry.is_empty() || entry.starts_with('#') {
+                continue;
+            }
+            if entry.contains(['*', '?']) {
+                patterns.push(glob_to_regex(entry));
+            } else {
+                names.push(ntfs_upcase(entry));
+            }
+        }
+
+        let names = AhoCorasick::builder()
+            .match_kind(MatchKind::LeftmostLongest)
+            .start_kind(StartKind::Anchored)
+            .build(&names)
+            .map_err(|e| regex::Error::Syntax(e.to_string()))?;
+        Ok(Self {
+            names,
+            patterns: RegexSet::new(&patterns)?,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.patterns_len() + self.patterns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_match(&self, name: &str) -> bool {
+        let name = ntfs_upcase(name);
+
+        /* the longest name which starts at the beginning must cover the whole name */
+        let input = Input::new(&name).anchored(Anchored::Yes);
+        self.names.find(input).is_some_and(|m| m.end() == name.len()) || self.patterns.is_match(&name)
+    }
+}
+
+impl RecordFilter for NameList {
+    fn matches(&self, record: &CommonUsnRecord) -> bool {
+        self.is_match(record.data.filename())
+    }
+}
@@ -0,0 +1,521 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::rc::Rc;
+use std::str::FromStr;
+use chrono::{DateTime, Utc};
+use crate::{CommonUsnRecord, DisplayTimezone, FileAttributeValue, PathResolver, UsnReasonValue};
+
+use super::{EntryReference, NameGlob, NameRegex, RecordFilter, ntfs_upcase};
+
+/// an error in the text of a [`Query`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryError {
+    /// offset of the erroneous token, in characters
+    pub position: usize,
+    pub message: String,
+}
+
+impl QueryError {
+    fn new(position: usize, message: impl Into<String>) -> Self {
+        Self {
+            position,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid query at position {}: {}", self.position, self.message)
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+/// a filter, which is given as an expression like
+///
+/// ```text
+/// reason has FILE_DELETE and name ~ "\.exe$" and time > 2026-03-01 or parent = 5
+/// ```
+///
+/// A condition consists of a field, an operator and a value. Conditions
+/// can be combined with `and`, `or`, `not` and parentheses, where `and`
+/// binds stronger than `or`. Values which contain spaces or operators must
+/// be quoted.
+///
+/// | field | operators | value |
+/// |-|-|-|
+/// | `usn`, `entry`, `seq`, `sourceinfo` | `=` `!=` `<` `<=` `>` `>=` | decimal or hexadecimal (`0x...`) number |
+/// | `time` | `=` `!=` `<` `<=` `>` `>=` | RFC 3339 timestamp, or date and time like `"2026-03-01 14:02"` in the timezone of the query (UTC by default) |
+/// | `parent` | `=` `!=` | entry number, or entry and sequence number like `12345-3` |
+/// | `reason`, `attributes` | `has` (all flags are set), `=` `!=` (exactly these flags) | flags separated by `,` or `+`, like `FILE_DELETE,CLOSE` |
+/// | `name`, `path` | `=` `!=`, `~` `!~` (regular expression), `like` (wildcards) | text; case is ignored |
+///
+/// The `path` field requires a [`PathResolver`], see [`Query::with_resolver`].
+/// Without it, or if the path cannot be resolved, the name of the file is used.
+/// Timestamps without offset are interpreted in UTC, unless the query is parsed
+/// with [`Query::parse_in_timezone`].
+pub struct Query {
+    root: Expression,
+    resolver: Rc<PathResolver>,
+}
+
+impl Query {
+    pub fn parse(text: &str) -> Result<Self, QueryError> {
+        Self::parse_in_timezone(text, DisplayTimezone::default())
+    }
+
+    /// parses a query, where timestamps without offset are interpreted in `timezone`
+    pub fn parse_in_timezone(text: &str, timezone: DisplayTimezone) -> Result<Self, QueryError> {
+        let tokens = tokenize(text)?;
+        let mut parser = Parser { tokens, next: 0, length: text.chars().count(), timezone };
+        let root = parser.parse_or()?;
+        if let Some((position, token)) = parser.tokens.get(parser.next) {
+            return Err(QueryError::new(*position, format!("unexpected {}", token)));
+        }
+        Ok(Self {
+            root,
+            resolver: Rc::new(PathResolver::default()),
+        })
+    }
+
+    /// sets the resolver which is used to evaluate the `path` field
+    pub fn with_resolver(mut self, resolver: Rc<PathResolver>) -> Self {
+        self.resolver = resolver;
+        self
+    }
+
+    /// returns `true` if the query refers to the `path` field
+    pub fn uses_path(&self) -> bool {
+        self.root.uses_path()
+    }
+}
+
+impl FromStr for Query {
+    type Err = QueryError;
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Self::parse(text)
+    }
+}
+
+impl RecordFilter for Query {
+    fn matches(&self, record: &CommonUsnRecord) -> bool {
+        self.root.evaluate(record, &self.resolver)
+    }
+}
+
+enum Expression {
+    And(Box<Expression>, Box<Expression>),
+    Or(Box<Expression>, Box<Expression>),
+    Not(Box<Expression>),
+    Condition(Condition),
+}
+
+impl Expression {
+    fn evaluate(&self, record: &CommonUsnRecord, resolver: &PathResolver) -> bool {
+        match self {
+            Self::And(lhs, rhs) => lhs.evaluate(record, resolver) && rhs.evaluate(record, resolver),
+            Self::Or(lhs, rhs) => lhs.evaluate(record, resolver) || rhs.evaluate(record, resolver),
+            Self::Not(expression) => !expression.evaluate(record, resolver),
+            Self::Condition(condition) => condition.evaluate(record, resolver),
+        }
+    }
+
+    fn uses_path(&self) -> bool {
+        match self {
+            Self::And(lhs, rhs) | Self::Or(lhs, rhs) => lhs.uses_path() || rhs.uses_path(),
+            Self::Not(expression) => expression.uses_path(),
+            Self::Condition(Condition::Text { field, .. }) => *field == TextField::Path,
+            Self::Condition(_) => false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum NumberField {
+    Usn,
+    Entry,
+    Sequence,
+    SourceInfo,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum FlagField {
+    Reason,
+    Attributes,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum TextField {
+    Name,
+    Path,
+}
+
+enum Field {
+    Number(NumberField),
+    Time,
+    Parent,
+    Flags(FlagField),
+    Text(TextField),
+}
+
+impl Field {
+    fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "usn" => Some(Self::Number(NumberField::Usn)),
+            "entry" => Some(Self::Number(NumberField::Entry)),
+            "seq" | "sequence" => Some(Self::Number(NumberField::Sequence)),
+            "sourceinfo" | "source_info" => Some(Self::Number(NumberField::SourceInfo)),
+            "time" | "timestamp" => Some(Self::Time),
+            "parent" => Some(Self::Parent),
+            "reason" | "reasons" => Some(Self::Flags(FlagField::Reason)),
+            "attributes" | "attr" => Some(Self::Flags(FlagField::Attributes)),
+            "name" | "filename" => Some(Self::Text(TextField::Name)),
+            "path" => Some(Self::Text(TextField::Path)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Operator {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Match,
+    NotMatch,
+    Has,
+    Like,
+}
+
+impl Operator {
+    fn compare<T: Ord>(&self, lhs: T, rhs: T) -> bool {
+        let ordering = lhs.cmp(&rhs);
+        match self {
+            Self::Equal => ordering == Ordering::Equal,
+            Self::NotEqual => ordering != Ordering::Equal,
+            Self::Less => ordering == Ordering::Less,
+            Self::LessOrEqual => ordering != Ordering::Greater,
+            Self::Greater => ordering == Ordering::Greater,
+            Self::GreaterOrEqual => ordering != Ordering::Less,
+            _ => false,
+        }
+    }
+
+    fn is_ordering(&self) -> bool {
+        matches!(self, Self::Equal | Self::NotEqual | Self::Less | Self::LessOrEqual | Self::Greater | Self::GreaterOrEqual)
+    }
+}
+
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Self::Equal => "=",
+            Self::NotEqual => "!=",
+            Self::Less => "<",
+            Self::LessOrEqual => "<=",
+            Self::Greater => ">",
+            Self::GreaterOrEqual => ">=",
+            Self::Match => "~",
+            Self::NotMatch => "!~",
+            Self::Has => "has",
+            Self::Like => "like",
+        };
+        write!(f, "'{}'", name)
+    }
+}
+
+enum TextMatcher {
+    Equal(String),
+    Regex(NameRegex),
+    Glob(NameGlob),
+}
+
+enum Condition {
+    Number { field: NumberField, operator: Operator, value: i64 },
+    Time { operator: Operator, value: DateTime<Utc> },
+    Parent { reference: EntryReference, negate: bool },
+    Flags { field: FlagField, operator: Operator, value: u32 },
+    Text { field: TextField, matcher: TextMatcher, negate: bool },
+}
+
+impl Condition {
+    fn evaluate(&self, record: &CommonUsnRecord, resolver: &PathResolver) -> bool {
+        let data = &record.data;
+        match self {
+            Self::Number { field, operator, value } => {
+                let actual = match field {
+                    NumberField::Usn => data.usn(),
+                    NumberField::Entry => data.file_reference().entry as i64,
+                    NumberField::Sequence => data.file_reference().sequence as i64,
+                    NumberField::SourceInfo => data.source_info() as i64,
+                };
+                operator.compare(actual, *value)
+            }
+            Self::Time { operator, value } => operator.compare(data.timestamp(), value),
+            Self::Parent { reference, negate } => reference.matches(data.parent_reference()) != *negate,
+            Self::Flags { field, operator, value } => {
+                let actual = match field {
+                    FlagField::Reason => data.reason().value(),
+                    FlagField::Attributes => data.file_attributes().value(),
+                };
+                match operator {
+                    Operator::Has => actual & value == *value,
+                    Operator::Equal => actual == *value,
+                    _ => actual != *value,
+                }
+            }
+            Self::Text { field, matcher, negate } => {
+                let path;
+                let text = match field {
+                    TextField::Name => data.filename(),
+                    TextField::Path => {
+                        path = resolver.full_path(record);
+                        path.as_deref().unwrap_or_else(|| data.filename())
+                    }
+                };
+                let matches = match matcher {
+                    TextMatcher::Equal(value) => ntfs_upcase(text) == *value,
+                    TextMatcher::Regex(regex) => regex.is_match(text),
+                    TextMatcher::Glob(glob) => glob.is_match(text),
+                };
+                matches != *negate
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    /// a keyword, field name or unquoted value
+    Word(String),
+    Quoted(String),
+    Operator(Operator),
+    OpenParen,
+    CloseParen,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Word(word) => write!(f, "'{}'", word),
+            Self::Quoted(text) => write!(f, "\"{}\"", text),
+            Self::Operator(operator) => write!(f, "{}", operator),
+            Self::OpenParen => write!(f, "'('"),
+            Self::CloseParen => write!(f, "')'"),
+        }
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    !c.is_whitespace() && !"()\"'=!<>~".contains(c)
+}
+
+fn tokenize(text: &str) -> Result<Vec<(usize, Token)>, QueryError> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut position = 0;
+    while position < chars.len() {
+        let start = position;
+        let c = chars[position];
+        let next = chars.get(position + 1).copied();
+        let (token, length) = match (c, next) {
+            (c, _) if c.is_whitespace() => {
+                position += 1;
+                continue;
+            }
+            ('(', _) => (Token::OpenParen, 1),
+            (')', _) => (Token::CloseParen, 1),
+            ('=', Some('=')) => (Token::Operator(Operator::Equal), 2),
+            ('=', _) => (Token::Operator(Operator::Equal), 1),
+            ('!', Some('=')) => (Token::Operator(Operator::NotEqual), 2),
+            ('!', Some('~')) => (Token::Operator(Operator::NotMatch), 2),
+            ('<', Some('=')) => (Token::Operator(Operator::LessOrEqual), 2),
+            ('<', _) => (Token::Operator(Operator::Less), 1),
+            ('>', Some('=')) => (Token::Operator(Operator::GreaterOrEqual), 2),
+            ('>', _) => (Token::Operator(Operator::Greater), 1),
+            ('~', _) => (Token::Operator(Operator::Match), 1),
+            ('"', _) | ('\'', _) => {
+                /* backslashes are kept, unless they escape the quote or a backslash,
+                 * so that regular expressions can be written without doubling them */
+                let mut value = String::new();
+                let mut end = position + 1;
+                loop {
+                    match (chars.get(end), chars.get(end + 1)) {
+                        (None, _) => return Err(QueryError::new(start, "unterminated string")),
+                        (Some('\\'), Some(escaped)) if *escaped == c || *escaped == '\\' => {
+                            value.push(*escaped);
+                            end += 2;
+                        }
+                        (Some(q), _) if *q == c => break,
+                        (Some(other), _) => {
+                            value.push(*other);
+                            end += 1;
+                        }
+                    }
+                }
+                (Token::Quoted(value), end + 1 - position)
+            }
+            (c, _) if is_word_char(c) => {
+                let word: String = chars[position..].iter().take_while(|c| is_word_char(**c)).collect();
+                let length = word.chars().count();
+                (Token::Word(word), length)
+            }
+            (c, _) => return Err(QueryError::new(start, format!("unexpected character '{}'", c))),
+        };
+        tokens.push((start, token));
+        position += length;
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    next: usize,
+
+    /// length of the query, which is reported as position if the query ends unexpectedly
+    length: usize,
+
+    /// timezone of timestamps without offset
+    timezone: DisplayTimezone,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.next).map(|(_, token)| token)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens.get(self.next).map(|(position, _)| *position).unwrap_or(self.length)
+    }
+
+    fn next_token(&mut self, expected: &str) -> Result<(usize, Token), QueryError> {
+        match self.tokens.get(self.next) {
+            Some(token) => {
+                self.next += 1;
+                Ok(token.clone())
+            }
+            None => Err(QueryError::new(self.length, format!("expected {}", expected))),
+        }
+    }
+
+    /// consumes the next token, if it is the keyword `keyword`
+    fn keyword(&mut self, keyword: &str) -> bool {
+        match self.peek() {
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword) => {
+                self.next += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expression, QueryError> {
+        let mut expression = self.parse_and()?;
+        while self.keyword("or") {
+            expression = Expression::Or(Box::new(expression), Box::new(self.parse_and()?));
+        }
+        Ok(expression)
+    }
+
+    fn parse_and(&mut self) -> Result<Expression, QueryError> {
+        let mut expression = self.parse_unary()?;
+        while self.keyword("and") {
+            expression = Expression::And(Box::new(expression), Box::new(self.parse_unary()?));
+        }
+        Ok(expression)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expression, QueryError> {
+        if self.keyword("not") {
+            return Ok(Expression::Not(Box::new(self.parse_unary()?)));
+        }
+        if self.peek() == Some(&Token::OpenParen) {
+            self.next += 1;
+            let expression = self.parse_or()?;
+            return match self.next_token("')'")? {
+                (_, Token::CloseParen) => Ok(expression),
+                (position, token) => Err(QueryError::new(position, format!("expected ')' instead of {}", token))),
+            };
+        }
+        self.parse_condition().map(Expression::Condition)
+    }
+
+    fn parse_condition(&mut self) -> Result<Condition, QueryError> {
+        let field = match self.next_token("a field name")? {
+            (position, Token::Word(name)) => Field::parse(&name)
+                .ok_or_else(|| QueryError::new(position, format!("unknown field '{}'", name)))?,
+            (position, token) => return Err(QueryError::new(position, format!("expected a field name instead of {}", token))),
+        };
+
+        let operator_position = self.position();
+        let operator = match self.next_token("an operator")? {
+            (_, Token::Operator(operator)) => operator,
+            (_, Token::Word(word)) if word.eq_ignore_ascii_case("has") => Operator::Has,
+            (_, Token::Word(word)) if word.eq_ignore_ascii_case("like") => Operator::Like,
+            (position, token) => return Err(QueryError::new(position, format!("expected an operator instead of {}", token))),
+        };
+
+        let (value_position, value) = match self.next_token("a value")? {
+            (position, Token::Word(value)) | (position, Token::Quoted(value)) => (position, value),
+            (position, token) => return Err(QueryError::new(position, format!("expected a value instead of {}", token))),
+        };
+        let invalid_value = |message: String| QueryError::new(value_position, message);
+        let invalid_operator = || QueryError::new(operator_position, format!("operator {} cannot be used here", operator));
+
+        match field {
+            Field::Number(field) if operator.is_ordering() => Ok(Condition::Number {
+                field,
+                operator,
+                value: parse_number(&value).ok_or_else(|| invalid_value(format!("invalid number: '{}'", value)))?,
+            }),
+            Field::Time if operator.is_ordering() => Ok(Condition::Time {
+                operator,
+                value: self.timezone.parse_timestamp(&value).map_err(invalid_value)?,
+            }),
+            Field::Parent if matches!(operator, Operator::Equal | Operator::NotEqual) => Ok(Condition::Parent {
+                reference: value.parse().map_err(invalid_value)?,
+                negate: operator == Operator::NotEqual,
+            }),
+            Field::Flags(field) if matches!(operator, Operator::Has | Operator::Equal | Operator::NotEqual) => {
+                let value = parse_flags(field, &value).map_err(invalid_value)?;
+                Ok(Condition::Flags { field, operator, value })
+            }
+            Field::Text(field) => {
+                let matcher = match operator {
+                    Operator::Equal | Operator::NotEqual => TextMatcher::Equal(ntfs_upcase(&value)),
+                    Operator::Match | Operator::NotMatch => TextMatcher::Regex(
+                        NameRegex::new(&value).map_err(|e| invalid_value(e.to_string()))?),
+                    Operator::Like => TextMatcher::Glob(
+                        NameGlob::new(&value).map_err(|e| invalid_value(e.to_string()))?),
+                    _ => return Err(invalid_operator()),
+                };
+                Ok(Condition::Text {
+                    field,
+                    matcher,
+                    negate: matches!(operator, Operator::NotEqual | Operator::NotMatch),
+                })
+            }
+            _ => Err(invalid_operator()),
+        }
+    }
+}
+
+fn parse_number(value: &str) -> Option<i64> {
+    match value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")) {
+        Some(hex) => i64::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+fn parse_flags(field: FlagField, value: &str) -> Result<u32, String> {
+    value.split([',', '+'])
+        .map(|flag| match field {
+            FlagField::Reason => UsnReasonValue::from_str(flag.trim()).map(|f| f as u32),
+            FlagField::Attributes => FileAttributeValue::from_str(flag.trim()).map(|f| f as u32),
+        })
+        .try_fold(0, |flags, flag| flag.map(|f| flags | f))
+}
@@ -0,0 +1,82 @@
+use std::fmt;
+use std::str::FromStr;
+use winstructs::ntfs::mft_reference::MftReference;
+use crate::CommonUsnRecord;
+
+use super::RecordFilter;
+
+/// an MFT entry number, optionally with a sequence number. Without a
+/// sequence number, all files which ever used this MFT entry are matched.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EntryReference {
+    pub entry: u64,
+    pub sequence: Option<u16>,
+}
+
+impl EntryReference {
+    pub fn new(entry: u64, sequence: Option<u16>) -> Self {
+        Self { entry, sequence }
+    }
+
+    pub fn matches(&self, reference: &MftReference) -> bool {
+        reference.entry == self.entry && self.sequence.is_none_or(|s| s == reference.sequence)
+    }
+}
+
+impl FromStr for EntryReference {
+    type Err = String;
+
+    /// parses either an entry number like `12345` or entry and sequence number like `12345-3`
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid file reference: '{}'", value);
+        match value.split_once('-') {
+            Some((entry, sequence)) => Ok(Self::new(
+                entry.parse().map_err(|_| invalid())?,
+                Some(sequence.parse().map_err(|_| invalid())?))),
+            None => Ok(Self::new(value.parse().map_err(|_| invalid())?, None)),
+        }
+    }
+}
+
+impl fmt::Display for EntryReference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.sequence {
+            Some(sequence) => write!(f, "{}-{}", self.entry, sequence),
+            None => write!(f, "{}", self.entry),
+        }
+    }
+}
+
+/// matches all records of a file
+pub struct FileReferenceFilter {
+    reference: EntryReference,
+}
+
+impl FileReferenceFilter {
+    pub fn new(reference: EntryReference) -> Self {
+        Self { reference }
+    }
+}
+
+impl RecordFilter for FileReferenceFilter {
+    fn matches(&self, record: &CommonUsnRecord) -> bool {
+        self.reference.matches(record.data.file_reference())
+    }
+}
+
+/// matches all records of files in a directory (not including subdirectories)
+pub struct ParentFilter {
+    reference: EntryReference,
+}
+
+impl ParentFilter {
+    pub fn new(reference: EntryReference) -> Self {
+        Self { reference }
+    }
+}
+
+impl RecordFilter for ParentFilter {
+    fn matches(&self, record: &CommonUsnRecord) -> bool {
+        self.reference.matches(record.data.parent_reference())
+    }
+}
@@ -0,0 +1,105 @@
+use std::io::{self, Write};
+use std::rc::Rc;
+use bodyfile::Bodyfile3Line;
+use crate::{CommonUsnRecord, PathResolver, UsnReasonClass, UsnRecordData};
+use crate::open_files::OpenFiles;
+use crate::serialize::reference_to_string;
+
+use super::RecordWriter;
+
+pub struct BodyfileFormatter {}
+impl RecordWriter for BodyfileFormatter {
+    fn write_record(&mut self, out: &mut dyn Write, record: &CommonUsnRecord) -> io::Result<()> {
+        let message = format!("{} (UsnJrnl reason: {})",
+                        record.data.filename(),
+                        record.data.reason());
+        let mut bf_line = Bodyfile3Line::new()
+            .with_name(&message)
+            .with_mtime(record.data.timestamp().timestamp());
+
+        #[allow(irrefutable_let_patterns)]
+        if let UsnRecordData::V2(ref v2record) = record.data {
+            bf_line = bf_line.with_owned_inode(format!("{}", v2record.FileReferenceNumber.entry));
+        }
+        writeln!(out, "{}", bf_line)
+    }
+}
+
+/// the bodyfile column which is set by a bodyfile line
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum MacbColumn {
+    Modified,
+    Changed,
+    Born,
+}
+
+/// creates bodyfile lines, which can be merged with bodyfiles created by
+/// `mft2bodyfile` or `fls`.
+///
+/// Because reason flags accumulate until the file is closed, only flags which
+/// have not been seen in the previous record of the same file result in a
+/// bodyfile line. Every line sets only the column which matches its reasons:
+/// creation sets `crtime`, data changes set `mtime`, and all other changes
+/// set `ctime`.
+pub struct MacbBodyfileFormatter {
+    resolver: Rc<PathResolver>,
+    open_files: OpenFiles,
+}
+
+impl MacbBodyfileFormatter {
+    pub fn new(resolver: Rc<PathResolver>) -> Self {
+        Self {
+            resolver,
+            open_files: OpenFiles::default(),
+        }
+    }
+}
+
+impl RecordWriter for MacbBodyfileFormatter {
+    fn write_record(&mut self, out: &mut dyn Write, record: &CommonUsnRecord) -> io::Result<()> {
+        let data = &record.data;
+        let new_reasons = self.open_files.new_reasons(record);
+        let file_reference = data.file_reference();
+        let parent_reference = data.parent_reference();
+        let timestamp = data.timestamp().timestamp();
+
+        /* if the path is unknown, add the parent reference to make the name unambiguous */
+        let (name, parent) = match self.resolver.full_path(record) {
+            Some(path) => (path, String::new()),
+            None => (data.filename().to_owned(),
+                     format!(", parent: {}", reference_to_string(parent_reference))),
+        };
+
+        for column in [MacbColumn::Born, MacbColumn::Modified, MacbColumn::Changed] {
+            let flags: Vec<_> = new_reasons.flags()
+                .filter(|f| macb_column(f.class()) == Some(column))
+                .map(|f| f.short_name())
+                .collect();
+            if flags.is_empty() {
+                continue;
+            }
+
+            let line = Bodyfile3Line::new()
+                .with_owned_name(format!("{} ($UsnJrnl: {}{})", name, flags.join("+"), parent))
+                .with_owned_inode(reference_to_string(file_reference));
+            let line = match column {
+                MacbColumn::Born => line.with_crtime(timestamp),
+                MacbColumn::Modified => line.with_mtime(timestamp),
+                MacbColumn::Changed => line.with_ctime(timestamp),
+            };
+            writeln!(out, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+fn macb_column(class: UsnReasonClass) -> Option<MacbColumn> {
+    match class {
+        UsnReasonClass::Create => Some(MacbColumn::Born),
+        UsnReasonClass::Data => Some(MacbColumn::Modified),
+        UsnReasonClass::Metadata |
+        UsnReasonClass::Rename |
+        UsnReasonClass::Delete => Some(MacbColumn::Changed),
+        UsnReasonClass::Close => None,
+    }
+}
@@ -0,0 +1,76 @@
+use std::io::{self, Write};
+use std::rc::Rc;
+use crate::{CommonUsnRecord, PathResolver, UsnReasonClass};
+use crate::serialize::reference_to_string;
+
+use super::{PRODUCT_NAME, RecordWriter, header_value};
+
+/// creates events in the ArcSight Common Event Format (CEF)
+pub struct CefFormatter {
+    host_name: Option<String>,
+    resolver: Rc<PathResolver>,
+}
+
+impl CefFormatter {
+    pub fn new(host_name: Option<&str>, resolver: Rc<PathResolver>) -> Self {
+        Self {
+            host_name: host_name.map(|h| h.to_owned()),
+            resolver,
+        }
+    }
+}
+
+impl RecordWriter for CefFormatter {
+    fn write_record(&mut self, out: &mut dyn Write, record: &CommonUsnRecord) -> io::Result<()> {
+        let data = &record.data;
+        let class = data.reason().primary_class();
+        let (name, severity) = match class {
+            Some(UsnReasonClass::Delete) => ("File deleted", 5),
+            Some(UsnReasonClass::Create) => ("File created", 3),
+            Some(UsnReasonClass::Rename) => ("File renamed", 3),
+            Some(UsnReasonClass::Data) => ("File modified", 3),
+            Some(UsnReasonClass::Metadata) => ("File metadata changed", 2),
+            Some(UsnReasonClass::Close) | None => ("File closed", 1),
+        };
+        let event_class = class.map(|c| c.to_string()).unwrap_or_else(|| "none".to_owned());
+
+        let mut extension = vec![
+            ("rt", data.timestamp().timestamp_millis().to_string()),
+            ("fname", data.filename().to_owned()),
+            ("fileId", reference_to_string(data.file_reference())),
+            ("act", data.reason().to_string()),
+            ("cn1Label", "usn".to_owned()),
+            ("cn1", data.usn().to_string()),
+            ("cs1Label", "parentFileReference".to_owned()),
+            ("cs1", reference_to_string(data.parent_reference())),
+            ("cs2Label", "fileAttributes".to_owned()),
+            ("cs2", data.file_attributes().to_string()),
+        ];
+        if let Some(path) = self.resolver.full_path(record) {
+            extension.push(("filePath", path));
+        }
+        if let Some(ref host_name) = self.host_name {
+            extension.push(("dvchost", host_name.clone()));
+        }
+
+        writeln!(out, "CEF:0|{}|{}|{}|{}|{}|{}|{}",
+            header_value("usnjrnl"),
+            header_value(PRODUCT_NAME),
+            header_value(env!("CARGO_PKG_VERSION")),
+            header_value(&event_class),
+            header_value(name),
+            severity,
+            extension.iter()
+                .map(|(key, value)| format!("{}={}", key, extension_value(value)))
+                .collect::<Vec<_>>()
+                .join(" "))
+    }
+}
+
+/// escapes `\`, `=` and line breaks in extension values
+fn extension_value(value: &str) -> String {
+    value.replace('\\', "\\\\")
+        .replace('=', "\\=")
+        .replace('\r', "\\r")
+        .replace('\n', "\\n")
+}
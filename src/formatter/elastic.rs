@@ -0,0 +1,176 @@
+use std::io::{self, Write};
+use std::rc::Rc;
+use serde_json::{json, Value};
+use crate::{CommonUsnRecord, PathResolver, UsnReason, UsnReasonClass};
+use crate::analysis::extension_of;
+
+use super::RecordWriter;
+
+/// version of the Elastic Common Schema which is used by this formatter
+const ECS_VERSION: &str = "8.11.0";
+
+/// creates bulk API requests (NDJSON), consisting of an action line followed
+/// by a document, which is mapped to the Elastic Common Schema (ECS)
+pub struct ElasticFormatter {
+    index: String,
+    host_name: Option<String>,
+    resolver: Rc<PathResolver>,
+}
+
+impl ElasticFormatter {
+    pub fn new(index: &str, host_name: Option<&str>, resolver: Rc<PathResolver>) -> Self {
+        Self {
+            index: index.to_owned(),
+            host_name: host_name.map(|h| h.to_owned()),
+            resolver,
+        }
+    }
+}
+
+impl RecordWriter for ElasticFormatter {
+    fn write_record(&mut self, out: &mut dyn Write, record: &CommonUsnRecord) -> io::Result<()> {
+        let reason = record.data.reason();
+        let filename = record.data.filename();
+        let file_reference = record.data.file_reference();
+        let parent_reference = record.data.parent_reference();
+
+        let (action, event_types) = ecs_event(reason);
+        let mut document = json!({
+            "@timestamp": record.data.timestamp(),
+            "ecs": { "version": ECS_VERSION },
+            "event": {
+                "kind": "event",
+                "category": ["file"],
+                "type": event_types,
+                "action": action,
+                "module": "usnjrnl",
+                "dataset": "usnjrnl.record",
+            },
+            "file": {
+                "name": filename,
+                "inode": format!("{}", file_reference.entry),
+                "attributes": record.data.file_attributes().flags()
+                    .map(|f| f.short_name().to_lowercase())
+                    .collect::<Vec<_>>(),
+            },
+            "usnjrnl": {
+                "usn": record.data.usn(),
+                "reason": reason.flags().map(|f| f.short_name()).collect::<Vec<_>>(),
+                "file_reference": {
+                    "entry": file_reference.entry,
+                    "sequence": file_reference.sequence,
+                },
+                "parent_reference": {
+                    "entry": parent_reference.entry,
+                    "sequence": parent_reference.sequence,
+                },
+            },
+        });
+
+        if let Some(extension) = extension_of(filename) {
+            document["file"]["extension"] = Value::String(extension);
+        }
+        if let (Some(directory), Some(path)) = (
+                self.resolver.directory_path(parent_reference),
+                self.resolver.full_path(record)) {
+            document["file"]["directory"] = Value::String(directory);
+            document["file"]["path"] = Value::String(path);
+        }
+        if let Some(ref host_name) = self.host_name {
+            document["host"] = json!({ "name": host_name });
+        }
+
+        let action_line = json!({ "index": { "_index": self.index } });
+        writeln!(out, "{}\n{}", action_line, document)
+    }
+}
+
+/// maps the reason flags of a record to the values of `event.action` and
+/// `event.type`. If multiple reasons are set, the most significant one
+/// determines `event.action`.
+fn ecs_event(reason: &UsnReason) -> (&'static str, Vec<&'static str>) {
+    let classes = reason.classes();
+    let mut types: Vec<_> = classes.iter().filter_map(|c| match c {
+        UsnReasonClass::Delete => Some("deletion"),
+        UsnReasonClass::Create => Some("creation"),
+        UsnReasonClass::Close => None,
+        _ => Some("change"),
+    }).collect();
+    types.dedup();
+    if types.is_empty() {
+        types.push("info");
+    }
+
+    let action = match classes.first() {
+        Some(UsnReasonClass::Delete) => "deleted",
+        Some(UsnReasonClass::Create) => "created",
+        Some(UsnReasonClass::Rename) => "renamed",
+        Some(UsnReasonClass::Data) => "modified",
+        Some(UsnReasonClass::Metadata) => "attributes-modified",
+        Some(UsnReasonClass::Close) => "closed",
+        None => "changed",
+    };
+    (action, types)
+}
+
+/// creates an index template for all indices which start with `index`,
+/// which contains the mappings of all fields generated by [`ElasticFormatter`]
+pub fn elastic_index_template(index: &str) -> String {
+    let keyword = json!({ "type": "keyword" });
+    let template = json!({
+        "index_patterns": [format!("{}*", index)],
+        "priority": 200,
+        "template": {
+            "mappings": {
+                "dynamic": false,
+                "properties": {
+                    "@timestamp": { "type": "date" },
+                    "ecs": { "properties": { "version": keyword } },
+                    "event": {
+                        "properties": {
+                            "kind": keyword,
+                            "category": keyword,
+                            "type": keyword,
+                            "action": keyword,
+                            "module": keyword,
+                            "dataset": keyword,
+                        }
+                    },
+                    "file": {
+                        "properties": {
+                            "name": keyword,
+                            "extension": keyword,
+                            "attributes": keyword,
+                            "inode": keyword,
+                            "directory": keyword,
+                            "path": {
+                                "type": "keyword",
+                                "fields": { "text": { "type": "text" } }
+                            },
+                        }
+                    },
+                    "host": { "properties": { "name": keyword } },
+                    "usnjrnl": {
+                        "properties": {
+                            "usn": { "type": "long" },
+                            "reason": keyword,
+                            "file_reference": {
+                                "properties": {
+                                    "entry": { "type": "long" },
+                                    "sequence": { "type": "integer" },
+                                }
+                            },
+                            "parent_reference": {
+                                "properties": {
+                                    "entry": { "type": "long" },
+                                    "sequence": { "type": "integer" },
+                                }
+                            },
+                        }
+                    },
+                }
+            }
+        }
+    });
+    serde_json::to_string_pretty(&template).unwrap()
+}
@@ -0,0 +1,23 @@
+use std::io::{self, Write};
+use serde_json::json;
+use crate::{CommonUsnRecord, UsnRecordData};
+
+use super::RecordWriter;
+
+pub struct JsonFormatter {}
+impl RecordWriter for JsonFormatter {
+    fn write_record(&mut self, out: &mut dyn Write, record: &CommonUsnRecord) -> io::Result<()> {
+        let mut json = json!({
+            "timestamp": record.data.timestamp(),
+            "filename": record.data.filename(),
+            "reason": record.data.reason().to_string(),
+        });
+
+        #[allow(irrefutable_let_patterns)]
+        if let UsnRecordData::V2(ref v2record) = record.data {
+            json["inode"] = serde_json::Value::String(format!("{}", v2record.FileReferenceNumber.entry));
+            json["parent"] = serde_json::Value::String(format!("{}", v2record.ParentFileReferenceNumber.entry));
+        }
+        writeln!(out, "{}", json)
+    }
+}
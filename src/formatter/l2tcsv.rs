@@ -0,0 +1,82 @@
+use std::io::{self, Write};
+use std::rc::Rc;
+use crate::{CommonUsnRecord, PathResolver, UsnReasonClass};
+use crate::serialize::reference_to_string;
+
+use super::{DisplayTimezone, RecordWriter, sanitize, timestamp_desc};
+
+const HEADER: &str = "date,time,timezone,MACB,source,sourcetype,type,user,host,short,desc,version,filename,inode,notes,format,extra";
+
+/// creates the 17-column CSV format of log2timeline/plaso (l2tcsv)
+pub struct L2tCsvFormatter {
+    host_name: String,
+    user_name: String,
+    timezone: DisplayTimezone,
+    resolver: Rc<PathResolver>,
+}
+
+impl L2tCsvFormatter {
+    /// `timezone` is used to display date and time, and its name or offset is
+    /// written into the timezone column
+    pub fn new(host_name: Option<&str>,
+               user_name: Option<&str>,
+               timezone: DisplayTimezone,
+               resolver: Rc<PathResolver>) -> Self {
+        Self {
+            host_name: host_name.unwrap_or("-").to_owned(),
+            user_name: user_name.unwrap_or("-").to_owned(),
+            timezone,
+            resolver,
+        }
+    }
+}
+
+impl RecordWriter for L2tCsvFormatter {
+    fn write_header(&mut self, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "{}", HEADER)
+    }
+
+    fn write_record(&mut self, out: &mut dyn Write, record: &CommonUsnRecord) -> io::Result<()> {
+        let data = &record.data;
+        let timestamp = data.timestamp();
+        let reason = data.reason();
+        let classes = reason.classes();
+        let path = self.resolver.full_path(record);
+        let filename = path.as_deref().unwrap_or_else(|| data.filename());
+
+        let macb = format!("{}{}{}{}",
+            if classes.contains(&UsnReasonClass::Data) {'M'} else {'.'},
+            '.',
+            if classes.iter().any(|c| matches!(c,
+                UsnReasonClass::Metadata | UsnReasonClass::Rename | UsnReasonClass::Delete)) {'C'} else {'.'},
+            if classes.contains(&UsnReasonClass::Create) {'B'} else {'.'});
+
+        let short = format!("{} {}", data.filename(), reason);
+        let desc = format!("{} Update reason: {} File attributes: {} USN: {}",
+            filename, reason, data.file_attributes(), data.usn());
+        let extra = format!("file_reference: {} parent_file_reference: {} source_info: {} security_id: {}",
+            reference_to_string(data.file_reference()), reference_to_string(data.parent_reference()),
+            data.source_info(), data.security_id());
+
+        let fields = [
+            self.timezone.format(timestamp, "%m/%d/%Y"),
+            self.timezone.format(timestamp, "%H:%M:%S"),
+            self.timezone.name_at(timestamp),
+            macb,
+            "FILE".to_owned(),
+            "NTFS USN change".to_owned(),
+            timestamp_desc(reason.primary_class()).to_owned(),
+            self.user_name.clone(),
+            self.host_name.clone(),
+            short,
+            desc,
+            "2".to_owned(),
+            filename.to_owned(),
+            data.file_reference().entry.to_string(),
+            "-".to_owned(),
+            "usnjrnl".to_owned(),
+            extra,
+        ];
+        writeln!(out, "{}", fields.iter().map(|field| sanitize(field, ',')).collect::<Vec<_>>().join(","))
+    }
+}
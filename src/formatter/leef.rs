@@ -0,0 +1,67 @@
+use std::io::{self, Write};
+use std::rc::Rc;
+use crate::{CommonUsnRecord, PathResolver};
+use crate::serialize::reference_to_string;
+
+use super::{PRODUCT_NAME, RecordWriter, header_value};
+
+/// format of `devTime`, in the syntax of Java's `SimpleDateFormat`
+const DEV_TIME_FORMAT: &str = "yyyy-MM-dd HH:mm:ss.SSS z";
+
+/// creates events in the IBM QRadar Log Event Extended Format (LEEF 2.0),
+/// using a tab as delimiter between attributes
+pub struct LeefFormatter {
+    host_name: Option<String>,
+    resolver: Rc<PathResolver>,
+}
+
+impl LeefFormatter {
+    pub fn new(host_name: Option<&str>, resolver: Rc<PathResolver>) -> Self {
+        Self {
+            host_name: host_name.map(|h| h.to_owned()),
+            resolver,
+        }
+    }
+}
+
+impl RecordWriter for LeefFormatter {
+    fn write_record(&mut self, out: &mut dyn Write, record: &CommonUsnRecord) -> io::Result<()> {
+        let data = &record.data;
+        let event_id = data.reason().primary_class()
+            .map(|c| c.to_string())
+            .unwrap_or_else(|| "none".to_owned());
+
+        let mut attributes = vec![
+            ("devTime", data.timestamp().format("%Y-%m-%d %H:%M:%S%.3f UTC").to_string()),
+            ("devTimeFormat", DEV_TIME_FORMAT.to_owned()),
+            ("cat", "file".to_owned()),
+            ("usn", data.usn().to_string()),
+            ("reason", data.reason().to_string()),
+            ("fileName", data.filename().to_owned()),
+            ("fileId", reference_to_string(data.file_reference())),
+            ("parentFileId", reference_to_string(data.parent_reference())),
+            ("fileAttributes", data.file_attributes().to_string()),
+        ];
+        if let Some(path) = self.resolver.full_path(record) {
+            attributes.push(("filePath", path));
+        }
+        if let Some(ref host_name) = self.host_name {
+            attributes.push(("identHostName", host_name.clone()));
+        }
+
+        writeln!(out, "LEEF:2.0|{}|{}|{}|{}|x09|{}",
+            header_value("usnjrnl"),
+            header_value(PRODUCT_NAME),
+            header_value(env!("CARGO_PKG_VERSION")),
+            header_value(&event_id),
+            attributes.iter()
+                .map(|(key, value)| format!("{}={}", key, attribute_value(value)))
+                .collect::<Vec<_>>()
+                .join("\t"))
+    }
+}
+
+/// LEEF has no escaping for the delimiter, so tabs and line breaks are replaced
+fn attribute_value(value: &str) -> String {
+    value.replace(['\t', '\r', '\n'], " ")
+}
@@ -0,0 +1,207 @@
+//! formatters, which write journal records in various output formats.
+//!
+//! Every output format implements [`RecordWriter`]. Applications can add
+//! their own formats by registering a factory in a [`FormatRegistry`]:
+//!
+//! ```
+//! use std::io::{self, Write};
+//! use usnjrnl::CommonUsnRecord;
+//! use usnjrnl::formatter::{FormatRegistry, RecordWriter};
+//!
+//! struct NameOnly;
+//!
+//! impl RecordWriter for NameOnly {
+//!     fn write_record(&mut self, out: &mut dyn Write, record: &CommonUsnRecord) -> io::Result<()> {
+//!         writeln!(out, "{}", record.data.filename())
+//!     }
+//! }
+//!
+//! let mut registry = FormatRegistry::default();
+//! registry.register("names", |_| Box::new(NameOnly));
+//! assert!(registry.names().any(|name| name == "names"));
+//! ```
+
+use std::io::{self, Write};
+use std::rc::Rc;
+use crate::{CommonUsnRecord, PathResolver, UsnReaderError, UsnReasonClass};
+pub use crate::DisplayTimezone;
+
+mod bodyfile;
+mod cef;
+mod elastic;
+mod json;
+mod l2tcsv;
+mod leef;
+mod syslog;
+mod table;
+mod timesketch;
+mod tln;
+
+pub use self::bodyfile::{BodyfileFormatter, MacbBodyfileFormatter};
+pub use self::cef::CefFormatter;
+pub use self::elastic::{ElasticFormatter, elastic_index_template};
+pub use self::json::JsonFormatter;
+pub use self::l2tcsv::L2tCsvFormatter;
+pub use self::leef::LeefFormatter;
+pub use self::syslog::SyslogFormatter;
+pub use self::table::TableFormatter;
+pub use self::timesketch::{TimesketchFormatter, TimesketchFormat};
+pub use self::tln::TlnFormatter;
+
+/// name of the product in SIEM formats, which matches the name of the binary
+const PRODUCT_NAME: &str = "usnjrnl_dump";
+
+/// writes journal records in a specific output format
+pub trait RecordWriter {
+    /// writes everything which must precede the first record, e.g. a header line
+    fn write_header(&mut self, _out: &mut dyn Write) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// writes a single record, including the trailing line break
+    fn write_record(&mut self, out: &mut dyn Write, record: &CommonUsnRecord) -> io::Result<()>;
+
+    /// writes everything which must follow the last record
+    fn finish(&mut self, _out: &mut dyn Write) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// settings which are passed to the factories of a [`FormatRegistry`]
+pub struct FormatOptions {
+    pub host_name: Option<String>,
+    pub user_name: Option<String>,
+    pub timezone: DisplayTimezone,
+    pub elastic_index: String,
+
+    /// maximum line width of the table output
+    pub width: Option<usize>,
+
+    /// colorize the table output
+    pub color: bool,
+    pub resolver: Rc<PathResolver>,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            host_name: None,
+            user_name: None,
+            timezone: DisplayTimezone::default(),
+            elastic_index: "usnjrnl".to_owned(),
+            width: None,
+            color: false,
+            resolver: Rc::new(PathResolver::default()),
+        }
+    }
+}
+
+type FormatFactory = Box<dyn Fn(&FormatOptions) -> Box<dyn RecordWriter>>;
+
+/// maps names of output formats to functions which create the matching
+/// [`RecordWriter`]. The default registry contains all builtin formats.
+pub struct FormatRegistry {
+    formats: Vec<(String, FormatFactory)>,
+}
+
+impl FormatRegistry {
+    /// creates a registry without any formats
+    pub fn empty() -> Self {
+        Self { formats: Vec::new() }
+    }
+
+    /// adds a format, or replaces the format with the same name
+    pub fn register<F>(&mut self, name: &str, factory: F)
+    where
+        F: Fn(&FormatOptions) -> Box<dyn RecordWriter> + 'static,
+    {
+        match self.formats.iter_mut().find(|(n, _)| n == name) {
+            Some(entry) => entry.1 = Box::new(factory),
+            None => self.formats.push((name.to_owned(), Box::new(factory))),
+        }
+    }
+
+    /// returns the names of all formats, in the order of registration
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.formats.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// creates a writer for the format `name`, if such a format exists
+    pub fn create(&self, name: &str, options: &FormatOptions) -> Option<Box<dyn RecordWriter>> {
+        self.formats.iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, factory)| factory(options))
+    }
+}
+
+impl Default for FormatRegistry {
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry.register("json", |_| Box::new(JsonFormatter{}));
+        registry.register("bodyfile", |_| Box::new(BodyfileFormatter{}));
+        registry.register("bodyfile-macb", |o| Box::new(MacbBodyfileFormatter::new(Rc::clone(&o.resolver))));
+        registry.register("elastic", |o| Box::new(ElasticFormatter::new(
+            &o.elastic_index, o.host_name.as_deref(), Rc::clone(&o.resolver))));
+        registry.register("timesketch", |o| Box::new(TimesketchFormatter::new(
+            TimesketchFormat::Jsonl, Rc::clone(&o.resolver))));
+        registry.register("timesketch-csv", |o| Box::new(TimesketchFormatter::new(
+            TimesketchFormat::Csv, Rc::clone(&o.resolver))));
+        registry.register("l2tcsv", |o| Box::new(L2tCsvFormatter::new(
+            o.host_name.as_deref(), o.user_name.as_deref(), o.timezone, Rc::clone(&o.resolver))));
+        registry.register("tln", |o| Box::new(TlnFormatter::new(
+            o.host_name.as_deref(), o.user_name.as_deref(), Rc::clone(&o.resolver))));
+        registry.register("table", |o| Box::new(TableFormatter::new(o.timezone, o.width, o.color)));
+        registry.register("syslog", |o| Box::new(SyslogFormatter::new(o.host_name.as_deref(), Rc::clone(&o.resolver))));
+        registry.register("cef", |o| Box::new(CefFormatter::new(o.host_name.as_deref(), Rc::clone(&o.resolver))));
+        registry.register("leef", |o| Box::new(LeefFormatter::new(o.host_name.as_deref(), Rc::clone(&o.resolver))));
+        registry
+    }
+}
+
+/// writes all `records` to `out`. Records which cannot be read are logged and skipped.
+pub fn write_records<I>(records: I, writer: &mut dyn RecordWriter, out: &mut dyn Write) -> io::Result<()>
+where
+    I: IntoIterator<Item = Result<CommonUsnRecord, UsnReaderError>>,
+{
+    writer.write_header(out)?;
+    for record in records {
+        match record {
+            Ok(record) => writer.write_record(out, &record)?,
+            Err(why) => log::error!("{}", why),
+        }
+    }
+    writer.finish(out)?;
+    out.flush()
+}
+
+/// quotes a CSV field if required, according to RFC 4180
+fn csv_escape(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
+
+/// describes the meaning of the timestamp, based on the most significant reason
+fn timestamp_desc(class: Option<UsnReasonClass>) -> &'static str {
+    match class {
+        Some(UsnReasonClass::Delete) => "Deletion Time",
+        Some(UsnReasonClass::Create) => "Creation Time",
+        Some(UsnReasonClass::Rename) => "Rename Time",
+        Some(UsnReasonClass::Data) => "Content Modification Time",
+        Some(UsnReasonClass::Metadata) => "Metadata Modification Time",
+        Some(UsnReasonClass::Close) | None => "Update Time",
+    }
+}
+
+/// replaces all occurrences of the field delimiter, because formats like
+/// l2tcsv and TLN do not support quoting
+fn sanitize(value: &str, delimiter: char) -> String {
+    value.replace(delimiter, " ")
+}
+
+/// escapes `\` and `|` in header fields of CEF and LEEF
+fn header_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('|', "\\|")
+}
@@ -0,0 +1,94 @@
+use std::io::{self, Write};
+use std::rc::Rc;
+use chrono::SecondsFormat;
+use crate::{CommonUsnRecord, PathResolver, UsnReasonClass};
+
+use super::{PRODUCT_NAME, RecordWriter};
+
+/// facility `local0`
+const FACILITY: u8 = 16;
+
+/// private enterprise number used for the structured data element. 32473 is
+/// reserved for documentation by RFC 5612.
+const ENTERPRISE_NUMBER: u32 = 32473;
+
+/// creates syslog messages according to RFC 5424. All record fields are
+/// contained in a structured data element, the message contains the filename
+/// and the reason.
+pub struct SyslogFormatter {
+    host_name: String,
+    resolver: Rc<PathResolver>,
+}
+
+impl SyslogFormatter {
+    pub fn new(host_name: Option<&str>, resolver: Rc<PathResolver>) -> Self {
+        Self {
+            host_name: host_name.map(header_field).unwrap_or_else(|| "-".to_owned()),
+            resolver,
+        }
+    }
+}
+
+impl RecordWriter for SyslogFormatter {
+    fn write_record(&mut self, out: &mut dyn Write, record: &CommonUsnRecord) -> io::Result<()> {
+        let data = &record.data;
+        let path = self.resolver.full_path(record);
+        let name = path.as_deref().unwrap_or_else(|| data.filename());
+
+        /* deletions are a bit more important than the rest */
+        let severity = match data.reason().primary_class() {
+            Some(UsnReasonClass::Delete) => 5,
+            _ => 6,
+        };
+
+        let mut params = vec![
+            ("usn", data.usn().to_string()),
+            ("reason", data.reason().to_string()),
+            ("filename", data.filename().to_owned()),
+            ("entry", data.file_reference().entry.to_string()),
+            ("seq", data.file_reference().sequence.to_string()),
+            ("parentEntry", data.parent_reference().entry.to_string()),
+            ("parentSeq", data.parent_reference().sequence.to_string()),
+            ("attributes", data.file_attributes().to_string()),
+            ("sourceInfo", data.source_info().to_string()),
+        ];
+        if let Some(ref path) = path {
+            params.push(("path", path.clone()));
+        }
+        let structured_data = params.iter()
+            .map(|(name, value)| format!(" {}=\"{}\"", name, param_value(value)))
+            .collect::<String>();
+
+        writeln!(out, "<{}>1 {} {} {} - USN [usnjrnl@{}{}] {}: {}",
+            FACILITY * 8 + severity,
+            data.timestamp().to_rfc3339_opts(SecondsFormat::Micros, true),
+            self.host_name,
+            PRODUCT_NAME,
+            ENTERPRISE_NUMBER,
+            structured_data,
+            message(name),
+            data.reason())
+    }
+}
+
+/// header fields must consist of printable ASCII characters without spaces
+fn header_field(value: &str) -> String {
+    value.chars().filter(|c| c.is_ascii_graphic()).collect()
+}
+
+/// escapes `"`, `\` and `]` in values of structured data parameters
+fn param_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '"' | '\\' | ']') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// every message must fit into a single line
+fn message(value: &str) -> String {
+    value.replace(['\r', '\n'], " ")
+}
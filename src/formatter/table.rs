@@ -0,0 +1,86 @@
+use std::io::{self, Write};
+use crate::{CommonUsnRecord, UsnReasonClass};
+
+use super::{DisplayTimezone, RecordWriter};
+
+const TIME_WIDTH: usize = 26;
+const USN_WIDTH: usize = 12;
+const REFERENCE_WIDTH: usize = 12;
+const REASON_WIDTH: usize = 40;
+const ATTRIBUTES_WIDTH: usize = 28;
+
+/// prints records as aligned columns, which is meant to be read by humans.
+///
+/// Columns are padded to a fixed minimum width, so that the output can be
+/// streamed. If a `width` is given, every line is truncated to it.
+pub struct TableFormatter {
+    timezone: DisplayTimezone,
+    width: Option<usize>,
+    color: bool,
+}
+
+impl TableFormatter {
+    pub fn new(timezone: DisplayTimezone, width: Option<usize>, color: bool) -> Self {
+        Self {
+            timezone,
+            width,
+            color,
+        }
+    }
+
+    fn truncate(&self, line: String) -> String {
+        match self.width {
+            Some(width) if line.chars().count() > width => {
+                let mut truncated: String = line.chars().take(width.saturating_sub(1)).collect();
+                truncated.push('…');
+                truncated
+            }
+            _ => line,
+        }
+    }
+}
+
+impl RecordWriter for TableFormatter {
+    fn write_header(&mut self, out: &mut dyn Write) -> io::Result<()> {
+        let line = format!("{:<tw$} {:>uw$} {:<rw$} {:<rsw$} {:<aw$} Filename",
+            format!("Time ({})", self.timezone),
+            "USN",
+            "Entry/Seq",
+            "Reason",
+            "Attributes",
+            tw = TIME_WIDTH, uw = USN_WIDTH, rw = REFERENCE_WIDTH,
+            rsw = REASON_WIDTH, aw = ATTRIBUTES_WIDTH);
+        writeln!(out, "{}", self.truncate(line))
+    }
+
+    fn write_record(&mut self, out: &mut dyn Write, record: &CommonUsnRecord) -> io::Result<()> {
+        let data = &record.data;
+        let line = format!("{:<tw$} {:>uw$} {:<rw$} {:<rsw$} {:<aw$} {}",
+            self.timezone.format(data.timestamp(), "%Y-%m-%d %H:%M:%S%.6f"),
+            data.usn(),
+            format!("{}/{}", data.file_reference().entry, data.file_reference().sequence),
+            data.reason().to_string(),
+            data.file_attributes().to_string(),
+            data.filename(),
+            tw = TIME_WIDTH, uw = USN_WIDTH, rw = REFERENCE_WIDTH,
+            rsw = REASON_WIDTH, aw = ATTRIBUTES_WIDTH);
+        let line = self.truncate(line);
+
+        /* colorize after truncation, because escape sequences have no width */
+        match self.color.then(|| ansi_color(data.reason().primary_class())).flatten() {
+            Some(color) => writeln!(out, "\x1b[{}m{}\x1b[0m", color, line),
+            None => writeln!(out, "{}", line),
+        }
+    }
+}
+
+/// returns the ANSI color code for the most significant reason of a record
+fn ansi_color(class: Option<UsnReasonClass>) -> Option<u8> {
+    match class {
+        Some(UsnReasonClass::Delete) => Some(31),
+        Some(UsnReasonClass::Create) => Some(32),
+        Some(UsnReasonClass::Rename) => Some(33),
+        Some(UsnReasonClass::Data) => Some(36),
+        _ => None,
+    }
+}
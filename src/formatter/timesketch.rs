@@ -0,0 +1,102 @@
+use std::io::{self, Write};
+use std::rc::Rc;
+use chrono::SecondsFormat;
+use serde_json::{json, Map, Value};
+use crate::{CommonUsnRecord, PathResolver};
+
+use super::{RecordWriter, csv_escape, timestamp_desc};
+
+/// columns of the CSV format, in the order in which they are written
+const CSV_COLUMNS: [&str; 16] = [
+    "message", "datetime", "timestamp", "timestamp_desc", "data_type",
+    "usn", "filename", "path", "reason", "file_attributes",
+    "file_entry", "file_sequence", "parent_entry", "parent_sequence",
+    "source_info", "security_id",
+];
+
+#[derive(Clone, Copy)]
+pub enum TimesketchFormat {
+    Jsonl,
+    Csv,
+}
+
+/// creates events which can be imported into Timesketch, either as JSON lines
+/// or as CSV
+pub struct TimesketchFormatter {
+    format: TimesketchFormat,
+    resolver: Rc<PathResolver>,
+}
+
+impl TimesketchFormatter {
+    pub fn new(format: TimesketchFormat, resolver: Rc<PathResolver>) -> Self {
+        Self {
+            format,
+            resolver,
+        }
+    }
+
+    fn event(&self, record: &CommonUsnRecord) -> Map<String, Value> {
+        let data = &record.data;
+        let path = self.resolver.full_path(record);
+        let reason = data.reason();
+        let attributes = data.file_attributes();
+
+        let message = format!("{} [{}] attributes: {}",
+            path.as_deref().unwrap_or_else(|| data.filename()),
+            reason,
+            attributes);
+
+        let event = json!({
+            "message": message,
+            "datetime": data.timestamp().to_rfc3339_opts(SecondsFormat::Micros, true),
+            "timestamp": data.timestamp().timestamp_micros(),
+            "timestamp_desc": timestamp_desc(reason.primary_class()),
+            "data_type": "fs:ntfs:usn_change",
+            "usn": data.usn(),
+            "filename": data.filename(),
+            "path": path,
+            "reason": reason.to_string(),
+            "file_attributes": attributes.to_string(),
+            "file_entry": data.file_reference().entry,
+            "file_sequence": data.file_reference().sequence,
+            "parent_entry": data.parent_reference().entry,
+            "parent_sequence": data.parent_reference().sequence,
+            "source_info": data.source_info(),
+            "security_id": data.security_id(),
+        });
+
+        match event {
+            Value::Object(map) => map,
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl RecordWriter for TimesketchFormatter {
+    fn write_header(&mut self, out: &mut dyn Write) -> io::Result<()> {
+        match self.format {
+            TimesketchFormat::Jsonl => Ok(()),
+            TimesketchFormat::Csv => writeln!(out, "{}", CSV_COLUMNS.join(",")),
+        }
+    }
+
+    fn write_record(&mut self, out: &mut dyn Write, record: &CommonUsnRecord) -> io::Result<()> {
+        let mut event = self.event(record);
+        match self.format {
+            TimesketchFormat::Jsonl => {
+                if event["path"].is_null() {
+                    event.remove("path");
+                }
+                writeln!(out, "{}", Value::Object(event))
+            }
+            TimesketchFormat::Csv => {
+                let line = CSV_COLUMNS.iter().map(|column| match &event[*column] {
+                    Value::String(s) => csv_escape(s),
+                    Value::Null => String::new(),
+                    v => v.to_string(),
+                }).collect::<Vec<_>>().join(",");
+                writeln!(out, "{}", line)
+            }
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use std::io::{self, Write};
+use std::rc::Rc;
+use crate::{CommonUsnRecord, PathResolver};
+
+use super::{RecordWriter, sanitize};
+
+/// creates the five-field TLN format (`Time|Source|Host|User|Description`).
+/// TLN always uses Unix time in UTC, so there is no timezone handling.
+pub struct TlnFormatter {
+    host_name: String,
+    user_name: String,
+    resolver: Rc<PathResolver>,
+}
+
+impl TlnFormatter {
+    pub fn new(host_name: Option<&str>, user_name: Option<&str>, resolver: Rc<PathResolver>) -> Self {
+        Self {
+            host_name: host_name.unwrap_or_default().to_owned(),
+            user_name: user_name.unwrap_or_default().to_owned(),
+            resolver,
+        }
+    }
+}
+
+impl RecordWriter for TlnFormatter {
+    fn write_record(&mut self, out: &mut dyn Write, record: &CommonUsnRecord) -> io::Result<()> {
+        let data = &record.data;
+        let path = self.resolver.full_path(record);
+        let description = format!("{}: {} (FileRef: {}/{}, USN: {})",
+            path.as_deref().unwrap_or_else(|| data.filename()),
+            data.reason(),
+            data.file_reference().entry,
+            data.file_reference().sequence,
+            data.usn());
+
+        let fields = [
+            data.timestamp().timestamp().to_string(),
+            "USN".to_owned(),
+            self.host_name.clone(),
+            self.user_name.clone(),
+            description,
+        ];
+        writeln!(out, "{}", fields.iter().map(|field| sanitize(field, '|')).collect::<Vec<_>>().join("|"))
+    }
+}
@@ -0,0 +1,381 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use chrono::{DateTime, Duration, Utc};
+use strum::IntoEnumIterator;
+use winstructs::ntfs::mft_reference::MftReference;
+
+use crate::{CommonUsnRecord, PathResolver, UsnReasonClass};
+use crate::analysis::window_start;
+use crate::path_resolver::join_path;
+#[cfg(feature = "formatters")]
+use crate::serialize::{reference_to_string, timestamp_to_string};
+
+/// index of the root directory in the arena of nodes
+const ROOT_NODE: usize = 0;
+
+/// index of the node, which collects directories whose path cannot be resolved
+const UNRESOLVED_NODE: usize = 1;
+
+pub struct HeatmapSettings {
+    /// length of a time bucket
+    pub bucket: Duration,
+
+    /// classes of reasons, which are counted
+    pub classes: Vec<UsnReasonClass>,
+}
+
+impl Default for HeatmapSettings {
+    fn default() -> Self {
+        Self {
+            bucket: Duration::minutes(1),
+            classes: UsnReasonClass::iter().filter(|c| *c != UsnReasonClass::Close).collect(),
+        }
+    }
+}
+
+/// number of records per reason class. A record is counted once for every class of its reasons.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ClassCounts(BTreeMap<UsnReasonClass, u64>);
+
+impl ClassCounts {
+    pub fn add(&mut self, class: UsnReasonClass, count: u64) {
+        *self.0.entry(class).or_insert(0) += count;
+    }
+
+    pub fn merge(&mut self, other: &ClassCounts) {
+        for (class, count) in other.iter() {
+            self.add(class, count);
+        }
+    }
+
+    pub fn get(&self, class: UsnReasonClass) -> u64 {
+        self.0.get(&class).copied().unwrap_or(0)
+    }
+
+    pub fn total(&self) -> u64 {
+        self.0.values().sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// returns all classes with their counts, the most significant class first
+    pub fn iter(&self) -> impl Iterator<Item=(UsnReasonClass, u64)> + '_ {
+        self.0.iter().map(|(class, count)| (*class, *count))
+    }
+
+    #[cfg(feature = "formatters")]
+    pub fn to_json(&self) -> serde_json::Value {
+        self.iter().map(|(class, count)| (class.to_string(), serde_json::Value::from(count))).collect()
+    }
+}
+
+impl fmt::Display for ClassCounts {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let counts: Vec<_> = self.iter().map(|(class, count)| format!("{} {}", class, count)).collect();
+        write!(f, "{}", counts.join(", "))
+    }
+}
+
+/// the activity in a directory and all its subdirectories
+#[derive(Clone, Debug)]
+pub struct DirectoryNode {
+    /// `None` for the root directory and for the node, which collects
+    /// directories whose path cannot be resolved
+    pub reference: Option<MftReference>,
+    pub name: String,
+
+    /// the path of the directory, which starts with `?` if it cannot be resolved up to the root directory
+    pub path: String,
+
+    /// records of files directly in this directory
+    pub own: ClassCounts,
+
+    /// records of files in this directory and all its subdirectories
+    pub total: ClassCounts,
+
+    /// `total`, split into time buckets
+    pub buckets: BTreeMap<DateTime<Utc>, ClassCounts>,
+
+    /// subdirectories, the most active one first
+    pub children: Vec<DirectoryNode>,
+}
+
+impl DirectoryNode {
+    fn new(reference: Option<MftReference>, name: String, path: String) -> Self {
+        Self {
+            reference,
+            name,
+            path,
+            own: ClassCounts::default(),
+            total: ClassCounts::default(),
+            buckets: BTreeMap::new(),
+            children: Vec::new(),
+        }
+    }
+
+    /// returns the time bucket with the most records
+    pub fn peak(&self) -> Option<(&DateTime<Utc>, &ClassCounts)> {
+        self.buckets.iter().rev().max_by_key(|(_, counts)| counts.total())
+    }
+
+    /// removes all subdirectories with less than `min_total` records
+    pub fn prune(&mut self, min_total: u64) {
+        self.children.retain(|child| child.total.total() >= min_total);
+        for child in self.children.iter_mut() {
+            child.prune(min_total);
+        }
+    }
+
+    /// removes all subdirectories, which are more than `depth` levels below this directory
+    pub fn truncate(&mut self, depth: usize) {
+        if depth == 0 {
+            self.children.clear();
+        }
+        for child in self.children.iter_mut() {
+            child.truncate(depth - 1);
+        }
+    }
+
+    /// returns this directory and all its subdirectories, depth first, together with their depth
+    pub fn iter(&self) -> impl Iterator<Item=(usize, &DirectoryNode)> {
+        let mut stack = vec![(0, self)];
+        std::iter::from_fn(move || {
+            let (depth, node) = stack.pop()?;
+            stack.extend(node.children.iter().rev().map(|child| (depth + 1, child)));
+            Some((depth, node))
+        })
+    }
+
+    /// returns the tree as JSON, which can be used as is for treemap and sunburst
+    /// views: `value` is the number of records directly in the directory
+    #[cfg(feature = "formatters")]
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": self.name,
+            "path": self.path,
+            "reference": self.reference.as_ref().map(reference_to_string),
+            "value": self.own.total(),
+            "total": self.total.total(),
+            "own": self.own.to_json(),
+            "classes": self.total.to_json(),
+            "buckets": self.buckets.iter().map(|(start, counts)| serde_json::json!({
+                "start": timestamp_to_string(start),
+                "classes": counts.to_json(),
+            })).collect::<Vec<_>>(),
+            "children": self.children.iter().map(DirectoryNode::to_json).collect::<Vec<_>>(),
+        })
+    }
+}
+
+/// the records of files directly in one directory in one time bucket
+#[derive(Clone, Debug)]
+pub struct Hotspot {
+    pub reference: MftReference,
+    pub path: String,
+    pub start: DateTime<Utc>,
+    pub counts: ClassCounts,
+}
+
+impl Hotspot {
+    #[cfg(feature = "formatters")]
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "reference": reference_to_string(&self.reference),
+            "path": self.path,
+            "start": timestamp_to_string(&self.start),
+            "total": self.counts.total(),
+            "classes": self.counts.to_json(),
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Heatmap {
+    pub bucket: Duration,
+
+    /// the root directory of the volume
+    pub root: DirectoryNode,
+
+    /// directories, whose path cannot be resolved up to the root directory
+    pub unresolved: DirectoryNode,
+
+    /// all directories in all time buckets, the most active one first
+    pub hotspots: Vec<Hotspot>,
+}
+
+impl Heatmap {
+    /// removes all directories with less than `min_total` records
+    pub fn prune(&mut self, min_total: u64) {
+        self.root.prune(min_total);
+        self.unresolved.prune(min_total);
+        self.hotspots.retain(|h| h.counts.total() >= min_total);
+    }
+
+    #[cfg(feature = "formatters")]
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "bucket_seconds": self.bucket.num_seconds(),
+            "root": self.root.to_json(),
+            "unresolved": self.unresolved.to_json(),
+            "hotspots": self.hotspots.iter().map(Hotspot::to_json).collect::<Vec<_>>(),
+        })
+    }
+}
+
+/// a directory while the tree is being built
+struct Node {
+    data: DirectoryNode,
+    parent: Option<usize>,
+    children: Vec<usize>,
+}
+
+/// counts the activity per reason class, directory and time bucket, and rolls
+/// it up the directory tree.
+///
+/// Records are assigned to the directory, which is given by their parent
+/// reference. Directories are arranged using the names and parents, which are
+/// found in the journal; a directory, whose path cannot be resolved up to the
+/// root directory, is placed below a `?` node.
+///
+/// ```rust,no_run
+/// use std::path::PathBuf;
+/// use usnjrnl::{HeatmapCollector, HeatmapSettings, UsnJrnlReader};
+///
+/// let mut collector = HeatmapCollector::new(HeatmapSettings::default());
+/// for record in UsnJrnlReader::from(&PathBuf::from("$UsnJrnl:$J")).unwrap().into_iter().flatten() {
+///     collector.add(&record);
+/// }
+/// let heatmap = collector.finish();
+/// for hotspot in heatmap.hotspots.iter().take(10) {
+///     println!("{} {}: {}", hotspot.start, hotspot.path, hotspot.counts);
+/// }
+/// ```
+pub struct HeatmapCollector {
+    settings: HeatmapSettings,
+    resolver: PathResolver,
+    directories: HashMap<MftReference, BTreeMap<DateTime<Utc>, ClassCounts>>,
+}
+
+impl HeatmapCollector {
+    pub fn new(settings: HeatmapSettings) -> Self {
+        Self {
+            settings,
+            resolver: PathResolver::default(),
+            directories: HashMap::new(),
+        }
+    }
+
+    pub fn add(&mut self, record: &CommonUsnRecord) {
+        let data = &record.data;
+        self.resolver.learn(record);
+
+        let classes: Vec<_> = data.reason().classes().into_iter()
+            .filter(|c| self.settings.classes.contains(c))
+            .collect();
+        if classes.is_empty() {
+            return;
+        }
+        let start = window_start(data.timestamp(), self.settings.bucket);
+        let counts = self.directories.entry(*data.parent_reference()).or_default().entry(start).or_default();
+        for class in classes {
+            counts.add(class, 1);
+        }
+    }
+
+    pub fn finish(self) -> Heatmap {
+        let mut tree = Tree {
+            resolver: &self.resolver,
+            nodes: vec![
+                Node { data: DirectoryNode::new(None, String::new(), "/".to_owned()), parent: None, children: Vec::new() },
+                Node { data: DirectoryNode::new(None, "?".to_owned(), "?".to_owned()), parent: None, children: Vec::new() },
+            ],
+            index: HashMap::new(),
+        };
+
+        let mut hotspots = Vec::new();
+        for (reference, buckets) in &self.directories {
+            let directory = tree.node_of(reference);
+            for (start, counts) in buckets {
+                tree.nodes[directory].data.own.merge(counts);
+                let mut current = Some(directory);
+                while let Some(index) = current {
+                    let node = &mut tree.nodes[index].data;
+                    node.total.merge(counts);
+                    node.buckets.entry(*start).or_default().merge(counts);
+                    current = tree.nodes[index].parent;
+                }
+                hotspots.push(Hotspot {
+                    reference: *reference,
+                    path: tree.nodes[directory].data.path.clone(),
+                    start: *start,
+                    counts: counts.clone(),
+                });
+            }
+        }
+        hotspots.sort_by(|a, b| b.counts.total().cmp(&a.counts.total())
+            .then_with(|| a.start.cmp(&b.start))
+            .then_with(|| a.path.cmp(&b.path)));
+
+        let mut nodes: Vec<_> = tree.nodes.into_iter().map(Some).collect();
+        let root = build_tree(&mut nodes, ROOT_NODE);
+        let unresolved = build_tree(&mut nodes, UNRESOLVED_NODE);
+        Heatmap {
+            bucket: self.settings.bucket,
+            root,
+            unresolved,
+            hotspots,
+        }
+    }
+}
+
+/// the directories, while they are arranged in a tree
+struct Tree<'a> {
+    resolver: &'a PathResolver,
+    nodes: Vec<Node>,
+    index: HashMap<MftReference, usize>,
+}
+
+impl Tree<'_> {
+    /// returns the node of a directory, creating it and its ancestors if needed
+    fn node_of(&mut self, reference: &MftReference) -> usize {
+        let (chain, complete) = self.resolver.ancestor_chain(reference);
+        let mut parent = if complete { ROOT_NODE } else { UNRESOLVED_NODE };
+        let mut missing = Vec::new();
+        for (reference, name) in chain {
+            if let Some(index) = self.index.get(&reference) {
+                parent = *index;
+                break;
+            }
+            missing.push((reference, name));
+        }
+
+        for (reference, name) in missing.into_iter().rev() {
+            let name = match name {
+                Some(name) => name.to_owned(),
+                None => format!("[{}-{}]", reference.entry, reference.sequence),
+            };
+            let path = join_path(&self.nodes[parent].data.path, &name);
+            self.nodes.push(Node {
+                data: DirectoryNode::new(Some(reference), name, path),
+                parent: Some(parent),
+                children: Vec::new(),
+            });
+            let index = self.nodes.len() - 1;
+            self.nodes[parent].children.push(index);
+            self.index.insert(reference, index);
+            parent = index;
+        }
+        parent
+    }
+}
+
+/// moves the node at `index` and all its descendants out of the arena
+fn build_tree(nodes: &mut [Option<Node>], index: usize) -> DirectoryNode {
+    let node = nodes[index].take().unwrap();
+    let mut data = node.data;
+    data.children = node.children.into_iter().map(|child| build_tree(nodes, child)).collect();
+    data.children.sort_by(|a, b| b.total.total().cmp(&a.total.total()).then_with(|| a.name.cmp(&b.name)));
+    data
+}
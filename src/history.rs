@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+use std::fmt;
+use chrono::{DateTime, Utc};
+use winstructs::ntfs::mft_reference::MftReference;
+
+use crate::filter::EntryReference;
+use crate::open_files::OpenFiles;
+use crate::{CommonUsnRecord, UsnReasonClass, UsnReasonValue};
+
+/// something which happened to a file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FileAction {
+    Created,
+    Renamed {
+        old_name: Option<String>,
+        new_name: String,
+    },
+    Written(Vec<UsnReasonValue>),
+    AttributesChanged(Vec<UsnReasonValue>),
+    Deleted,
+}
+
+impl fmt::Display for FileAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let flags = |flags: &[UsnReasonValue]| flags.iter()
+            .map(|f| f.short_name())
+            .collect::<Vec<_>>()
+            .join("+");
+        match self {
+            Self::Created => write!(f, "created"),
+            Self::Renamed { old_name: Some(old_name), new_name } => write!(f, "renamed: {} -> {}", old_name, new_name),
+            Self::Renamed { old_name: None, new_name } => write!(f, "renamed: ? -> {}", new_name),
+            Self::Written(reasons) => write!(f, "written ({})", flags(reasons)),
+            Self::AttributesChanged(reasons) => write!(f, "attributes changed ({})", flags(reasons)),
+            Self::Deleted => write!(f, "deleted"),
+        }
+    }
+}
+
+/// an action, together with the record which revealed it
+#[derive(Clone, Debug)]
+pub struct FileEvent {
+    pub timestamp: DateTime<Utc>,
+    pub usn: i64,
+    pub file_reference: MftReference,
+    pub parent_reference: MftReference,
+    pub name: String,
+    pub action: FileAction,
+}
+
+/// collects everything which happened to a single MFT entry.
+///
+/// Because reason flags accumulate until the file is closed, only flags
+/// which have not been set in the previous record of the same file result
+/// in an event. If no sequence number is given, the history contains all
+/// files which used the MFT entry, which can be told apart by the sequence
+/// number of their `file_reference`.
+pub struct FileHistory {
+    reference: EntryReference,
+    open_files: OpenFiles,
+    old_names: HashMap<MftReference, String>,
+    events: Vec<FileEvent>,
+}
+
+impl FileHistory {
+    pub fn new(reference: EntryReference) -> Self {
+        Self {
+            reference,
+            open_files: OpenFiles::default(),
+            old_names: HashMap::new(),
+            events: Vec::new(),
+        }
+    }
+
+    /// adds the events revealed by `record`, if it belongs to the file of
+    /// this history. Records must be added in the order of their USN.
+    pub fn add(&mut self, record: &CommonUsnRecord) {
+        let data = &record.data;
+        let file_reference = *data.file_reference();
+        if !self.reference.matches(&file_reference) {
+            return;
+        }
+
+        let reason = data.reason();
+        let new_reasons = self.open_files.new_reasons(record);
+
+        let mut actions = Vec::new();
+        if new_reasons.has_flag(UsnReasonValue::USN_REASON_FILE_CREATE) {
+            actions.push(FileAction::Created);
+        }
+
+        /* the old name and the new name are reported in separate records */
+        if reason.has_flag(UsnReasonValue::USN_REASON_RENAME_OLD_NAME) {
+            self.old_names.insert(file_reference, data.filename().to_owned());
+        } else if new_reasons.has_flag(UsnReasonValue::USN_REASON_RENAME_NEW_NAME) {
+            actions.push(FileAction::Renamed {
+                old_name: self.old_names.remove(&file_reference),
+                new_name: data.filename().to_owned(),
+            });
+        }
+
+        let data_changes: Vec<_> = new_reasons.flags().filter(|f| f.class() == UsnReasonClass::Data).collect();
+        if !data_changes.is_empty() {
+            actions.push(FileAction::Written(data_changes));
+        }
+        let metadata_changes: Vec<_> = new_reasons.flags().filter(|f| f.class() == UsnReasonClass::Metadata).collect();
+        if !metadata_changes.is_empty() {
+            actions.push(FileAction::AttributesChanged(metadata_changes));
+        }
+        if new_reasons.has_flag(UsnReasonValue::USN_REASON_FILE_DELETE) {
+            actions.push(FileAction::Deleted);
+        }
+
+        for action in actions {
+            self.events.push(FileEvent {
+                timestamp: *data.timestamp(),
+                usn: data.usn(),
+                file_reference,
+                parent_reference: *data.parent_reference(),
+                name: data.filename().to_owned(),
+                action,
+            });
+        }
+    }
+
+    /// returns all events, in the order in which they happened
+    pub fn events(&self) -> &[FileEvent] {
+        &self.events
+    }
+}
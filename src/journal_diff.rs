@@ -0,0 +1,247 @@
+use std::cmp::Ordering;
+
+use crate::analysis::RecordRef;
+use crate::open_files::OpenFiles;
+use crate::rename::RenameTracker;
+use crate::{CommonUsnRecord, Rename, UsnJournalMax, UsnReasonValue};
+#[cfg(feature = "formatters")]
+use crate::serialize::{reference_to_string, timestamp_to_string};
+
+/// the number of records in a range of a journal, together with the first and the last of them
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct JournalSpan {
+    pub records: usize,
+    pub first: Option<RecordRef>,
+    pub last: Option<RecordRef>,
+}
+
+impl JournalSpan {
+    fn add(&mut self, record: &RecordRef) {
+        self.records += 1;
+        if self.first.is_none() {
+            self.first = Some(record.clone());
+        }
+        self.last = Some(record.clone());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records == 0
+    }
+
+    #[cfg(feature = "formatters")]
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "records": self.records,
+            "first_usn": self.first.as_ref().map(|r| r.usn),
+            "last_usn": self.last.as_ref().map(|r| r.usn),
+            "first_timestamp": self.first.as_ref().map(|r| timestamp_to_string(&r.timestamp)),
+            "last_timestamp": self.last.as_ref().map(|r| timestamp_to_string(&r.timestamp)),
+        })
+    }
+}
+
+/// compares two journals of the same volume, like a baseline and a later snapshot.
+///
+/// Both journals are lined up by their USN, which is the same for the same
+/// record in both journals as long as the journal has not been deleted and
+/// created again. The journal ids in `$UsnJrnl:$Max` tell if this happened.
+///
+/// ```rust,no_run
+/// use std::path::PathBuf;
+/// use usnjrnl::{JournalDiff, UsnJrnlReader};
+///
+/// let a = UsnJrnlReader::from(&PathBuf::from("baseline/$UsnJrnl:$J")).unwrap();
+/// let b = UsnJrnlReader::from(&PathBuf::from("incident/$UsnJrnl:$J")).unwrap();
+/// let diff = JournalDiff::compare(a.into_iter().flatten(), b.into_iter().flatten());
+/// println!("{} records are only in the second journal", diff.new_records.records);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct JournalDiff {
+    pub journal_id_a: Option<u64>,
+    pub journal_id_b: Option<u64>,
+
+    pub a: JournalSpan,
+    pub b: JournalSpan,
+
+    /// records of A which are older than the oldest record of B, so they have
+    /// been overwritten since A was taken
+    pub overwritten: JournalSpan,
+
+    /// records which are in both journals
+    pub common_records: usize,
+
+    /// records with the same USN but a different content, as pairs of the record in A and the record in B
+    pub differing_records: Vec<(RecordRef, RecordRef)>,
+
+    /// records in the common range of USNs, which are missing in one of the journals
+    pub missing_in_a: Vec<RecordRef>,
+    pub missing_in_b: Vec<RecordRef>,
+
+    /// records of A which are newer than the newest record of B, so B might be older than A
+    pub newer_in_a: JournalSpan,
+
+    /// records of B which are newer than the newest record of A
+    pub new_records: JournalSpan,
+
+    /// files which have been created, deleted or renamed in the new records
+    pub created: Vec<RecordRef>,
+    pub deleted: Vec<RecordRef>,
+    pub renamed: Vec<Rename>,
+}
+
+impl JournalDiff {
+    /// compares the records of journal `a` with the records of journal `b`,
+    /// which must both be ordered by USN
+    pub fn compare<A, B>(a: A, b: B) -> Self
+    where
+        A: IntoIterator<Item=CommonUsnRecord>,
+        B: IntoIterator<Item=CommonUsnRecord>,
+    {
+        Self::compare_journals(a, None, b, None)
+    }
+
+    /// compares the records of two journals like [`JournalDiff::compare`]. If
+    /// the journal ids differ, the journal has been created again between both
+    /// snapshots, so that all records of `b` are new.
+    pub fn compare_journals<A, B>(a: A, max_a: Option<&UsnJournalMax>, b: B, max_b: Option<&UsnJournalMax>) -> Self
+    where
+        A: IntoIterator<Item=CommonUsnRecord>,
+        B: IntoIterator<Item=CommonUsnRecord>,
+    {
+        let mut diff = Self {
+            journal_id_a: max_a.map(|m| m.usn_journal_id),
+            journal_id_b: max_b.map(|m| m.usn_journal_id),
+            ..Default::default()
+        };
+        let recreated = diff.journal_recreated();
+        let mut changes = FileChanges::default();
+        let mut a = a.into_iter().peekable();
+        let mut b = b.into_iter().peekable();
+        loop {
+            let order = match (a.peek(), b.peek()) {
+                (None, None) => break,
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(_), Some(_)) if recreated => Ordering::Less,
+                (Some(ra), Some(rb)) => ra.data.usn().cmp(&rb.data.usn()),
+            };
+            match order {
+                Ordering::Less => {
+                    let record = RecordRef::from(&a.next().unwrap());
+                    diff.a.add(&record);
+                    if b.peek().is_none() && !diff.b.is_empty() {
+                        diff.newer_in_a.add(&record);
+                    } else if diff.b.is_empty() {
+                        diff.overwritten.add(&record);
+                    } else {
+                        diff.missing_in_b.push(record);
+                    }
+                }
+                Ordering::Greater => {
+                    let record = b.next().unwrap();
+                    let record_ref = RecordRef::from(&record);
+                    diff.b.add(&record_ref);
+                    if a.peek().is_none() {
+                        diff.new_records.add(&record_ref);
+                        changes.add(&record, Some(&mut diff));
+                    } else {
+                        changes.add(&record, None);
+                        diff.missing_in_a.push(record_ref);
+                    }
+                }
+                Ordering::Equal => {
+                    let record_a = a.next().unwrap();
+                    let record_b = b.next().unwrap();
+                    let (ref_a, ref_b) = (RecordRef::from(&record_a), RecordRef::from(&record_b));
+                    diff.a.add(&ref_a);
+                    diff.b.add(&ref_b);
+                    diff.common_records += 1;
+                    changes.add(&record_b, None);
+                    if ref_a != ref_b
+                        || record_a.data.reason() != record_b.data.reason()
+                        || record_a.data.file_attributes() != record_b.data.file_attributes() {
+                        diff.differing_records.push((ref_a, ref_b));
+                    }
+                }
+            }
+        }
+        diff
+    }
+
+    /// returns `true` if both journal ids are known and differ
+    pub fn journal_recreated(&self) -> bool {
+        matches!((self.journal_id_a, self.journal_id_b), (Some(a), Some(b)) if a != b)
+    }
+
+    /// returns `true` if B starts after the end of A, so that the records in between are lost
+    pub fn has_gap(&self) -> bool {
+        !self.journal_recreated() && !self.a.is_empty() && self.common_records == 0
+            && self.overwritten.records == self.a.records && !self.b.is_empty()
+    }
+
+    /// returns `true` if the records, which are in both journals, are identical
+    pub fn is_consistent(&self) -> bool {
+        self.differing_records.is_empty() && self.missing_in_a.is_empty()
+            && self.missing_in_b.is_empty() && self.newer_in_a.is_empty()
+    }
+
+    #[cfg(feature = "formatters")]
+    pub fn to_json(&self) -> serde_json::Value {
+        let records = |records: &[RecordRef]| records.iter().map(RecordRef::to_json).collect::<Vec<_>>();
+        serde_json::json!({
+            "journal_id_a": self.journal_id_a,
+            "journal_id_b": self.journal_id_b,
+            "journal_recreated": self.journal_recreated(),
+            "a": self.a.to_json(),
+            "b": self.b.to_json(),
+            "overwritten": self.overwritten.to_json(),
+            "gap": self.has_gap(),
+            "common_records": self.common_records,
+            "consistent": self.is_consistent(),
+            "differing_records": self.differing_records.iter().map(|(a, b)| serde_json::json!({
+                "a": a.to_json(),
+                "b": b.to_json(),
+            })).collect::<Vec<_>>(),
+            "missing_in_a": records(&self.missing_in_a),
+            "missing_in_b": records(&self.missing_in_b),
+            "newer_in_a": self.newer_in_a.to_json(),
+            "new_records": self.new_records.to_json(),
+            "created": records(&self.created),
+            "deleted": records(&self.deleted),
+            "renamed": self.renamed.iter().map(|rename| serde_json::json!({
+                "file_reference": reference_to_string(&rename.file_reference),
+                "old_name": rename.old_name,
+                "old_parent": rename.old_parent.as_ref().map(reference_to_string),
+                "new_name": rename.new_name,
+                "new_parent": reference_to_string(&rename.new_parent),
+                "timestamp": timestamp_to_string(&rename.timestamp),
+                "usn": rename.usn,
+            })).collect::<Vec<_>>(),
+        })
+    }
+}
+
+/// follows the files in B, to tell which of them have been created, deleted or renamed in the new records
+#[derive(Default)]
+struct FileChanges {
+    open_files: OpenFiles,
+    renames: RenameTracker,
+}
+
+impl FileChanges {
+    fn add(&mut self, record: &CommonUsnRecord, diff: Option<&mut JournalDiff>) {
+        let new_reasons = self.open_files.new_reasons(record);
+        let rename = self.renames.add(record);
+        if let Some(diff) = diff {
+            if new_reasons.has_flag(UsnReasonValue::USN_REASON_FILE_CREATE) {
+                diff.created.push(RecordRef::from(record));
+            }
+            if new_reasons.has_flag(UsnReasonValue::USN_REASON_FILE_DELETE) {
+                diff.deleted.push(RecordRef::from(record));
+            }
+            if let Some(rename) = rename {
+                diff.renamed.push(rename);
+            }
+        }
+    }
+}
@@ -1,5 +1,5 @@
 mod usnjrnl_reader;
-pub use usnjrnl_reader::UsnJrnlReader;
+pub use usnjrnl_reader::{UsnJrnlReader, UsrJrnlIterator, ReadSeek, PAGE_SIZE};
 
 mod usn_record;
 pub use usn_record::{CommonUsnRecord, UsnRecordData};
@@ -7,4 +7,47 @@ pub use usn_record::{CommonUsnRecord, UsnRecordData};
 mod usn_reader_error;
 pub use usn_reader_error::UsnReaderError;
 
-mod usn_reason;
\ No newline at end of file
+mod usn_reason;
+pub use usn_reason::{UsnReason, UsnReasonValue, UsnReasonClass};
+
+mod file_attributes;
+pub use file_attributes::{FileAttributes, FileAttributeValue};
+
+mod usn_journal_max;
+pub use usn_journal_max::UsnJournalMax;
+
+mod open_files;
+
+mod path_resolver;
+pub use path_resolver::PathResolver;
+
+mod history;
+pub use history::{FileAction, FileEvent, FileHistory};
+
+mod lifecycle;
+pub use lifecycle::{FileLifecycle, LifecycleEngine, Lifecycles, Operation, OperationCollector};
+
+mod rename;
+pub use rename::{Rename, RenameChain, RenameChains, RenameTracker, Renames};
+
+mod journal_diff;
+pub use journal_diff::{JournalDiff, JournalSpan};
+
+mod timezone;
+pub use timezone::DisplayTimezone;
+
+mod heatmap;
+pub use heatmap::{ClassCounts, DirectoryNode, Heatmap, HeatmapCollector, HeatmapSettings, Hotspot};
+
+pub mod filter;
+
+pub mod analysis;
+
+#[cfg(feature = "rules")]
+pub mod rules;
+
+#[cfg(feature = "formatters")]
+pub mod formatter;
+
+#[cfg(feature = "formatters")]
+pub mod serialize;
@@ -0,0 +1,327 @@
+use std::collections::HashMap;
+use chrono::{DateTime, Utc};
+use winstructs::ntfs::mft_reference::MftReference;
+
+use crate::filter::{EntryReference, ntfs_upcase};
+use crate::{CommonUsnRecord, UsnReason, UsnReasonClass, UsnReasonValue};
+#[cfg(feature = "formatters")]
+use crate::serialize::{reference_to_string, timestamp_to_string};
+
+/// all records of a file between opening and closing it.
+///
+/// The reason flags of a file accumulate until the file is closed, so the
+/// reasons of an operation are the reasons of its last record. If the journal
+/// ends before the file is closed, the operation is not `closed`.
+#[derive(Clone, Debug)]
+pub struct Operation {
+    pub file_reference: MftReference,
+    pub reasons: UsnReason,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub first_usn: i64,
+    pub last_usn: i64,
+    pub records: usize,
+    pub name_before: String,
+    pub name_after: String,
+    pub parent_before: MftReference,
+    pub parent_after: MftReference,
+    pub closed: bool,
+}
+
+impl Operation {
+    fn new(record: &CommonUsnRecord) -> Self {
+        let data = &record.data;
+        Self {
+            file_reference: *data.file_reference(),
+            reasons: UsnReason::from(data.reason().value()),
+            start: *data.timestamp(),
+            end: *data.timestamp(),
+            first_usn: data.usn(),
+            last_usn: data.usn(),
+            records: 1,
+            name_before: data.filename().to_owned(),
+            name_after: data.filename().to_owned(),
+            parent_before: *data.parent_reference(),
+            parent_after: *data.parent_reference(),
+            closed: data.reason().has_flag(UsnReasonValue::USN_REASON_CLOSE),
+        }
+    }
+
+    fn extend(&mut self, record: &CommonUsnRecord) {
+        let data = &record.data;
+        self.reasons = UsnReason::from(self.reasons.value() | data.reason().value());
+        self.end = *data.timestamp();
+        self.last_usn = data.usn();
+        self.records += 1;
+        self.name_after = data.filename().to_owned();
+        self.parent_after = *data.parent_reference();
+        self.closed = data.reason().has_flag(UsnReasonValue::USN_REASON_CLOSE);
+    }
+
+    pub fn has_flag(&self, flag: UsnReasonValue) -> bool {
+        self.reasons.has_flag(flag)
+    }
+
+    /// returns `true` if the file got another name or was moved to another directory
+    pub fn is_rename(&self) -> bool {
+        self.has_flag(UsnReasonValue::USN_REASON_RENAME_NEW_NAME)
+            || self.name_before != self.name_after
+            || self.parent_before != self.parent_after
+    }
+
+    /// returns `true` if the content of the file was changed
+    pub fn is_write(&self) -> bool {
+        self.reasons.flags().any(|f| f.class() == UsnReasonClass::Data)
+    }
+
+    #[cfg(feature = "formatters")]
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "file_reference": reference_to_string(&self.file_reference),
+            "reasons": self.reasons.flags().map(|f| f.short_name()).collect::<Vec<_>>(),
+            "start": timestamp_to_string(&self.start),
+            "end": timestamp_to_string(&self.end),
+            "first_usn": self.first_usn,
+            "last_usn": self.last_usn,
+            "records": self.records,
+            "name_before": self.name_before,
+            "name_after": self.name_after,
+            "parent_before": reference_to_string(&self.parent_before),
+            "parent_after": reference_to_string(&self.parent_after),
+            "closed": self.closed,
+        })
+    }
+}
+
+/// collapses records into operations.
+///
+/// ```rust,no_run
+/// use std::path::PathBuf;
+/// use usnjrnl::{OperationCollector, UsnJrnlReader};
+///
+/// let mut collector = OperationCollector::default();
+/// for record in UsnJrnlReader::from(&PathBuf::from("$UsnJrnl:$J")).unwrap().into_iter().flatten() {
+///     if let Some(operation) = collector.add(&record) {
+///         println!("{} {}", operation.name_after, operation.reasons);
+///     }
+/// }
+/// for operation in collector.finish() {
+///     println!("{} {} (still open)", operation.name_after, operation.reasons);
+/// }
+/// ```
+#[derive(Default)]
+pub struct OperationCollector {
+    open_operations: HashMap<MftReference, Operation>,
+}
+
+impl OperationCollector {
+    /// adds `record` to the operation of its file, and returns the operation
+    /// if `record` closed the file. Records must be added in the order of their USN.
+    pub fn add(&mut self, record: &CommonUsnRecord) -> Option<Operation> {
+        let file_reference = *record.data.file_reference();
+        let operation = match self.open_operations.remove(&file_reference) {
+            Some(mut operation) => {
+                operation.extend(record);
+                operation
+            }
+            None => Operation::new(record),
+        };
+
+        if operation.closed {
+            Some(operation)
+        } else {
+            self.open_operations.insert(file_reference, operation);
+            None
+        }
+    }
+
+    /// returns all operations which have not been closed, ordered by their first USN
+    pub fn finish(&mut self) -> Vec<Operation> {
+        let mut operations: Vec<_> = self.open_operations.drain().map(|(_, o)| o).collect();
+        operations.sort_by_key(|o| o.first_usn);
+        operations
+    }
+}
+
+/// everything which happened to a single file, as a list of operations
+#[derive(Clone, Debug)]
+pub struct FileLifecycle {
+    pub file_reference: MftReference,
+    pub operations: Vec<Operation>,
+}
+
+impl FileLifecycle {
+    /// returns the time when the file was created, if this is part of the journal
+    pub fn created(&self) -> Option<&DateTime<Utc>> {
+        self.operations.iter()
+            .find(|o| o.has_flag(UsnReasonValue::USN_REASON_FILE_CREATE))
+            .map(|o| &o.start)
+    }
+
+    /// returns the time when the file was deleted, if this is part of the journal
+    pub fn deleted(&self) -> Option<&DateTime<Utc>> {
+        self.operations.iter()
+            .find(|o| o.has_flag(UsnReasonValue::USN_REASON_FILE_DELETE))
+            .map(|o| &o.end)
+    }
+
+    pub fn renames(&self) -> impl Iterator<Item=&Operation> {
+        self.operations.iter().filter(|o| o.is_rename())
+    }
+
+    pub fn writes(&self) -> impl Iterator<Item=&Operation> {
+        self.operations.iter().filter(|o| o.is_write())
+    }
+
+    /// returns all names of the file, in the order in which they were used
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+        for operation in &self.operations {
+            for name in [&operation.name_before, &operation.name_after] {
+                if names.last() != Some(&name.as_str()) {
+                    names.push(name);
+                }
+            }
+        }
+        names
+    }
+
+    /// returns the last known name of the file
+    pub fn name(&self) -> &str {
+        &self.operations.last().expect("a lifecycle has at least one operation").name_after
+    }
+
+    /// returns the earliest start of all operations, as timestamps are only roughly ordered
+    pub fn first_seen(&self) -> &DateTime<Utc> {
+        self.operations.iter().map(|o| &o.start).min().expect("a lifecycle has at least one operation")
+    }
+
+    /// returns the latest end of all operations, as timestamps are only roughly ordered
+    pub fn last_seen(&self) -> &DateTime<Utc> {
+        self.operations.iter().map(|o| &o.end).max().expect("a lifecycle has at least one operation")
+    }
+
+    /// returns `true` if both the creation and the deletion of the file are part of the journal
+    pub fn is_complete(&self) -> bool {
+        self.created().is_some() && self.deleted().is_some()
+    }
+
+    #[cfg(feature = "formatters")]
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "file_reference": reference_to_string(&self.file_reference),
+            "names": self.names(),
+            "created": self.created().map(timestamp_to_string),
+            "deleted": self.deleted().map(timestamp_to_string),
+            "renames": self.renames().count(),
+            "writes": self.writes().count(),
+            "operations": self.operations.iter().map(Operation::to_json).collect::<Vec<_>>(),
+        })
+    }
+}
+
+/// builds the lifecycles of all files in a journal.
+///
+/// ```rust,no_run
+/// use std::path::PathBuf;
+/// use usnjrnl::{LifecycleEngine, UsnJrnlReader};
+///
+/// let mut engine = LifecycleEngine::default();
+/// for record in UsnJrnlReader::from(&PathBuf::from("$UsnJrnl:$J")).unwrap().into_iter().flatten() {
+///     engine.add(&record);
+/// }
+/// let lifecycles = engine.finish();
+/// for lifecycle in lifecycles.iter().filter(|l| l.is_complete()) {
+///     println!("{} was created and deleted", lifecycle.name());
+/// }
+/// ```
+#[derive(Default)]
+pub struct LifecycleEngine {
+    collector: OperationCollector,
+    lifecycles: Lifecycles,
+}
+
+impl LifecycleEngine {
+    /// adds `record`. Records must be added in the order of their USN.
+    pub fn add(&mut self, record: &CommonUsnRecord) {
+        if let Some(operation) = self.collector.add(record) {
+            self.lifecycles.add(operation);
+        }
+    }
+
+    /// returns the lifecycles of all files, including operations which have not been closed
+    pub fn finish(mut self) -> Lifecycles {
+        for operation in self.collector.finish() {
+            self.lifecycles.add(operation);
+        }
+        self.lifecycles.sort();
+        self.lifecycles
+    }
+}
+
+/// the lifecycles of all files in a journal, ordered by the first USN of each file
+#[derive(Default)]
+pub struct Lifecycles {
+    files: Vec<FileLifecycle>,
+    index: HashMap<MftReference, usize>,
+}
+
+impl Lifecycles {
+    fn add(&mut self, operation: Operation) {
+        let files = &mut self.files;
+        let idx = *self.index.entry(operation.file_reference).or_insert_with(|| {
+            files.push(FileLifecycle {
+                file_reference: operation.file_reference,
+                operations: Vec::new(),
+            });
+            files.len() - 1
+        });
+        self.files[idx].operations.push(operation);
+    }
+
+    fn sort(&mut self) {
+        for lifecycle in self.files.iter_mut() {
+            lifecycle.operations.sort_by_key(|o| o.first_usn);
+        }
+        self.files.sort_by_key(|l| l.operations[0].first_usn);
+        self.index = self.files.iter().enumerate().map(|(idx, l)| (l.file_reference, idx)).collect();
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item=&FileLifecycle> {
+        self.files.iter()
+    }
+
+    pub fn get(&self, file_reference: &MftReference) -> Option<&FileLifecycle> {
+        self.index.get(file_reference).map(|idx| &self.files[*idx])
+    }
+
+    /// returns the lifecycles of all files which used the MFT entry `reference`
+    pub fn by_entry<'a>(&'a self, reference: &'a EntryReference) -> impl Iterator<Item=&'a FileLifecycle> {
+        self.files.iter().filter(move |l| reference.matches(&l.file_reference))
+    }
+
+    /// returns the lifecycles of all files which had the name `name` at some time.
+    /// Names are compared case-insensitively, as NTFS does.
+    pub fn by_name<'a>(&'a self, name: &str) -> impl Iterator<Item=&'a FileLifecycle> {
+        let name = ntfs_upcase(name);
+        self.files.iter().filter(move |l| l.names().into_iter().any(|n| ntfs_upcase(n) == name))
+    }
+
+    /// returns the lifecycles of all files with activity between `from` and `to`
+    pub fn active_between<'a>(&'a self, from: &'a DateTime<Utc>, to: &'a DateTime<Utc>) -> impl Iterator<Item=&'a FileLifecycle> {
+        self.files.iter().filter(move |l| l.first_seen() <= to && l.last_seen() >= from)
+    }
+
+    #[cfg(feature = "formatters")]
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::Value::Array(self.files.iter().map(FileLifecycle::to_json).collect())
+    }
+}
@@ -1,10 +1,30 @@
 use anyhow::{Result, anyhow};
-use clap::{App, Arg};
+use chrono::{DateTime, Duration, Utc};
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use std::fs::File;
+use std::io::{BufWriter, IsTerminal, Write};
 use std::path::PathBuf;
+use std::rc::Rc;
+use std::str::FromStr;
 use simplelog::{TermLogger, LevelFilter, Config, TerminalMode, ColorChoice};
-use usnjrnl::{UsnJrnlReader, CommonUsnRecord, UsnRecordData};
-use bodyfile::Bodyfile3Line;
-use serde_json::json;
+use usnjrnl::{UsnJrnlReader, PathResolver, UsnReasonValue, UsnReasonClass, FileAttributeValue, FileHistory, HeatmapCollector, HeatmapSettings};
+use usnjrnl::filter::*;
+use usnjrnl::formatter::*;
+
+mod analyze;
+use analyze::{analyze, analyze_subcommand};
+
+mod diff;
+use diff::{diff, diff_subcommand};
+
+mod report;
+use report::HtmlReport;
+
+mod sink;
+use sink::open_sink;
+
+mod stats;
+use stats::JournalStatistics;
 
 pub fn main() -> Result<()> {
     let _ = TermLogger::init(
@@ -12,31 +32,292 @@ pub fn main() -> Result<()> {
         Config::default(),
         TerminalMode::Stderr,
         ColorChoice::Auto);
-    
-    #[cfg(feature = "gzip")]
-    let filename_help = "path to $UsnJrnl:$J file (file ending with .gz will be treated as being gzipped)";
 
-    #[cfg(not(feature = "gzip"))]
-    let filename_help = "path to $UsnJrnl:$J file";
+    let registry = FormatRegistry::default();
+    let format_names: Vec<&str> = registry.names().collect();
 
     let app = App::new(env!("CARGO_BIN_NAME"))
         .version(env!("CARGO_PKG_VERSION"))
         .author(env!("CARGO_PKG_AUTHORS"))
         .about(env!("CARGO_PKG_DESCRIPTION"))
-        .arg(
-            Arg::with_name("USNJRNL_FILE")
-                .help(filename_help)
-                .required(true)
-                .multiple(false)
-                .takes_value(true),
+        .setting(AppSettings::SubcommandsNegateReqs)
+        .arg(journal_file_arg()
         ).arg(
             Arg::with_name("BODYFILE_FORMAT")
                 .short("b")
                 .long("bodyfile")
+                .conflicts_with("FORMAT")
                 .help("output as bodyfile instead of JSON")
-        );
-    
+        ).arg(
+            Arg::with_name("FORMAT")
+                .short("F")
+                .long("format")
+                .help("output format")
+                .takes_value(true)
+                .possible_values(&format_names)
+                .default_value("json")
+        ).arg(
+            Arg::with_name("OUTPUT")
+                .short("o")
+                .long("output")
+                .help("destination of the output: '-' for stdout, a filename, 'udp://host:port' or 'tcp://host:port'")
+                .takes_value(true)
+                .default_value("-")
+        ).arg(
+            Arg::with_name("RESOLVE_PATHS")
+                .short("r")
+                .long("resolve-paths")
+                .help("resolve full paths of files, using the directory names found in the journal (reads the journal twice)")
+        ).arg(
+            Arg::with_name("HOST_NAME")
+                .long("host-name")
+                .help("name of the host the journal was taken from")
+                .takes_value(true)
+        ).arg(
+            Arg::with_name("USER_NAME")
+                .long("user-name")
+                .help("name of the user, which is written into the user field of l2tcsv and TLN")
+                .takes_value(true)
+        ).arg(
+            Arg::with_name("TIMEZONE")
+                .long("timezone")
+                .help("timezone used to display date and time in l2tcsv and table, as 'UTC', 'local' or offset like '+02:00'")
+                .takes_value(true)
+                .default_value("UTC")
+        ).arg(
+            Arg::with_name("COLOR")
+                .long("color")
+                .help("colorize the table output by reason (create, delete, rename, data)")
+                .takes_value(true)
+                .possible_values(&["auto", "always", "never"])
+                .default_value("auto")
+        ).arg(
+            Arg::with_name("WIDTH")
+                .long("width")
+                .help("truncate lines of the table output to this width (default: width of the terminal)")
+                .takes_value(true)
+        ).arg(
+            Arg::with_name("PAGER")
+                .long("pager")
+                .help("pager-friendly table output: lines are not truncated and colors are only used with '--color always' (use 'less -RS')")
+        ).arg(
+            Arg::with_name("SINCE")
+                .long("since")
+                .help("only show records at or after this time, e.g. '2022-11-18 14:02:00' (in the timezone given by '--timezone') or '2022-11-18T14:02:00Z'")
+                .takes_value(true)
+        ).arg(
+            Arg::with_name("UNTIL")
+                .long("until")
+                .help("only show records up to this time (as timestamps are only roughly ordered, reading stops one minute later)")
+                .takes_value(true)
+        ).arg(
+            Arg::with_name("START_USN")
+                .long("start-usn")
+                .help("start with the record which has this USN")
+                .takes_value(true)
+        ).arg(
+            Arg::with_name("END_USN")
+                .long("end-usn")
+                .help("stop after the record which has this USN")
+                .takes_value(true)
+        ).arg(
+            Arg::with_name("REASON")
+                .long("reason")
+                .help("only show records with one of these reasons, e.g. 'FILE_DELETE,RENAME_OLD_NAME'")
+                .takes_value(true)
+                .multiple(true)
+                .require_delimiter(true)
+        ).arg(
+            Arg::with_name("EXCLUDE_REASON")
+                .long("exclude-reason")
+                .help("hide records with one of these reasons, e.g. 'CLOSE'")
+                .takes_value(true)
+                .multiple(true)
+                .require_delimiter(true)
+        ).arg(
+            Arg::with_name("ALL_REASONS")
+                .long("all-reasons")
+                .conflicts_with("ANY_REASON")
+                .help("only show records which have all reasons given with '--reason'")
+        ).arg(
+            Arg::with_name("ANY_REASON")
+                .long("any-reason")
+                .help("only show records which have at least one of the reasons given with '--reason' (default)")
+        ).arg(
+            Arg::with_name("ATTRIBUTES")
+                .long("attributes")
+                .help("only show records of files which have all of these attributes, e.g. 'DIRECTORY,HIDDEN'")
+                .takes_value(true)
+                .multiple(true)
+                .require_delimiter(true)
+        ).arg(
+            Arg::with_name("NAME_GLOB")
+                .long("name-glob")
+                .help("only show records of files whose name matches this wildcard pattern, e.g. '*.ps1' (ignoring case)")
+                .takes_value(true)
+        ).arg(
+            Arg::with_name("NAME_REGEX")
+                .long("name-regex")
+                .help("only show records of files whose name matches this regular expression, e.g. '\\.(locked|encrypted)$' (ignoring case)")
+                .takes_value(true)
+        ).arg(
+            Arg::with_name("NAME_LIST")
+                .long("name-list")
+                .help("only show records of files whose name is contained in this file, which contains one name or wildcard pattern per line")
+                .takes_value(true)
+        ).arg(
+            Arg::with_name("ENTRY")
+                .long("entry")
+                .help("only show records of this MFT entry, given as entry number or as entry and sequence number, e.g. '12345-3'")
+                .takes_value(true)
+        ).arg(
+            Arg::with_name("PARENT")
+                .long("parent")
+                .help("only show records of files in this directory, given as entry number or as entry and sequence number")
+                .takes_value(true)
+        ).arg(
+            Arg::with_name("WHERE")
+                .long("where")
+                .help("only show records which match this expression, e.g. 'reason has FILE_DELETE and name ~ \"\\.exe$\"' (paths are resolved automatically if the expression uses 'path')")
+                .takes_value(true)
+        ).arg(elastic_index_arg()
+        ).subcommand(
+            SubCommand::with_name("elastic-template")
+                .about("prints an index template for documents created with '--format elastic'")
+                .arg(elastic_index_arg())
+        ).subcommand(
+            SubCommand::with_name("history")
+                .about("prints everything that happened to a single MFT entry: creation, renames, writes, attribute changes and deletion")
+                .arg(journal_file_arg())
+                .arg(
+                    Arg::with_name("ENTRY")
+                        .long("entry")
+                        .help("entry number, or entry and sequence number like '12345-3'")
+                        .takes_value(true)
+                        .required(true)
+                ).arg(
+                    Arg::with_name("RESOLVE_PATHS")
+                        .short("r")
+                        .long("resolve-paths")
+                        .help("resolve full paths, using the directory names found in the journal (reads the journal twice)")
+                ).arg(
+                    Arg::with_name("TIMEZONE")
+                        .long("timezone")
+                        .help("timezone used to display date and time, as 'UTC', 'local' or offset like '+02:00'")
+                        .takes_value(true)
+                        .default_value("UTC")
+                )
+        ).subcommand(
+            SubCommand::with_name("stats")
+                .about("reads the journal once and prints statistics: time span, reasons, activity per day and hour, most active directories and extensions")
+                .arg(journal_file_arg())
+                .arg(
+                    Arg::with_name("JSON")
+                        .long("json")
+                        .help("print statistics as JSON instead of plain text")
+                ).arg(
+                    Arg::with_name("TOP")
+                        .long("top")
+                        .help("number of directories and extensions to show")
+                        .takes_value(true)
+                        .default_value("10")
+                )
+        ).subcommand(
+            SubCommand::with_name("heatmap")
+                .about("counts the activity per reason class, directory and time bucket, rolled up the directory tree")
+                .arg(journal_file_arg())
+                .arg(
+                    Arg::with_name("JSON")
+                        .long("json")
+                        .help("print the directory tree as JSON, e.g. for treemap and sunburst views")
+                ).arg(
+                    Arg::with_name("BUCKET")
+                        .long("bucket")
+                        .help("length of a time bucket in seconds")
+                        .takes_value(true)
+                        .default_value("60")
+                ).arg(
+                    Arg::with_name("CLASSES")
+                        .long("classes")
+                        .help("reason classes to count, e.g. 'create,data' (default: all but close)")
+                        .takes_value(true)
+                        .multiple(true)
+                        .require_delimiter(true)
+                        .possible_values(&["delete", "create", "rename", "data", "metadata", "close"])
+                ).arg(
+                    Arg::with_name("DEPTH")
+                        .long("depth")
+                        .help("maximum depth of the directory tree")
+                        .takes_value(true)
+                ).arg(
+                    Arg::with_name("MIN_COUNT")
+                        .long("min-count")
+                        .help("hide directories with less records")
+                        .takes_value(true)
+                        .default_value("1")
+                ).arg(
+                    Arg::with_name("TOP")
+                        .long("top")
+                        .help("number of hotspots to show, which are the directories and time buckets with the most records")
+                        .takes_value(true)
+                        .default_value("10")
+                )
+        ).subcommand(
+            SubCommand::with_name("report")
+                .about("creates a self-contained HTML report")
+                .arg(journal_file_arg())
+                .arg(
+                    Arg::with_name("HTML_FILE")
+                        .long("html")
+                        .help("name of the HTML file to create")
+                        .takes_value(true)
+                        .required(true)
+                ).arg(
+                    Arg::with_name("TITLE")
+                        .long("title")
+                        .help("title of the report")
+                        .takes_value(true)
+                ).arg(
+                    Arg::with_name("MAX_RECORDS")
+                        .long("max-records")
+                        .help("maximum number of records in the record table, as the browser has to load all of them (the summary always covers all records)")
+                        .takes_value(true)
+                        .default_value("100000")
+                )
+        ).subcommand(diff_subcommand()
+        ).subcommand(analyze_subcommand());
+
     let matches = app.get_matches();
+
+    if let Some(matches) = matches.subcommand_matches("elastic-template") {
+        println!("{}", elastic_index_template(matches.value_of("ES_INDEX").unwrap()));
+        return Ok(());
+    }
+
+    if let Some(matches) = matches.subcommand_matches("history") {
+        return print_history(matches);
+    }
+
+    if let Some(matches) = matches.subcommand_matches("stats") {
+        return print_statistics(matches);
+    }
+
+    if let Some(matches) = matches.subcommand_matches("heatmap") {
+        return print_heatmap(matches);
+    }
+
+    if let Some(matches) = matches.subcommand_matches("report") {
+        return create_report(matches);
+    }
+
+    if let Some(matches) = matches.subcommand_matches("diff") {
+        return diff(matches);
+    }
+
+    if let Some(matches) = matches.subcommand_matches("analyze") {
+        return analyze(matches);
+    }
+
     let filename = match matches.value_of("USNJRNL_FILE") {
         None => {
             return Err(anyhow!("Missing filename for $UsnJrnl:$J file"));
@@ -44,62 +325,364 @@ pub fn main() -> Result<()> {
         Some(v) => PathBuf::from(v)
     };
 
+    let timezone: DisplayTimezone = matches.value_of("TIMEZONE").unwrap().parse().map_err(|e| anyhow!("{}", e))?;
+    let query = matches.value_of("WHERE").map(|q| Query::parse_in_timezone(q, timezone)).transpose()?;
+    let uses_path = query.as_ref().is_some_and(|q| q.uses_path());
+
+    let resolver = Rc::new(if matches.is_present("RESOLVE_PATHS") || uses_path {
+        resolve_paths(&filename)?
+    } else {
+        PathResolver::default()
+    });
+
+    let format = if matches.is_present("BODYFILE_FORMAT") {
+        "bodyfile"
+    } else {
+        matches.value_of("FORMAT").unwrap()
+    };
+
+    let (width, color) = table_settings(&matches)?;
+    let options = FormatOptions {
+        host_name: matches.value_of("HOST_NAME").map(|h| h.to_owned()),
+        user_name: matches.value_of("USER_NAME").map(|u| u.to_owned()),
+        timezone,
+        elastic_index: matches.value_of("ES_INDEX").unwrap().to_owned(),
+        width,
+        color,
+        resolver: Rc::clone(&resolver),
+    };
+    let mut writer = registry.create(format, &options)
+        .ok_or_else(|| anyhow!("unknown format: '{}'", format))?;
+
+    let since = matches.value_of("SINCE").map(|t| timezone.parse_timestamp(t)).transpose().map_err(|e| anyhow!("{}", e))?;
+    let until = matches.value_of("UNTIL").map(|t| timezone.parse_timestamp(t)).transpose().map_err(|e| anyhow!("{}", e))?;
+    let start_usn: Option<i64> = matches.value_of("START_USN").map(|u| u.parse()).transpose()?;
+    let end_usn: Option<i64> = matches.value_of("END_USN").map(|u| u.parse()).transpose()?;
+
+    let mut filter = record_filter(&matches)?;
+    if let Some(query) = query {
+        filter.push(query.with_resolver(Rc::clone(&resolver)));
+    }
+
     let reader = UsnJrnlReader::from(&filename)?;
-    let formatter: Box<dyn RecordFormat> = if matches.is_present("BODYFILE_FORMAT") {
-        Box::new(BodyfileFormatter{})
+    let seekable = reader.is_seekable();
+    let mut records = reader.into_iter();
+    if let Some(usn) = start_usn {
+        records.seek_to_usn(usn)?;
+    } else if let Some(since) = since.as_ref() {
+        if seekable {
+            let offset = records.seek_to_time(since)?;
+            log::info!("first record at or after {} is located at offset 0x{:x}", since, offset);
+        } else {
+            log::warn!("{} cannot be seeked, so the whole journal must be read", filename.display());
+        }
+    }
+
+    /* stop right after the record with '--end-usn', without reading the next record */
+    let mut reached_end = false;
+    let records = std::iter::from_fn(move || {
+        if reached_end {
+            return None;
+        }
+        let record = records.next()?;
+        if let (Ok(record), Some(end_usn)) = (&record, end_usn) {
+            if record.data.usn() > end_usn {
+                return None;
+            }
+            reached_end = record.data.usn() == end_usn;
+        }
+        Some(record)
+    });
+
+    /* records which cannot be read are passed through, to be logged by the writer */
+    let records = records
+        .filter(|r| match (r, since) {
+            (Ok(record), Some(since)) => record.data.timestamp() >= &since,
+            _ => true,
+        })
+        /* stop some time after '--until', even if the filters would hide the records */
+        .take_while(|r| match (r, until) {
+            (Ok(record), Some(until)) => *record.data.timestamp() <= until + Duration::seconds(UNTIL_SLACK),
+            _ => true,
+        })
+        .filter(|r| match (r, until) {
+            (Ok(record), Some(until)) => record.data.timestamp() <= &until,
+            _ => true,
+        })
+        .filter(|r| match r {
+            Ok(record) => filter.matches(record),
+            Err(_) => true,
+        });
+
+    let mut output = open_sink(matches.value_of("OUTPUT").unwrap())?;
+    write_records(records, writer.as_mut(), &mut output)?;
+
+    Ok(())
+}
+
+/// number of seconds, which timestamps of subsequent records may go back in
+/// time. Reading continues this long after '--until'.
+const UNTIL_SLACK: i64 = 60;
+
+fn journal_file_arg<'a, 'b>() -> Arg<'a, 'b> {
+    #[cfg(feature = "gzip")]
+    let filename_help = "path to $UsnJrnl:$J file (file ending with .gz will be treated as being gzipped)";
+
+    #[cfg(not(feature = "gzip"))]
+    let filename_help = "path to $UsnJrnl:$J file";
+
+    Arg::with_name("USNJRNL_FILE")
+        .help(filename_help)
+        .required(true)
+        .multiple(false)
+        .takes_value(true)
+}
+
+fn elastic_index_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("ES_INDEX")
+        .long("es-index")
+        .help("name of the Elasticsearch/OpenSearch index")
+        .takes_value(true)
+        .default_value("usnjrnl")
+}
+
+/// reads the whole journal to learn the names of all directories
+fn resolve_paths(filename: &PathBuf) -> Result<PathResolver> {
+    let mut resolver = PathResolver::default();
+    for record in UsnJrnlReader::from(filename)?.into_iter().flatten() {
+        resolver.learn(&record);
+    }
+    log::info!("found names of {} files and directories", resolver.len());
+    Ok(resolver)
+}
+
+/// creates a filter from the command line options which select records by their content
+fn record_filter(matches: &ArgMatches) -> Result<AllOf> {
+    let mut filter = AllOf::default();
+    if let Some(reasons) = matches.values_of("REASON") {
+        let reasons = reasons.map(UsnReasonValue::from_str).collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| anyhow!("{}", e))?;
+        let mode = if matches.is_present("ALL_REASONS") { MatchMode::All } else { MatchMode::Any };
+        filter.push(ReasonFilter::new(&reasons, mode));
+    }
+    if let Some(reasons) = matches.values_of("EXCLUDE_REASON") {
+        let reasons = reasons.map(UsnReasonValue::from_str).collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| anyhow!("{}", e))?;
+        filter.push(Not::new(ReasonFilter::any(&reasons)));
+    }
+    if let Some(attributes) = matches.values_of("ATTRIBUTES") {
+        let attributes = attributes.map(FileAttributeValue::from_str).collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| anyhow!("{}", e))?;
+        filter.push(AttributeFilter::all(&attributes));
+    }
+    if let Some(reference) = matches.value_of("ENTRY") {
+        filter.push(FileReferenceFilter::new(reference.parse().map_err(|e| anyhow!("{}", e))?));
+    }
+    if let Some(reference) = matches.value_of("PARENT") {
+        filter.push(ParentFilter::new(reference.parse().map_err(|e| anyhow!("{}", e))?));
+    }
+    if let Some(pattern) = matches.value_of("NAME_GLOB") {
+        filter.push(NameGlob::new(pattern)?);
+    }
+    if let Some(pattern) = matches.value_of("NAME_REGEX") {
+        filter.push(NameRegex::new(pattern)?);
+    }
+    if let Some(list_file) = matches.value_of("NAME_LIST") {
+        let names = NameList::new(std::fs::read_to_string(list_file)?.lines())?;
+        log::info!("loaded {} names from {}", names.len(), list_file);
+        filter.push(names);
+    }
+    Ok(filter)
+}
+
+/// parses a duration, which is given as number of seconds
+fn parse_seconds(value: &str) -> Result<Duration> {
+    Duration::try_seconds(value.parse()?).ok_or_else(|| anyhow!("duration out of range: {} seconds", value))
+}
+
+/// returns width and colorization of the table output
+fn table_settings(matches: &ArgMatches) -> Result<(Option<usize>, bool)> {
+    let pager = matches.is_present("PAGER");
+    let is_terminal = std::io::stdout().is_terminal();
+    let color = match matches.value_of("COLOR").unwrap().parse().map_err(|e| anyhow!("{}", e))? {
+        ColorMode::Always => true,
+        ColorMode::Never => false,
+        ColorMode::Auto => is_terminal && !pager,
+    };
+    let width = match matches.value_of("WIDTH") {
+        _ if pager => None,
+        Some(width) => Some(width.parse()?),
+        None if is_terminal => terminal_size::terminal_size().map(|(w, _)| w.0 as usize),
+        None => None,
+    };
+    Ok((width, color))
+}
+
+#[derive(Clone, Copy)]
+enum ColorMode {
+    Auto,
+    Always,
+    Never,
+}
+
+impl FromStr for ColorMode {
+    type Err = String;
+    fn from_str(mode: &str) -> Result<Self, Self::Err> {
+        match mode {
+            "auto" => Ok(Self::Auto),
+            "always" => Ok(Self::Always),
+            "never" => Ok(Self::Never),
+            _ => Err(format!("invalid color mode: '{}'", mode)),
+        }
+    }
+}
+
+fn print_history(matches: &ArgMatches) -> Result<()> {
+    let filename = PathBuf::from(matches.value_of("USNJRNL_FILE").unwrap());
+    let reference: EntryReference = matches.value_of("ENTRY").unwrap().parse().map_err(|e| anyhow!("{}", e))?;
+    let timezone: DisplayTimezone = matches.value_of("TIMEZONE").unwrap().parse().map_err(|e| anyhow!("{}", e))?;
+    let resolver = if matches.is_present("RESOLVE_PATHS") {
+        resolve_paths(&filename)?
     } else {
-        Box::new(JsonFormatter{})
+        PathResolver::default()
     };
-    for entry in reader.into_iter() {
+
+    let mut history = FileHistory::new(reference);
+    for entry in UsnJrnlReader::from(&filename)? {
         match entry {
-            Ok(e) => {
-                println!("{}", formatter.fmt(&e));
-            }
+            Ok(record) => history.add(&record),
+            Err(why) => log::error!("{}", why),
+        }
+    }
+
+    let mut output = BufWriter::new(std::io::stdout());
+    let mut current_file = None;
+    for event in history.events() {
+        let file_reference = event.file_reference;
+        if current_file != Some(file_reference) {
+            writeln!(output, "MFT entry {}, sequence number {}", file_reference.entry, file_reference.sequence)?;
+            current_file = Some(file_reference);
+        }
+        let location = resolver.path_of(&event.parent_reference, &event.name).unwrap_or_else(|| event.name.clone());
+        writeln!(output, "  {}  USN {:<12} {:<40} {}",
+            timezone.format(&event.timestamp, "%Y-%m-%d %H:%M:%S%.6f"),
+            event.usn,
+            event.action.to_string(),
+            location)?;
+    }
+    if history.events().is_empty() {
+        log::warn!("there are no records for MFT entry {}", reference);
+    }
+    output.flush()?;
+    Ok(())
+}
+
+fn print_statistics(matches: &ArgMatches) -> Result<()> {
+    let filename = PathBuf::from(matches.value_of("USNJRNL_FILE").unwrap());
+    let mut statistics = JournalStatistics::new(matches.value_of("TOP").unwrap().parse()?);
+    for entry in UsnJrnlReader::from(&filename)? {
+        match entry {
+            Ok(record) => statistics.add(&record),
             Err(why) => {
                 log::error!("{}", why);
+                statistics.add_error(&why);
             }
         }
     }
 
+    let mut output = BufWriter::new(std::io::stdout());
+    if matches.is_present("JSON") {
+        statistics.write_json(&mut output)?;
+    } else {
+        statistics.write_text(&mut output)?;
+    }
+    output.flush()?;
     Ok(())
 }
 
-trait RecordFormat {
-    fn fmt(&self, record: &CommonUsnRecord) -> String;
-}
+fn print_heatmap(matches: &ArgMatches) -> Result<()> {
+    let filename = PathBuf::from(matches.value_of("USNJRNL_FILE").unwrap());
+    let mut settings = HeatmapSettings {
+        bucket: parse_seconds(matches.value_of("BUCKET").unwrap())?,
+        ..Default::default()
+    };
+    if let Some(classes) = matches.values_of("CLASSES") {
+        settings.classes = classes.map(UsnReasonClass::from_str).collect::<std::result::Result<_, _>>()
+            .map_err(|e| anyhow!("{}", e))?;
+    }
+    let top: usize = matches.value_of("TOP").unwrap().parse()?;
+
+    let mut collector = HeatmapCollector::new(settings);
+    for entry in UsnJrnlReader::from(&filename)? {
+        match entry {
+            Ok(record) => collector.add(&record),
+            Err(why) => log::error!("{}", why),
+        }
+    }
+    let mut heatmap = collector.finish();
+    heatmap.prune(matches.value_of("MIN_COUNT").unwrap().parse()?);
+    heatmap.hotspots.truncate(top);
+    if let Some(depth) = matches.value_of("DEPTH") {
+        let depth = depth.parse()?;
+        heatmap.root.truncate(depth);
+        heatmap.unresolved.truncate(depth);
+    }
 
-struct BodyfileFormatter {}
-impl RecordFormat for BodyfileFormatter {
-    fn fmt(&self, record: &CommonUsnRecord) -> String {
-        let message = format!("{} (UsnJrnl reason: {})",
-                        record.data.filename(),
-                        record.data.reason());
-        let mut bf_line = Bodyfile3Line::new()
-            .with_name(&message)
-            .with_mtime(record.data.timestamp().timestamp());
-
-        #[allow(irrefutable_let_patterns)]
-        if let UsnRecordData::V2(ref v2record) = record.data {
-            bf_line = bf_line.with_owned_inode(format!("{}", v2record.FileReferenceNumber.entry));
+    let mut output = BufWriter::new(std::io::stdout());
+    if matches.is_present("JSON") {
+        writeln!(output, "{}", serde_json::to_string_pretty(&heatmap.to_json())?)?;
+        output.flush()?;
+        return Ok(());
+    }
+
+    let format_timestamp = |t: &DateTime<Utc>| t.format("%Y-%m-%d %H:%M:%S UTC").to_string();
+    if !heatmap.hotspots.is_empty() {
+        writeln!(output, "hotspots (records in a directory within {} seconds):", heatmap.bucket.num_seconds())?;
+        for hotspot in &heatmap.hotspots {
+            writeln!(output, "  {}  {:>8}  {} ({})",
+                format_timestamp(&hotspot.start), hotspot.counts.total(), hotspot.path, hotspot.counts)?;
         }
-        bf_line.to_string()
+        writeln!(output)?;
     }
+    for tree in [&heatmap.root, &heatmap.unresolved] {
+        if tree.total.is_empty() {
+            continue;
+        }
+        for (depth, node) in tree.iter() {
+            let name = if node.name.is_empty() { "/" } else { &node.name };
+            write!(output, "{:indent$}{}  {} ({})", "", name, node.total.total(), node.total, indent = depth * 2)?;
+            if let Some((start, counts)) = node.peak() {
+                write!(output, ", peak {}: {}", format_timestamp(start), counts.total())?;
+            }
+            writeln!(output)?;
+        }
+    }
+    output.flush()?;
+    Ok(())
 }
 
-struct JsonFormatter {}
-impl RecordFormat for JsonFormatter {
-    fn fmt(&self, record: &CommonUsnRecord) -> String {
-        let mut json = json!({
-            "timestamp": record.data.timestamp(),
-            "filename": record.data.filename(),
-            "reason": record.data.reason().to_string(),
-        });
+fn create_report(matches: &ArgMatches) -> Result<()> {
+    let filename = PathBuf::from(matches.value_of("USNJRNL_FILE").unwrap());
+    let max_records = matches.value_of("MAX_RECORDS").unwrap().parse()?;
+    let title = match matches.value_of("TITLE") {
+        Some(title) => title.to_owned(),
+        None => format!("$UsnJrnl report of {}", filename.display()),
+    };
 
-        #[allow(irrefutable_let_patterns)]
-        if let UsnRecordData::V2(ref v2record) = record.data {
-            json["inode"] = serde_json::Value::String(format!("{}", v2record.FileReferenceNumber.entry));
-            json["parent"] = serde_json::Value::String(format!("{}", v2record.ParentFileReferenceNumber.entry));
+    let resolver = resolve_paths(&filename)?;
+    let mut report = HtmlReport::new(&title, &resolver, max_records);
+    for entry in UsnJrnlReader::from(&filename)? {
+        match entry {
+            Ok(record) => report.add(&record),
+            Err(why) => {
+                log::error!("{}", why);
+                report.add_error();
+            }
         }
-        json.to_string()
     }
-}
\ No newline at end of file
+
+    let mut output = BufWriter::new(File::create(matches.value_of("HTML_FILE").unwrap())?);
+    report.write(&mut output)?;
+    output.flush()?;
+    Ok(())
+}
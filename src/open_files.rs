@@ -0,0 +1,28 @@
+use std::collections::HashMap;
+use winstructs::ntfs::mft_reference::MftReference;
+
+use crate::{CommonUsnRecord, UsnReason, UsnReasonValue};
+
+/// remembers the reasons of all open files.
+///
+/// Reason flags accumulate until a file is closed, so this is needed to
+/// tell which reasons of a record are new.
+#[derive(Default)]
+pub(crate) struct OpenFiles {
+    reasons: HashMap<MftReference, u32>,
+}
+
+impl OpenFiles {
+    /// returns the reasons of `record`, which have not been set in the previous
+    /// record of the same file. Records must be added in the order of their USN.
+    pub(crate) fn new_reasons(&mut self, record: &CommonUsnRecord) -> UsnReason {
+        let file_reference = *record.data.file_reference();
+        let reason = record.data.reason();
+        let previous = if reason.has_flag(UsnReasonValue::USN_REASON_CLOSE) {
+            self.reasons.remove(&file_reference)
+        } else {
+            self.reasons.insert(file_reference, reason.value())
+        };
+        UsnReason::from(reason.value() & !previous.unwrap_or(0))
+    }
+}
@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+use winstructs::ntfs::mft_reference::MftReference;
+
+use crate::CommonUsnRecord;
+
+/// MFT entry number of the root directory of every NTFS volume
+const ROOT_ENTRY: u64 = 5;
+
+/// the maximum directory depth we follow before we assume a reference loop
+const MAX_DEPTH: usize = 256;
+
+struct PathEntry {
+    name: String,
+    parent: MftReference,
+}
+
+/// Resolves file references to full paths, using only the names and parent
+/// references found in the journal itself.
+///
+/// The journal does not contain full paths, only the name of a file and the
+/// reference of its parent directory. If the parent directory had journal
+/// activity itself, we know its name and its parent as well, and so on up to
+/// the root directory. A path can only be resolved if every directory up to
+/// the root is known.
+///
+/// ```rust,no_run
+/// use std::path::PathBuf;
+/// use usnjrnl::{UsnJrnlReader, PathResolver};
+///
+/// let mut resolver = PathResolver::default();
+/// for entry in UsnJrnlReader::from(&PathBuf::from("$UsnJrnl:$J")).unwrap() {
+///     if let Ok(record) = entry {
+///         resolver.learn(&record);
+///     }
+/// }
+/// ```
+#[derive(Default)]
+pub struct PathResolver {
+    entries: HashMap<MftReference, PathEntry>,
+}
+
+impl PathResolver {
+    /// remembers the name and the parent of the file which is referenced by `record`.
+    /// If the same file is seen multiple times, the latest name wins.
+    pub fn learn(&mut self, record: &CommonUsnRecord) {
+        self.entries.insert(
+            *record.data.file_reference(),
+            PathEntry {
+                name: record.data.filename().to_owned(),
+                parent: *record.data.parent_reference(),
+            },
+        );
+    }
+
+    /// returns the name and the parent of the file referenced by `reference`, if it is known
+    pub fn name_and_parent(&self, reference: &MftReference) -> Option<(&str, MftReference)> {
+        self.entries.get(reference).map(|entry| (entry.name.as_str(), entry.parent))
+    }
+
+    /// returns the number of file references which are known by this resolver
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// resolves the full path of the directory referenced by `reference`.
+    /// The root directory is returned as `/`.
+    pub fn directory_path(&self, reference: &MftReference) -> Option<String> {
+        let (mut components, complete) = self.ancestors(reference);
+        if !complete {
+            return None;
+        }
+        components.reverse();
+        Some(format!("/{}", components.join("/")))
+    }
+
+    /// resolves as much of the path of the directory referenced by `reference`
+    /// as is known. If the root directory cannot be reached, the path starts
+    /// with `?` instead of `/`, e.g. `?/Local/Temp`.
+    pub fn partial_directory_path(&self, reference: &MftReference) -> String {
+        let (mut components, complete) = self.ancestors(reference);
+        components.reverse();
+        let prefix = if complete { "/" } else if components.is_empty() { "?" } else { "?/" };
+        format!("{}{}", prefix, components.join("/"))
+    }
+
+    /// returns the names of the directory referenced by `reference` and of its
+    /// known ancestors, innermost first, and whether the root directory has been reached
+    fn ancestors(&self, reference: &MftReference) -> (Vec<&str>, bool) {
+        let (chain, complete) = self.ancestor_chain(reference);
+        (chain.into_iter().filter_map(|(_, name)| name).collect(), complete)
+    }
+
+    /// returns the directory referenced by `reference` and its ancestors,
+    /// innermost first, without the root directory, and whether the root
+    /// directory has been reached. If a directory is not known, it is the
+    /// last one and has no name. The walk also stops at a reference loop.
+    pub(crate) fn ancestor_chain(&self, reference: &MftReference) -> (Vec<(MftReference, Option<&str>)>, bool) {
+        let mut chain: Vec<(MftReference, Option<&str>)> = Vec::new();
+        let mut current = *reference;
+        while current.entry != ROOT_ENTRY {
+            if chain.len() >= MAX_DEPTH || chain.iter().any(|(r, _)| *r == current) {
+                return (chain, false);
+            }
+            match self.entries.get(&current) {
+                Some(entry) => {
+                    chain.push((current, Some(entry.name.as_str())));
+                    current = entry.parent;
+                }
+                None => {
+                    chain.push((current, None));
+                    return (chain, false);
+                }
+            }
+        }
+        (chain, true)
+    }
+
+    /// resolves the full path of the file which is referenced by `record`
+    pub fn full_path(&self, record: &CommonUsnRecord) -> Option<String> {
+        self.path_of(record.data.parent_reference(), record.data.filename())
+    }
+
+    /// resolves the full path of a file named `name` in the directory referenced by `parent`
+    pub fn path_of(&self, parent: &MftReference, name: &str) -> Option<String> {
+        self.directory_path(parent).map(|directory| join_path(&directory, name))
+    }
+}
+
+/// appends `name` to the path of a directory, which might be the root directory `/`
+pub(crate) fn join_path(directory: &str, name: &str) -> String {
+    if directory.ends_with('/') {
+        format!("{}{}", directory, name)
+    } else {
+        format!("{}/{}", directory, name)
+    }
+}
@@ -0,0 +1,232 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::iter::FromIterator;
+use chrono::{DateTime, Utc};
+use winstructs::ntfs::mft_reference::MftReference;
+
+use crate::open_files::OpenFiles;
+use crate::{CommonUsnRecord, UsnReaderError, UsnReasonValue};
+
+/// a file which got a new name or was moved to another directory.
+///
+/// The old name is unknown if the journal starts between the
+/// `USN_REASON_RENAME_OLD_NAME` record and the `USN_REASON_RENAME_NEW_NAME`
+/// record.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rename {
+    pub file_reference: MftReference,
+    pub old_name: Option<String>,
+    pub old_parent: Option<MftReference>,
+    pub new_name: String,
+    pub new_parent: MftReference,
+    pub timestamp: DateTime<Utc>,
+
+    /// the USN of the record which contains the new name
+    pub usn: i64,
+}
+
+impl Rename {
+    /// returns `true` if the file was moved to another directory
+    pub fn is_move(&self) -> bool {
+        matches!(self.old_parent, Some(old_parent) if old_parent != self.new_parent)
+    }
+}
+
+impl fmt::Display for Rename {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} -> {}", self.old_name.as_deref().unwrap_or("?"), self.new_name)
+    }
+}
+
+/// the old name of a file, which waits for its new name
+struct PendingRename {
+    name: String,
+    parent: MftReference,
+}
+
+/// pairs the old name and the new name of renamed files, one record at a time
+#[derive(Default)]
+pub struct RenameTracker {
+    open_files: OpenFiles,
+    pending: HashMap<MftReference, PendingRename>,
+}
+
+impl RenameTracker {
+    /// returns the rename which is completed by `record`, if any.
+    /// Records must be added in the order of their USN.
+    pub fn add(&mut self, record: &CommonUsnRecord) -> Option<Rename> {
+        let data = &record.data;
+        let file_reference = *data.file_reference();
+        let reason = data.reason();
+        let new_reasons = self.open_files.new_reasons(record);
+
+        if reason.has_flag(UsnReasonValue::USN_REASON_RENAME_OLD_NAME) {
+            self.pending.insert(file_reference, PendingRename {
+                name: data.filename().to_owned(),
+                parent: *data.parent_reference(),
+            });
+            return None;
+        }
+        if !reason.has_flag(UsnReasonValue::USN_REASON_RENAME_NEW_NAME) {
+            return None;
+        }
+
+        /* the RENAME_NEW_NAME flag is repeated until the file is closed,
+         * so we need either the old name or a flag which was not set before */
+        let pending = self.pending.remove(&file_reference);
+        if pending.is_none() && !new_reasons.has_flag(UsnReasonValue::USN_REASON_RENAME_NEW_NAME) {
+            return None;
+        }
+        Some(Rename {
+            file_reference,
+            old_name: pending.as_ref().map(|p| p.name.clone()),
+            old_parent: pending.map(|p| p.parent),
+            new_name: data.filename().to_owned(),
+            new_parent: *data.parent_reference(),
+            timestamp: *data.timestamp(),
+            usn: data.usn(),
+        })
+    }
+}
+
+/// pairs the old name and the new name of renamed files.
+///
+/// Errors of the underlying iterator are passed through.
+///
+/// ```rust,no_run
+/// use std::path::PathBuf;
+/// use usnjrnl::{Renames, UsnJrnlReader};
+///
+/// let reader = UsnJrnlReader::from(&PathBuf::from("$UsnJrnl:$J")).unwrap();
+/// for rename in Renames::new(reader.into_iter()).flatten() {
+///     println!("{}: {}", rename.timestamp, rename);
+/// }
+/// ```
+pub struct Renames<I> {
+    records: I,
+    tracker: RenameTracker,
+}
+
+impl<I> Renames<I> where I: Iterator<Item=Result<CommonUsnRecord, UsnReaderError>> {
+    pub fn new(records: I) -> Self {
+        Self {
+            records,
+            tracker: RenameTracker::default(),
+        }
+    }
+}
+
+impl<I> Iterator for Renames<I> where I: Iterator<Item=Result<CommonUsnRecord, UsnReaderError>> {
+    type Item = Result<Rename, UsnReaderError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.records.next()? {
+                Err(why) => return Some(Err(why)),
+                Ok(record) => {
+                    if let Some(rename) = self.tracker.add(&record) {
+                        return Some(Ok(rename));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// all renames of a single file, in the order in which they happened
+#[derive(Clone, Debug)]
+pub struct RenameChain {
+    pub file_reference: MftReference,
+    pub renames: Vec<Rename>,
+}
+
+impl RenameChain {
+    /// returns all names of the file. If the first old name is unknown, the
+    /// chain starts with the first new name.
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+        if let Some(old_name) = self.renames.first().and_then(|r| r.old_name.as_deref()) {
+            names.push(old_name);
+        }
+        names.extend(self.renames.iter().map(|r| r.new_name.as_str()));
+        names
+    }
+
+    /// returns the oldest known name of the file
+    pub fn first_name(&self) -> &str {
+        self.names()[0]
+    }
+
+    /// returns the latest name of the file
+    pub fn last_name(&self) -> &str {
+        &self.renames.last().expect("a chain has at least one rename").new_name
+    }
+}
+
+impl fmt::Display for RenameChain {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.renames.first().is_some_and(|r| r.old_name.is_none()) {
+            write!(f, "? -> ")?;
+        }
+        write!(f, "{}", self.names().join(" -> "))
+    }
+}
+
+/// groups renames into chains per file, ordered by the first rename of each file
+///
+/// ```rust,no_run
+/// use std::path::PathBuf;
+/// use usnjrnl::{RenameChains, Renames, UsnJrnlReader};
+///
+/// let reader = UsnJrnlReader::from(&PathBuf::from("$UsnJrnl:$J")).unwrap();
+/// let chains: RenameChains = Renames::new(reader.into_iter()).flatten().collect();
+/// for chain in chains.iter().filter(|c| c.last_name().ends_with(".exe")) {
+///     println!("{}", chain);
+/// }
+/// ```
+#[derive(Default)]
+pub struct RenameChains {
+    chains: Vec<RenameChain>,
+    index: HashMap<MftReference, usize>,
+}
+
+impl RenameChains {
+    /// adds `rename` to the chain of its file. Renames must be added in the order of their USN.
+    pub fn add(&mut self, rename: Rename) {
+        let chains = &mut self.chains;
+        let idx = *self.index.entry(rename.file_reference).or_insert_with(|| {
+            chains.push(RenameChain {
+                file_reference: rename.file_reference,
+                renames: Vec::new(),
+            });
+            chains.len() - 1
+        });
+        self.chains[idx].renames.push(rename);
+    }
+
+    pub fn get(&self, file_reference: &MftReference) -> Option<&RenameChain> {
+        self.index.get(file_reference).map(|idx| &self.chains[*idx])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item=&RenameChain> {
+        self.chains.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.chains.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chains.is_empty()
+    }
+}
+
+impl FromIterator<Rename> for RenameChains {
+    fn from_iter<T: IntoIterator<Item=Rename>>(iter: T) -> Self {
+        let mut chains = Self::default();
+        for rename in iter {
+            chains.add(rename);
+        }
+        chains
+    }
+}
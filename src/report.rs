@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+use std::io::Write;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::json;
+use usnjrnl::{CommonUsnRecord, PathResolver, RenameTracker, UsnReasonValue};
+use usnjrnl::analysis::{EXECUTABLE_EXTENSIONS, extension_of};
+use usnjrnl::serialize::reference_to_string;
+use winstructs::ntfs::mft_reference::MftReference;
+
+struct Highlight {
+    timestamp: DateTime<Utc>,
+    usn: i64,
+    text: String,
+}
+
+/// collects records from a journal and creates a single, self-contained
+/// HTML file, which does not need any external resources
+pub struct HtmlReport<'r> {
+    resolver: &'r PathResolver,
+    title: String,
+    records: Vec<serde_json::Value>,
+    max_records: usize,
+    total_records: usize,
+    errors: usize,
+    first_timestamp: Option<DateTime<Utc>>,
+    last_timestamp: Option<DateTime<Utc>>,
+    reason_counts: HashMap<UsnReasonValue, usize>,
+    directory_counts: HashMap<MftReference, usize>,
+    rename_tracker: RenameTracker,
+    deletions: Vec<Highlight>,
+    renames: Vec<Highlight>,
+    executables: Vec<Highlight>,
+}
+
+impl<'r> HtmlReport<'r> {
+    pub fn new(title: &str, resolver: &'r PathResolver, max_records: usize) -> Self {
+        Self {
+            resolver,
+            title: title.to_owned(),
+            records: Vec::new(),
+            max_records,
+            total_records: 0,
+            errors: 0,
+            first_timestamp: None,
+            last_timestamp: None,
+            reason_counts: HashMap::new(),
+            directory_counts: HashMap::new(),
+            rename_tracker: RenameTracker::default(),
+            deletions: Vec::new(),
+            renames: Vec::new(),
+            executables: Vec::new(),
+        }
+    }
+
+    pub fn add_error(&mut self) {
+        self.errors += 1;
+    }
+
+    pub fn add(&mut self, record: &CommonUsnRecord) {
+        let data = &record.data;
+        let reason = data.reason();
+        let timestamp = *data.timestamp();
+        let path = self.resolver.full_path(record)
+            .unwrap_or_else(|| data.filename().to_owned());
+
+        self.total_records += 1;
+        self.first_timestamp = Some(self.first_timestamp.map_or(timestamp, |t| t.min(timestamp)));
+        self.last_timestamp = Some(self.last_timestamp.map_or(timestamp, |t| t.max(timestamp)));
+        for flag in reason.flags() {
+            *self.reason_counts.entry(flag).or_insert(0) += 1;
+        }
+        *self.directory_counts.entry(*data.parent_reference()).or_insert(0) += 1;
+
+        /* reasons accumulate until the file is closed, so we only look at the final record */
+        let closed = reason.has_flag(UsnReasonValue::USN_REASON_CLOSE);
+        if closed && reason.has_flag(UsnReasonValue::USN_REASON_FILE_DELETE) {
+            self.deletions.push(Highlight { timestamp, usn: data.usn(), text: path.clone() });
+        } else if closed && reason.has_flag(UsnReasonValue::USN_REASON_FILE_CREATE) && is_executable(data.filename()) {
+            self.executables.push(Highlight { timestamp, usn: data.usn(), text: path.clone() });
+        }
+
+        if let Some(rename) = self.rename_tracker.add(record) {
+            let old_path = match (&rename.old_parent, &rename.old_name) {
+                (Some(parent), Some(name)) => self.resolver.path_of(parent, name).unwrap_or_else(|| name.clone()),
+                _ => "?".to_owned(),
+            };
+            let text = format!("{} \u{2192} {}", old_path, path);
+
+            /* files which are created and renamed before they are closed are flagged by their final record */
+            let was_executable = rename.old_name.as_deref().is_some_and(is_executable);
+            if is_executable(&rename.new_name) && !was_executable && !reason.has_flag(UsnReasonValue::USN_REASON_FILE_CREATE) {
+                self.executables.push(Highlight { timestamp, usn: data.usn(), text: text.clone() });
+            }
+            self.renames.push(Highlight { timestamp, usn: data.usn(), text });
+        }
+
+        if self.records.len() < self.max_records {
+            self.records.push(json!([
+                timestamp.to_rfc3339_opts(SecondsFormat::Micros, true),
+                data.usn(),
+                reference_to_string(data.file_reference()),
+                reason.to_string(),
+                data.file_attributes().to_string(),
+                path,
+            ]));
+        }
+    }
+
+    pub fn write<W: Write>(&self, out: &mut W) -> std::io::Result<()> {
+        let title = html_escape(&self.title);
+        write!(out, "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{}</title>\n<style>{}</style></head>\n<body>\n<h1>{}</h1>\n",
+            title, STYLE, title)?;
+
+        writeln!(out, "<h2>Summary</h2>\n<table class=\"summary\">")?;
+        let time_span = match (self.first_timestamp, self.last_timestamp) {
+            (Some(first), Some(last)) => format!("{} &ndash; {}", first, last),
+            _ => "-".to_owned(),
+        };
+        writeln!(out, "<tr><th>time span</th><td>{}</td></tr>", time_span)?;
+        writeln!(out, "<tr><th>records</th><td>{}</td></tr>", self.total_records)?;
+        writeln!(out, "<tr><th>unreadable records</th><td>{}</td></tr>", self.errors)?;
+        writeln!(out, "<tr><th>deletions</th><td>{}</td></tr>", self.deletions.len())?;
+        writeln!(out, "<tr><th>renames</th><td>{}</td></tr>", self.renames.len())?;
+        writeln!(out, "<tr><th>new executables</th><td>{}</td></tr>", self.executables.len())?;
+        writeln!(out, "</table>")?;
+
+        let mut reasons: Vec<_> = self.reason_counts.iter().collect();
+        reasons.sort_by(|a, b| b.1.cmp(a.1));
+        writeln!(out, "<h2>Records by reason</h2>\n<table class=\"summary\">")?;
+        for (reason, count) in reasons {
+            writeln!(out, "<tr><th>{}</th><td>{}</td></tr>", reason.short_name(), count)?;
+        }
+        writeln!(out, "</table>")?;
+
+        let mut directories: Vec<_> = self.directory_counts.iter().collect();
+        directories.sort_by(|a, b| b.1.cmp(a.1));
+        writeln!(out, "<h2>Busiest directories</h2>\n<table class=\"summary\">")?;
+        for (directory, count) in directories.into_iter().take(20) {
+            let name = self.resolver.directory_path(directory)
+                .unwrap_or_else(|| format!("(unknown directory {})", reference_to_string(directory)));
+            writeln!(out, "<tr><th>{}</th><td>{}</td></tr>", html_escape(&name), count)?;
+        }
+        writeln!(out, "</table>")?;
+
+        write_highlights(out, "Deletions", "delete", &self.deletions)?;
+        write_highlights(out, "Renames", "rename", &self.renames)?;
+        write_highlights(out, "New executables", "executable", &self.executables)?;
+
+        writeln!(out, "<h2>Records</h2>")?;
+        if self.records.len() < self.total_records {
+            writeln!(out, "<p>only the first {} of {} records are contained in this report (see <code>--max-records</code>)</p>",
+                self.records.len(), self.total_records)?;
+        }
+        writeln!(out, "<input id=\"filter\" type=\"search\" placeholder=\"filter records\"> <span id=\"count\"></span>")?;
+        writeln!(out, "<table id=\"records\"><thead><tr><th>Timestamp</th><th>USN</th><th>Entry-Seq</th><th>Reason</th><th>Attributes</th><th>Name</th></tr></thead><tbody></tbody></table>")?;
+        writeln!(out, "<div id=\"pager\"><button id=\"prev\">&lt;</button> <span id=\"page\"></span> <button id=\"next\">&gt;</button></div>")?;
+
+        /* prevent the data from closing the script element */
+        let records = serde_json::to_string(&self.records)?.replace("</", "<\\/");
+        writeln!(out, "<script>const RECORDS = {};\n{}</script>", records, SCRIPT)?;
+        writeln!(out, "</body></html>")
+    }
+}
+
+fn write_highlights<W: Write>(out: &mut W, title: &str, class: &str, highlights: &[Highlight]) -> std::io::Result<()> {
+    writeln!(out, "<h2>{} ({})</h2>", title, highlights.len())?;
+    if highlights.is_empty() {
+        return Ok(());
+    }
+    writeln!(out, "<table class=\"highlight {}\"><tr><th>Timestamp</th><th>USN</th><th>Name</th></tr>", class)?;
+    for highlight in highlights {
+        writeln!(out, "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+            highlight.timestamp, highlight.usn, html_escape(&highlight.text))?;
+    }
+    writeln!(out, "</table>")
+}
+
+fn is_executable(filename: &str) -> bool {
+    extension_of(filename).is_some_and(|e| EXECUTABLE_EXTENSIONS.contains(&e.as_str()))
+}
+
+fn html_escape(value: &str) -> String {
+    value.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+const STYLE: &str = r#"
+body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; margin-bottom: 1em; }
+th, td { border: 1px solid #ccc; padding: 2px 6px; text-align: left; font-size: 90%; }
+table.summary th { background: #f0f0f0; }
+table.highlight.delete tr:nth-child(n+2) { background: #fde0e0; }
+table.highlight.rename tr:nth-child(n+2) { background: #fff4d0; }
+table.highlight.executable tr:nth-child(n+2) { background: #e0ecfd; }
+#records th { cursor: pointer; background: #f0f0f0; }
+#filter { width: 30em; margin-bottom: 0.5em; }
+"#;
+
+const SCRIPT: &str = r#"
+const PAGE_SIZE = 500;
+let rows = RECORDS;
+let page = 0;
+let sortColumn = 0;
+let sortAscending = true;
+
+function render() {
+    const body = document.querySelector('#records tbody');
+    const pages = Math.max(1, Math.ceil(rows.length / PAGE_SIZE));
+    page = Math.min(page, pages - 1);
+    body.replaceChildren(...rows.slice(page * PAGE_SIZE, (page + 1) * PAGE_SIZE).map(record => {
+        const tr = document.createElement('tr');
+        for (const value of record) {
+            const td = document.createElement('td');
+            td.textContent = value;
+            tr.appendChild(td);
+        }
+        return tr;
+    }));
+    document.getElementById('page').textContent = (page + 1) + ' / ' + pages;
+    document.getElementById('count').textContent = rows.length + ' of ' + RECORDS.length + ' records';
+}
+
+function sortRows() {
+    rows.sort((a, b) => {
+        const result = a[sortColumn] < b[sortColumn] ? -1 : (a[sortColumn] > b[sortColumn] ? 1 : 0);
+        return sortAscending ? result : -result;
+    });
+}
+
+document.getElementById('filter').addEventListener('input', event => {
+    const needle = event.target.value.toLowerCase();
+    rows = RECORDS.filter(record => record.some(value => String(value).toLowerCase().includes(needle)));
+    sortRows();
+    page = 0;
+    render();
+});
+
+document.querySelectorAll('#records th').forEach((th, column) => th.addEventListener('click', () => {
+    sortAscending = (sortColumn === column) ? !sortAscending : true;
+    sortColumn = column;
+    sortRows();
+    render();
+}));
+
+document.getElementById('prev').addEventListener('click', () => { page = Math.max(0, page - 1); render(); });
+document.getElementById('next').addEventListener('click', () => { page += 1; render(); });
+
+rows = RECORDS.slice();
+render();
+"#;
@@ -0,0 +1,487 @@
+//! detection rules, which are written as data instead of code.
+//!
+//! Rules are read from YAML, one rule per document or a list of rules per
+//! document. A rule matches a record if all conditions of its `detection`
+//! section match, unless all conditions of its optional `exclude` section
+//! match as well:
+//!
+//! ```yaml
+//! id: script-in-temp
+//! title: script dropped into a temporary directory
+//! level: high
+//! detection:
+//!   reason: FILE_CREATE
+//!   name: ["*.ps1", "*.vbs", "*.js"]
+//!   path|startswith: /Users/
+//!   time|between: ["22:00", "06:00"]
+//! exclude:
+//!   name|re: "^__PSScriptPolicyTest_"
+//! ---
+//! id: mass-delete
+//! detection:
+//!   reason: FILE_DELETE
+//! correlation:
+//!   count: 100
+//!   within: 10
+//!   group_by: parent
+//! ```
+//!
+//! | condition | matches if |
+//! |-|-|
+//! | `reason`, `attributes` | any of the flags is set |
+//! | `reason\|all`, `attributes\|all` | all of the flags are set |
+//! | `name` | the name matches any of the wildcard patterns |
+//! | `name\|re` | the name matches any of the regular expressions |
+//! | `path\|startswith` | the full path starts with any of the prefixes |
+//! | `time\|between` | the time of day (UTC) is in the range, which may wrap around midnight |
+//!
+//! Every condition accepts a single value or a list of values. Case is
+//! ignored, and flags can be given with or without the `USN_REASON_` or
+//! `FILE_ATTRIBUTE_` prefix. Paths are resolved from the records seen so far,
+//! so `path|startswith` only matches if the parent directory had activity
+//! before.
+//!
+//! With a `correlation`, a rule only raises an alert if it matched `count`
+//! records within `within` seconds, in the same parent directory
+//! (`group_by: parent`), of the same file (`group_by: file`) or anywhere
+//! (`group_by: none`, the default).
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::str::FromStr;
+use chrono::{DateTime, Duration, NaiveTime, Timelike, Utc};
+use serde::Deserialize;
+use winstructs::ntfs::mft_reference::MftReference;
+
+use crate::analysis::{Analyzer, RecordRef};
+use crate::filter::{AllOf, AttributeFilter, MatchMode, NameGlob, NameRegex, ReasonFilter, RecordFilter};
+use crate::{CommonUsnRecord, FileAttributeValue, PathResolver, UsnReaderError, UsnReasonValue};
+#[cfg(feature = "formatters")]
+use crate::serialize::timestamp_to_string;
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Default for OneOrMany {
+    fn default() -> Self {
+        Self::Many(Vec::new())
+    }
+}
+
+impl OneOrMany {
+    fn values(&self) -> &[String] {
+        match self {
+            Self::One(value) => std::slice::from_ref(value),
+            Self::Many(values) => values,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleDefinition {
+    id: String,
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    level: Option<String>,
+    detection: DetectionDefinition,
+    #[serde(default)]
+    exclude: Option<DetectionDefinition>,
+    #[serde(default)]
+    correlation: Option<CorrelationDefinition>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DetectionDefinition {
+    #[serde(default)]
+    reason: OneOrMany,
+    #[serde(default, rename = "reason|all")]
+    reason_all: OneOrMany,
+    #[serde(default)]
+    attributes: OneOrMany,
+    #[serde(default, rename = "attributes|all")]
+    attributes_all: OneOrMany,
+    #[serde(default)]
+    name: OneOrMany,
+    #[serde(default, rename = "name|re")]
+    name_regex: OneOrMany,
+    #[serde(default, rename = "path|startswith")]
+    path_prefix: OneOrMany,
+    #[serde(default, rename = "time|between")]
+    time_between: Option<(String, String)>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CorrelationDefinition {
+    count: usize,
+    within: u32,
+    #[serde(default)]
+    group_by: Option<String>,
+}
+
+/// specifies which matching records are counted together by a [`Correlation`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GroupBy {
+    None,
+    Parent,
+    File,
+}
+
+impl FromStr for GroupBy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "none" => Ok(Self::None),
+            "parent" => Ok(Self::Parent),
+            "file" => Ok(Self::File),
+            _ => Err(format!("invalid group_by: '{}', expected 'none', 'parent' or 'file'", value)),
+        }
+    }
+}
+
+/// raises an alert only if `count` records have matched within `within`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Correlation {
+    pub count: usize,
+    pub within: Duration,
+    pub group_by: GroupBy,
+}
+
+/// the conditions of a rule, which must all be met by a record
+struct Detection {
+    filters: AllOf,
+    path_prefixes: Vec<String>,
+    time_between: Option<(NaiveTime, NaiveTime)>,
+}
+
+impl Detection {
+    fn compile(definition: &DetectionDefinition) -> Result<Self, String> {
+        let mut filters = AllOf::default();
+        add_flag_filter(&mut filters, &definition.reason, MatchMode::Any, |f: &[UsnReasonValue], m| ReasonFilter::new(f, m))?;
+        add_flag_filter(&mut filters, &definition.reason_all, MatchMode::All, |f: &[UsnReasonValue], m| ReasonFilter::new(f, m))?;
+        add_flag_filter(&mut filters, &definition.attributes, MatchMode::Any, |f: &[FileAttributeValue], m| AttributeFilter::new(f, m))?;
+        add_flag_filter(&mut filters, &definition.attributes_all, MatchMode::All, |f: &[FileAttributeValue], m| AttributeFilter::new(f, m))?;
+
+        if !definition.name.values().is_empty() {
+            let globs = definition.name.values().iter()
+                .map(|pattern| NameGlob::new(pattern).map_err(|why| format!("invalid name pattern '{}': {}", pattern, why)))
+                .collect::<Result<Vec<_>, _>>()?;
+            filters.push(move |record: &CommonUsnRecord| globs.iter().any(|g| g.is_match(record.data.filename())));
+        }
+        if !definition.name_regex.values().is_empty() {
+            let regexes = definition.name_regex.values().iter()
+                .map(|pattern| NameRegex::new(pattern).map_err(|why| format!("invalid regular expression '{}': {}", pattern, why)))
+                .collect::<Result<Vec<_>, _>>()?;
+            filters.push(move |record: &CommonUsnRecord| regexes.iter().any(|r| r.is_match(record.data.filename())));
+        }
+
+        let time_between = match &definition.time_between {
+            None => None,
+            Some((from, to)) => Some((parse_time_of_day(from)?, parse_time_of_day(to)?)),
+        };
+        Ok(Self {
+            filters,
+            path_prefixes: definition.path_prefix.values().iter().map(|p| normalize_path(p)).collect(),
+            time_between,
+        })
+    }
+
+    fn uses_path(&self) -> bool {
+        !self.path_prefixes.is_empty()
+    }
+
+    fn matches(&self, record: &CommonUsnRecord, resolver: &PathResolver) -> bool {
+        if !self.filters.matches(record) {
+            return false;
+        }
+        if let Some((from, to)) = self.time_between {
+            let time = record.data.timestamp().time();
+            let time = NaiveTime::from_hms_opt(time.hour(), time.minute(), time.second()).unwrap();
+            let in_range = if from <= to {
+                from <= time && time < to
+            } else {
+                from <= time || time < to
+            };
+            if !in_range {
+                return false;
+            }
+        }
+        if self.uses_path() {
+            let path = match resolver.full_path(record) {
+                Some(path) => normalize_path(&path),
+                None => return false,
+            };
+            if !self.path_prefixes.iter().any(|prefix| path.starts_with(prefix)) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+fn add_flag_filter<T, F>(filters: &mut AllOf, names: &OneOrMany, mode: MatchMode, create: impl Fn(&[T], MatchMode) -> F) -> Result<(), String>
+where
+    T: FromStr<Err=String>,
+    F: RecordFilter + 'static,
+{
+    if names.values().is_empty() {
+        return Ok(());
+    }
+    let flags = names.values().iter()
+        .map(|name| T::from_str(name))
+        .collect::<Result<Vec<_>, _>>()?;
+    filters.push(create(&flags, mode));
+    Ok(())
+}
+
+fn parse_time_of_day(value: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(value, "%H:%M:%S")
+        .or_else(|_| NaiveTime::parse_from_str(value, "%H:%M"))
+        .map_err(|_| format!("invalid time of day: '{}', expected HH:MM or HH:MM:SS", value))
+}
+
+/// converts `path` to lowercase with `/` as separator
+fn normalize_path(path: &str) -> String {
+    path.replace('\\', "/").to_lowercase()
+}
+
+/// a compiled detection rule
+pub struct Rule {
+    pub id: String,
+    pub title: Option<String>,
+    pub level: Option<String>,
+    pub correlation: Option<Correlation>,
+    detection: Detection,
+    exclude: Option<Detection>,
+}
+
+impl Rule {
+    /// reads all rules from `text`, which may contain multiple YAML documents
+    pub fn from_yaml(text: &str) -> Result<Vec<Self>, String> {
+        let mut rules = Vec::new();
+        for document in serde_yaml::Deserializer::from_str(text) {
+            let value = serde_yaml::Value::deserialize(document).map_err(|why| format!("invalid rule: {}", why))?;
+            let definitions: Vec<RuleDefinition> = match value {
+                serde_yaml::Value::Null => Ok(Vec::new()),
+                serde_yaml::Value::Sequence(_) => serde_yaml::from_value(value),
+                _ => serde_yaml::from_value(value).map(|definition| vec![definition]),
+            }.map_err(|why| format!("invalid rule: {}", why))?;
+            for definition in definitions {
+                let id = definition.id.clone();
+                rules.push(Self::compile(definition).map_err(|why| format!("invalid rule '{}': {}", id, why))?);
+            }
+        }
+        Self::check_unique_ids(&rules)?;
+        Ok(rules)
+    }
+
+    /// fails if two rules have the same id, e.g. after reading multiple files
+    pub fn check_unique_ids(rules: &[Self]) -> Result<(), String> {
+        let mut ids = HashSet::new();
+        for rule in rules {
+            if !ids.insert(rule.id.as_str()) {
+                return Err(format!("duplicate rule id: '{}'", rule.id));
+            }
+        }
+        Ok(())
+    }
+
+    fn compile(definition: RuleDefinition) -> Result<Self, String> {
+        let correlation = match definition.correlation {
+            None => None,
+            Some(correlation) => Some(Correlation {
+                count: correlation.count.max(1),
+                within: Duration::seconds(correlation.within.into()),
+                group_by: correlation.group_by.as_deref().map_or(Ok(GroupBy::None), GroupBy::from_str)?,
+            }),
+        };
+        Ok(Self {
+            id: definition.id,
+            title: definition.title,
+            level: definition.level,
+            correlation,
+            detection: Detection::compile(&definition.detection)?,
+            exclude: definition.exclude.as_ref().map(Detection::compile).transpose()?,
+        })
+    }
+
+    fn uses_path(&self) -> bool {
+        self.detection.uses_path() || self.exclude.as_ref().is_some_and(Detection::uses_path)
+    }
+
+    /// returns `true` if `record` matches this rule, without regarding the correlation
+    pub fn matches(&self, record: &CommonUsnRecord, resolver: &PathResolver) -> bool {
+        self.detection.matches(record, resolver)
+            && !self.exclude.as_ref().is_some_and(|exclude| exclude.matches(record, resolver))
+    }
+}
+
+/// a rule which matched one record, or enough records to satisfy its correlation
+#[derive(Clone, Debug)]
+pub struct RuleAlert {
+    pub rule_id: String,
+    pub title: Option<String>,
+    pub level: Option<String>,
+
+    /// the timestamp of the last matching record
+    pub timestamp: DateTime<Utc>,
+
+    /// the matching records, in the order of their USN
+    pub records: Vec<RecordRef>,
+}
+
+impl RuleAlert {
+    #[cfg(feature = "formatters")]
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "rule": self.rule_id,
+            "title": self.title,
+            "level": self.level,
+            "timestamp": timestamp_to_string(&self.timestamp),
+            "usns": self.records.iter().map(|r| r.usn).collect::<Vec<_>>(),
+            "records": self.records.iter().map(RecordRef::to_json).collect::<Vec<_>>(),
+        })
+    }
+}
+
+/// evaluates rules on one record at a time.
+///
+/// A rule with a correlation keeps the matching records of the last
+/// `within` seconds per group. As soon as there are `count` of them, an alert
+/// is raised and the group starts over.
+pub struct RuleEngine {
+    rules: Vec<Rule>,
+    resolver: PathResolver,
+    uses_path: bool,
+
+    /// the recent matches of every rule with a correlation, by group
+    windows: Vec<HashMap<Option<MftReference>, VecDeque<RecordRef>>>,
+    alerts: Vec<RuleAlert>,
+}
+
+impl Analyzer for RuleEngine {
+    fn add(&mut self, record: &CommonUsnRecord) {
+        let alerts = self.process(record);
+        self.alerts.extend(alerts);
+    }
+}
+
+impl RuleEngine {
+    pub fn new(rules: Vec<Rule>) -> Self {
+        Self {
+            uses_path: rules.iter().any(Rule::uses_path),
+            windows: rules.iter().map(|_| HashMap::new()).collect(),
+            rules,
+            resolver: PathResolver::default(),
+            alerts: Vec::new(),
+        }
+    }
+
+    /// returns the alerts, which are raised by `record`.
+    /// Records must be added in the order of their USN.
+    pub fn process(&mut self, record: &CommonUsnRecord) -> Vec<RuleAlert> {
+        if self.uses_path {
+            self.resolver.learn(record);
+        }
+
+        let mut alerts = Vec::new();
+        for (rule, windows) in self.rules.iter().zip(self.windows.iter_mut()) {
+            if !rule.matches(record, &self.resolver) {
+                continue;
+            }
+            let record_ref = RecordRef::from(record);
+            let correlation = match &rule.correlation {
+                None => {
+                    alerts.push(alert(rule, vec![record_ref]));
+                    continue;
+                }
+                Some(correlation) => correlation,
+            };
+
+            let group = match correlation.group_by {
+                GroupBy::None => None,
+                GroupBy::Parent => Some(record_ref.parent_reference),
+                GroupBy::File => Some(record_ref.file_reference),
+            };
+            let window = windows.entry(group).or_default();
+            let start = record_ref.timestamp - correlation.within;
+            while window.front().is_some_and(|r| r.timestamp < start) {
+                window.pop_front();
+            }
+            window.push_back(record_ref);
+            if window.len() >= correlation.count {
+                alerts.push(alert(rule, window.drain(..).collect()));
+            }
+        }
+        alerts
+    }
+
+    /// returns all alerts, which have been raised by records passed to [`Analyzer::add`]
+    pub fn finish(self) -> Vec<RuleAlert> {
+        self.alerts
+    }
+}
+
+fn alert(rule: &Rule, records: Vec<RecordRef>) -> RuleAlert {
+    RuleAlert {
+        rule_id: rule.id.clone(),
+        title: rule.title.clone(),
+        level: rule.level.clone(),
+        timestamp: records.last().unwrap().timestamp,
+        records,
+    }
+}
+
+/// evaluates rules on all records of an iterator, and yields the alerts as soon as they are raised.
+///
+/// Errors of the underlying iterator are passed through.
+///
+/// ```rust,no_run
+/// use std::path::PathBuf;
+/// use usnjrnl::UsnJrnlReader;
+/// use usnjrnl::rules::{Rule, RuleAlerts, RuleEngine};
+///
+/// let rules = Rule::from_yaml(&std::fs::read_to_string("rules.yaml").unwrap()).unwrap();
+/// let reader = UsnJrnlReader::from(&PathBuf::from("$UsnJrnl:$J")).unwrap();
+/// for alert in RuleAlerts::new(RuleEngine::new(rules), reader.into_iter()).flatten() {
+///     println!("{}: {} records", alert.rule_id, alert.records.len());
+/// }
+/// ```
+pub struct RuleAlerts<I> {
+    engine: RuleEngine,
+    records: I,
+    pending: VecDeque<RuleAlert>,
+}
+
+impl<I> RuleAlerts<I> where I: Iterator<Item=Result<CommonUsnRecord, UsnReaderError>> {
+    pub fn new(engine: RuleEngine, records: I) -> Self {
+        Self {
+            engine,
+            records,
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+impl<I> Iterator for RuleAlerts<I> where I: Iterator<Item=Result<CommonUsnRecord, UsnReaderError>> {
+    type Item = Result<RuleAlert, UsnReaderError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(alert) = self.pending.pop_front() {
+                return Some(Ok(alert));
+            }
+            match self.records.next()? {
+                Err(why) => return Some(Err(why)),
+                Ok(record) => self.pending.extend(self.engine.process(&record)),
+            }
+        }
+    }
+}
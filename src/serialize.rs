@@ -0,0 +1,13 @@
+//! helpers to convert values into the text, which all output formats use for them
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use winstructs::ntfs::mft_reference::MftReference;
+
+/// returns the entry number and the sequence number, like `12345-3`
+pub fn reference_to_string(reference: &MftReference) -> String {
+    format!("{}-{}", reference.entry, reference.sequence)
+}
+
+pub fn timestamp_to_string(timestamp: &DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
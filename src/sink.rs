@@ -0,0 +1,275 @@
+use std::fs::File;
+use std::io::{self, BufWriter, ErrorKind, Write};
+use std::net::{TcpStream, ToSocketAddrs, UdpSocket};
+use std::thread;
+use std::time::Duration;
+
+/// how often we try to reconnect a lost TCP connection before we give up
+const TCP_RECONNECT_ATTEMPTS: u32 = 5;
+
+/// how long a single write may block before we log that the receiver is slow
+const TCP_WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// how many writes in a row may time out before we give up on a stalled receiver
+const TCP_WRITE_TIMEOUTS: u32 = 12;
+
+/// opens the destination of the output, which can be `-` (stdout),
+/// `udp://host:port`, `tcp://host:port` or the path of a file
+pub fn open_sink(destination: &str) -> io::Result<Box<dyn Write>> {
+    if destination == "-" {
+        Ok(Box::new(BufWriter::new(io::stdout())))
+    } else if let Some(address) = destination.strip_prefix("udp://") {
+        Ok(Box::new(UdpSink::connect(address)?))
+    } else if let Some(address) = destination.strip_prefix("tcp://") {
+        Ok(Box::new(BufWriter::new(TcpSink::connect(address)?)))
+    } else {
+        Ok(Box::new(BufWriter::new(File::create(destination)?)))
+    }
+}
+
+/// sends every line as a single datagram, without the trailing newline
+struct UdpSink {
+    socket: UdpSocket,
+    buffer: Vec<u8>,
+}
+
+impl UdpSink {
+    fn connect(address: &str) -> io::Result<Self> {
+        let target = resolve(address)?;
+        let local = if target.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" };
+        let socket = UdpSocket::bind(local)?;
+        socket.connect(target)?;
+        Ok(Self {
+            socket,
+            buffer: Vec::new(),
+        })
+    }
+}
+
+impl Write for UdpSink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        while let Some(end) = self.buffer.iter().position(|b| *b == b'\n') {
+            self.socket.send(&self.buffer[..end])?;
+            self.buffer.drain(..=end);
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.buffer.is_empty() {
+            self.socket.send(&self.buffer)?;
+            self.buffer.clear();
+        }
+        Ok(())
+    }
+}
+
+/// writes newline-delimited messages to a TCP connection.
+///
+/// If the receiver cannot keep up, writing blocks until the receiver has
+/// accepted the data, so that no messages are lost. If the receiver does not
+/// accept any data for a minute, we give up. A lost connection is
+/// reestablished a few times before we give up. Messages are only written as
+/// a whole: if the connection is lost while a message is being sent, the
+/// whole message is sent again on the new connection, so that the receiver
+/// never gets the rest of a message without its beginning.
+struct TcpSink<S: Write = TcpStream> {
+    address: String,
+    open: Box<dyn Fn() -> io::Result<S>>,
+    stream: S,
+
+    /// data, which has not been sent yet. It always starts with the beginning of a message
+    buffer: Vec<u8>,
+}
+
+impl TcpSink {
+    fn connect(address: &str) -> io::Result<Self> {
+        let target = address.to_owned();
+        Self::with_stream(address, Box::new(move || Self::open_stream(&target)))
+    }
+
+    fn open_stream(address: &str) -> io::Result<TcpStream> {
+        let stream = TcpStream::connect(resolve(address)?)?;
+        stream.set_write_timeout(Some(TCP_WRITE_TIMEOUT))?;
+        Ok(stream)
+    }
+}
+
+impl<S: Write> TcpSink<S> {
+    fn with_stream(address: &str, open: Box<dyn Fn() -> io::Result<S>>) -> io::Result<Self> {
+        Ok(Self {
+            address: address.to_owned(),
+            stream: open()?,
+            open,
+            buffer: Vec::new(),
+        })
+    }
+
+    fn reconnect(&mut self) -> io::Result<()> {
+        let mut last_error = None;
+        for attempt in 1..=TCP_RECONNECT_ATTEMPTS {
+            thread::sleep(Duration::from_millis(200 * attempt as u64));
+            match (self.open)() {
+                Ok(stream) => {
+                    log::warn!("reconnected to {}", self.address);
+                    self.stream = stream;
+                    return Ok(());
+                }
+                Err(why) => {
+                    log::warn!("unable to reconnect to {} (attempt {}): {}", self.address, attempt, why);
+                    last_error = Some(why);
+                }
+            }
+        }
+        Err(last_error.unwrap())
+    }
+
+    /// sends the first `length` bytes of the buffer, which end with the end of a message
+    fn send(&mut self, length: usize) -> io::Result<()> {
+        let mut sent = 0;
+        let mut timeouts = 0;
+        while sent < length {
+            match self.stream.write(&self.buffer[sent..length]) {
+                Ok(0) => return Err(ErrorKind::WriteZero.into()),
+                Ok(bytes) => {
+                    sent += bytes;
+                    timeouts = 0;
+                }
+                Err(why) => match why.kind() {
+                    ErrorKind::Interrupted => (),
+                    ErrorKind::WouldBlock | ErrorKind::TimedOut => {
+                        timeouts += 1;
+                        if timeouts >= TCP_WRITE_TIMEOUTS {
+                            log::error!("{} has not accepted any data for {} seconds, giving up",
+                                self.address, TCP_WRITE_TIMEOUTS as u64 * TCP_WRITE_TIMEOUT.as_secs());
+                            return Err(why);
+                        }
+                        log::warn!("{} does not accept more data, waiting", self.address);
+                    }
+                    ErrorKind::BrokenPipe | ErrorKind::ConnectionReset | ErrorKind::ConnectionAborted => {
+                        log::warn!("lost connection to {}: {}", self.address, why);
+                        self.reconnect()?;
+
+                        /* start again with the message, which has not been sent completely */
+                        let message_start = self.buffer[..sent].iter().rposition(|b| *b == b'\n').map_or(0, |end| end + 1);
+                        if message_start < sent {
+                            log::warn!("sending an incomplete message to {} again", self.address);
+                        }
+                        sent = message_start;
+                    }
+                    _ => return Err(why),
+                }
+            }
+        }
+        self.buffer.drain(..length);
+        Ok(())
+    }
+}
+
+impl<S: Write> Write for TcpSink<S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        if let Some(end) = self.buffer.iter().rposition(|b| *b == b'\n') {
+            self.send(end + 1)?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.buffer.is_empty() {
+            self.send(self.buffer.len())?;
+        }
+        self.stream.flush()
+    }
+}
+
+fn resolve(address: &str) -> io::Result<std::net::SocketAddr> {
+    address.to_socket_addrs()?.next().ok_or_else(|| {
+        io::Error::new(ErrorKind::InvalidInput, format!("unable to resolve '{}'", address))
+    })
+}
+
+/* unlike the other tests, these are unit tests: the sink is part of the
+ * binary, and a reconnect can only be forced by replacing the TCP stream */
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::io::{self, ErrorKind, Write};
+    use std::rc::Rc;
+    use super::{TcpSink, TCP_WRITE_TIMEOUTS};
+
+    /// a connection, which is lost after it has accepted `capacity` bytes
+    struct FlakyStream {
+        capacity: usize,
+        received: Rc<RefCell<Vec<Vec<u8>>>>,
+    }
+
+    impl Write for FlakyStream {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            let mut received = self.received.borrow_mut();
+            let connection = received.last_mut().unwrap();
+            if connection.len() >= self.capacity {
+                return Err(ErrorKind::BrokenPipe.into());
+            }
+            let bytes = buf.len().min(self.capacity - connection.len());
+            connection.extend_from_slice(&buf[..bytes]);
+            Ok(bytes)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_reconnect_in_the_middle_of_a_message() {
+        let received = Rc::new(RefCell::new(Vec::new()));
+        let connections = Rc::clone(&received);
+        let mut sink = TcpSink::with_stream("test", Box::new(move || {
+            /* the first connection is lost in the middle of the second message */
+            let capacity = if connections.borrow().is_empty() { 15 } else { usize::MAX };
+            connections.borrow_mut().push(Vec::new());
+            Ok(FlakyStream { capacity, received: Rc::clone(&connections) })
+        })).unwrap();
+
+        sink.write_all(b"first message\nsecond ").unwrap();
+        sink.write_all(b"message\nthird message\n").unwrap();
+        sink.write_all(b"incomplete").unwrap();
+        sink.flush().unwrap();
+
+        let received = received.borrow();
+        assert_eq!(received.len(), 2);
+        assert_eq!(received[0], b"first message\ns");
+        assert_eq!(received[1], b"second message\nthird message\nincomplete");
+    }
+
+    /// a receiver, which does not accept any data
+    struct StalledStream {
+        writes: Rc<RefCell<u32>>,
+    }
+
+    impl Write for StalledStream {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            *self.writes.borrow_mut() += 1;
+            Err(ErrorKind::TimedOut.into())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_stalled_receiver() {
+        let writes = Rc::new(RefCell::new(0));
+        let counter = Rc::clone(&writes);
+        let mut sink = TcpSink::with_stream("test", Box::new(move || {
+            Ok(StalledStream { writes: Rc::clone(&counter) })
+        })).unwrap();
+
+        let error = sink.write_all(b"message\n").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TimedOut);
+        assert_eq!(*writes.borrow(), TCP_WRITE_TIMEOUTS);
+    }
+}
@@ -0,0 +1,182 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::Write;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::json;
+use usnjrnl::{CommonUsnRecord, PathResolver, UsnReaderError, UsnReasonValue};
+use usnjrnl::analysis::extension_of;
+use usnjrnl::serialize::reference_to_string;
+use winstructs::ntfs::mft_reference::MftReference;
+
+/// maximum width of the bars of a histogram
+const BAR_WIDTH: usize = 50;
+
+/// counts everything we want to know about a journal before we take a closer look
+pub struct JournalStatistics {
+    top: usize,
+    records: usize,
+    errors: BTreeMap<&'static str, usize>,
+    first_timestamp: Option<DateTime<Utc>>,
+    last_timestamp: Option<DateTime<Utc>>,
+    first_usn: Option<i64>,
+    last_usn: Option<i64>,
+    reasons: HashMap<UsnReasonValue, usize>,
+    per_day: BTreeMap<String, usize>,
+    per_hour: BTreeMap<String, usize>,
+    directories: HashMap<MftReference, usize>,
+    extensions: HashMap<String, usize>,
+    files: HashSet<MftReference>,
+
+    /// learns the names of directories while we are counting, so that we need only one pass
+    resolver: PathResolver,
+}
+
+impl JournalStatistics {
+    /// creates empty statistics, which list the `top` most active directories and extensions
+    pub fn new(top: usize) -> Self {
+        Self {
+            top,
+            records: 0,
+            errors: BTreeMap::new(),
+            first_timestamp: None,
+            last_timestamp: None,
+            first_usn: None,
+            last_usn: None,
+            reasons: HashMap::new(),
+            per_day: BTreeMap::new(),
+            per_hour: BTreeMap::new(),
+            directories: HashMap::new(),
+            extensions: HashMap::new(),
+            files: HashSet::new(),
+            resolver: PathResolver::default(),
+        }
+    }
+
+    pub fn add(&mut self, record: &CommonUsnRecord) {
+        let data = &record.data;
+        let timestamp = *data.timestamp();
+        let usn = data.usn();
+
+        self.records += 1;
+        self.first_timestamp = Some(self.first_timestamp.map_or(timestamp, |t| t.min(timestamp)));
+        self.last_timestamp = Some(self.last_timestamp.map_or(timestamp, |t| t.max(timestamp)));
+        self.first_usn = Some(self.first_usn.map_or(usn, |u| u.min(usn)));
+        self.last_usn = Some(self.last_usn.map_or(usn, |u| u.max(usn)));
+        for flag in data.reason().flags() {
+            *self.reasons.entry(flag).or_insert(0) += 1;
+        }
+        *self.per_day.entry(timestamp.format("%Y-%m-%d").to_string()).or_insert(0) += 1;
+        *self.per_hour.entry(timestamp.format("%Y-%m-%d %H:00").to_string()).or_insert(0) += 1;
+        *self.directories.entry(*data.parent_reference()).or_insert(0) += 1;
+        if let Some(extension) = extension_of(data.filename()) {
+            *self.extensions.entry(extension).or_insert(0) += 1;
+        }
+        self.files.insert(*data.file_reference());
+        self.resolver.learn(record);
+    }
+
+    pub fn add_error(&mut self, error: &UsnReaderError) {
+        let variant = match error {
+            UsnReaderError::IO(_) => "IO",
+            UsnReaderError::BinRead(_) => "BinRead",
+            UsnReaderError::SyntaxError(_) => "SyntaxError",
+            UsnReaderError::FailedToReadWindowsTime(_) => "FailedToReadWindowsTime",
+            UsnReaderError::NoMoreData => "NoMoreData",
+            UsnReaderError::UsnNotFound(_) => "UsnNotFound",
+        };
+        *self.errors.entry(variant).or_insert(0) += 1;
+    }
+
+    /// returns the most active directories, as (path, reference, number of records)
+    fn top_directories(&self) -> Vec<(String, &MftReference, usize)> {
+        top(&self.directories, self.top, |d| (d.entry, d.sequence)).into_iter()
+            .map(|(directory, count)| {
+                let path = self.resolver.directory_path(directory)
+                    .unwrap_or_else(|| "(unknown)".to_owned());
+                (path, directory, count)
+            })
+            .collect()
+    }
+
+    pub fn write_json<W: Write>(&self, out: &mut W) -> std::io::Result<()> {
+        let timestamp = |t: Option<DateTime<Utc>>| t.map(|t| t.to_rfc3339_opts(SecondsFormat::Micros, true));
+        let reasons: BTreeMap<_, _> = self.reasons.iter().map(|(r, c)| (r.short_name(), c)).collect();
+        let statistics = json!({
+            "records": self.records,
+            "errors": self.errors,
+            "distinct_files": self.files.len(),
+            "first_timestamp": timestamp(self.first_timestamp),
+            "last_timestamp": timestamp(self.last_timestamp),
+            "first_usn": self.first_usn,
+            "last_usn": self.last_usn,
+            "reasons": reasons,
+            "per_day": self.per_day,
+            "per_hour": self.per_hour,
+            "top_directories": self.top_directories().into_iter().map(|(path, reference, count)| json!({
+                "path": path,
+                "reference": reference_to_string(reference),
+                "records": count,
+            })).collect::<Vec<_>>(),
+            "top_extensions": top(&self.extensions, self.top, String::clone).into_iter().map(|(extension, count)| json!({
+                "extension": extension,
+                "records": count,
+            })).collect::<Vec<_>>(),
+        });
+        writeln!(out, "{}", serde_json::to_string_pretty(&statistics)?)
+    }
+
+    pub fn write_text<W: Write>(&self, out: &mut W) -> std::io::Result<()> {
+        let timestamp = |t: Option<DateTime<Utc>>| t.map_or("-".to_owned(), |t| t.format("%Y-%m-%d %H:%M:%S%.6f UTC").to_string());
+        let usn = |u: Option<i64>| u.map_or("-".to_owned(), |u| u.to_string());
+        writeln!(out, "records:            {}", self.records)?;
+        writeln!(out, "unreadable records: {}", self.errors.values().sum::<usize>())?;
+        for (variant, count) in &self.errors {
+            writeln!(out, "  {:<24}{:>10}", variant, count)?;
+        }
+        writeln!(out, "distinct files:     {}", self.files.len())?;
+        writeln!(out, "first timestamp:    {}", timestamp(self.first_timestamp))?;
+        writeln!(out, "last timestamp:     {}", timestamp(self.last_timestamp))?;
+        writeln!(out, "first USN:          {}", usn(self.first_usn))?;
+        writeln!(out, "last USN:           {}", usn(self.last_usn))?;
+
+        writeln!(out, "\nrecords by reason:")?;
+        let mut reasons: Vec<_> = self.reasons.iter().map(|(r, c)| (r.short_name(), *c)).collect();
+        reasons.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        for (reason, count) in reasons {
+            writeln!(out, "  {:<30}{:>10}", reason, count)?;
+        }
+
+        writeln!(out, "\nrecords per day:")?;
+        write_histogram(out, &self.per_day)?;
+        writeln!(out, "\nrecords per hour:")?;
+        write_histogram(out, &self.per_hour)?;
+
+        writeln!(out, "\ntop {} directories:", self.top)?;
+        for (path, reference, count) in self.top_directories() {
+            writeln!(out, "  {:>10}  {} ({})", count, path, reference_to_string(reference))?;
+        }
+
+        writeln!(out, "\ntop {} extensions:", self.top)?;
+        for (extension, count) in top(&self.extensions, self.top, String::clone) {
+            writeln!(out, "  {:>10}  {}", count, extension)?;
+        }
+        Ok(())
+    }
+}
+
+/// returns the `n` keys with the highest counts, in descending order. Keys
+/// with the same count are ordered by `key_order`, to get a stable result.
+fn top<K, O: Ord>(counts: &HashMap<K, usize>, n: usize, key_order: impl Fn(&K) -> O) -> Vec<(&K, usize)> {
+    let mut entries: Vec<_> = counts.iter().map(|(k, c)| (k, *c)).collect();
+    entries.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| key_order(a.0).cmp(&key_order(b.0))));
+    entries.truncate(n);
+    entries
+}
+
+fn write_histogram<W: Write>(out: &mut W, counts: &BTreeMap<String, usize>) -> std::io::Result<()> {
+    let max = counts.values().copied().max().unwrap_or(0).max(1);
+    for (key, count) in counts {
+        let bar = (count * BAR_WIDTH).div_ceil(max);
+        writeln!(out, "  {:<18}{:>10} {}", key, count, "#".repeat(bar))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,79 @@
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use std::fmt;
+use std::str::FromStr;
+
+/// timezone which is used to display date and time
+#[derive(Clone, Copy)]
+pub enum DisplayTimezone {
+    Local,
+    Fixed(FixedOffset),
+}
+
+impl DisplayTimezone {
+    pub fn format(&self, timestamp: &DateTime<Utc>, fmt: &str) -> String {
+        match self {
+            Self::Local => timestamp.with_timezone(&Local).format(fmt).to_string(),
+            Self::Fixed(offset) => timestamp.with_timezone(offset).format(fmt).to_string(),
+        }
+    }
+
+    /// returns the name of the timezone, or the offset which was valid at `timestamp`
+    pub fn name_at(&self, timestamp: &DateTime<Utc>) -> String {
+        match self {
+            Self::Fixed(offset) if offset.local_minus_utc() == 0 => "UTC".to_owned(),
+            Self::Fixed(offset) => offset.to_string(),
+            Self::Local => timestamp.with_timezone(&Local).offset().to_string(),
+        }
+    }
+
+    /// parses a timestamp, which is either given in RFC 3339 format or as date
+    /// and time without offset, which is interpreted in this timezone
+    pub fn parse_timestamp(&self, value: &str) -> Result<DateTime<Utc>, String> {
+        if let Ok(timestamp) = DateTime::parse_from_rfc3339(value) {
+            return Ok(timestamp.with_timezone(&Utc));
+        }
+        let naive = ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M"].iter()
+            .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+            .or_else(|| NaiveDate::parse_from_str(value, "%Y-%m-%d").ok().and_then(|d| d.and_hms_opt(0, 0, 0)))
+            .ok_or_else(|| format!("invalid timestamp: '{}'", value))?;
+        let timestamp = match self {
+            Self::Local => Local.from_local_datetime(&naive).earliest().map(|t| t.with_timezone(&Utc)),
+            Self::Fixed(offset) => offset.from_local_datetime(&naive).earliest().map(|t| t.with_timezone(&Utc)),
+        };
+        timestamp.ok_or_else(|| format!("timestamp '{}' does not exist in timezone {}", value, self))
+    }
+}
+
+impl Default for DisplayTimezone {
+    /// UTC
+    fn default() -> Self {
+        Self::Fixed(FixedOffset::east_opt(0).unwrap())
+    }
+}
+
+impl FromStr for DisplayTimezone {
+    type Err = String;
+
+    /// parses either `UTC`, `local` or a fixed offset like `+02:00`
+    fn from_str(timezone: &str) -> Result<Self, Self::Err> {
+        if timezone.eq_ignore_ascii_case("UTC") {
+            Ok(Self::default())
+        } else if timezone.eq_ignore_ascii_case("local") {
+            Ok(Self::Local)
+        } else {
+            timezone.parse()
+                .map(Self::Fixed)
+                .map_err(|_| format!("invalid timezone: '{}'", timezone))
+        }
+    }
+}
+
+impl fmt::Display for DisplayTimezone {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Local => write!(f, "local"),
+            Self::Fixed(offset) if offset.local_minus_utc() == 0 => write!(f, "UTC"),
+            Self::Fixed(offset) => write!(f, "{}", offset),
+        }
+    }
+}
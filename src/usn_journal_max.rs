@@ -0,0 +1,42 @@
+use binread::prelude::*;
+use chrono::{DateTime, Utc};
+use std::fs::File;
+use std::io::{BufReader, Read, Seek};
+use std::path::PathBuf;
+use winstructs::timestamp::WinTimestamp;
+
+use crate::UsnReaderError;
+
+/// the content of `$UsnJrnl:$Max`, which describes the journal in `$UsnJrnl:$J`
+#[derive(BinRead, Clone, Debug, PartialEq, Eq)]
+#[br(little)]
+pub struct UsnJournalMax {
+    /// the size in bytes, up to which the journal grows before old records are discarded
+    pub maximum_size: u64,
+
+    /// the number of bytes, which are discarded or added at once
+    pub allocation_delta: u64,
+
+    /// identifies the journal. This is the time when the journal was created, as FILETIME.
+    pub usn_journal_id: u64,
+
+    /// all records with a lower USN have been discarded
+    pub lowest_valid_usn: i64,
+}
+
+impl UsnJournalMax {
+    pub fn from_reader<R: Read + Seek>(reader: &mut R) -> Result<Self, UsnReaderError> {
+        Ok(reader.read_le()?)
+    }
+
+    pub fn from_path(file_path: &PathBuf) -> Result<Self, UsnReaderError> {
+        Self::from_reader(&mut BufReader::new(File::open(file_path)?))
+    }
+
+    /// returns the time when the journal was created
+    pub fn created(&self) -> Option<DateTime<Utc>> {
+        WinTimestamp::new(&self.usn_journal_id.to_le_bytes())
+            .ok()
+            .map(|t| t.to_datetime())
+    }
+}
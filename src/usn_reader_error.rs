@@ -7,6 +7,7 @@ pub enum UsnReaderError {
     SyntaxError(String),
     FailedToReadWindowsTime([u8;8]),
     NoMoreData,
+    UsnNotFound(i64),
   }
   
   impl From<std::io::Error> for UsnReaderError {
@@ -38,6 +39,9 @@ pub enum UsnReaderError {
         Self::FailedToReadWindowsTime(data) => write!(f, "failed to read windows time: {:?}", data),
         Self::SyntaxError(err) => write!(f, "Syntax Error: {}", err),
         Self::NoMoreData => write!(f, "no more data"),
+        Self::UsnNotFound(usn) => write!(f, "there is no record with USN {}", usn),
       }
     }
-  }
\ No newline at end of file
+  }
+
+  impl std::error::Error for UsnReaderError {}
@@ -7,6 +7,7 @@ use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 use binread::prelude::*;
 
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub struct UsnReason {
   value: u32,
 }
@@ -23,6 +24,28 @@ impl UsnReason {
   pub fn has_flag(&self, flag: UsnReasonValue) -> bool {
     (self.value & flag as u32) != 0
   }
+
+  /// returns all flags which are set, in the order of their declaration
+  pub fn flags(&self) -> impl Iterator<Item=UsnReasonValue> + '_ {
+    UsnReasonValue::iter().filter(move |x| self.has_flag(*x))
+  }
+
+  pub fn value(&self) -> u32 {
+    self.value
+  }
+
+  /// returns the classes of all flags which are set, ordered by significance
+  pub fn classes(&self) -> Vec<UsnReasonClass> {
+    let mut classes: Vec<_> = self.flags().map(|x| x.class()).collect();
+    classes.sort();
+    classes.dedup();
+    classes
+  }
+
+  /// returns the most significant class of all flags which are set
+  pub fn primary_class(&self) -> Option<UsnReasonClass> {
+    self.flags().map(|x| x.class()).min()
+  }
 }
 
 impl fmt::Debug for UsnReason {
@@ -34,16 +57,15 @@ impl fmt::Debug for UsnReason {
 impl fmt::Display for UsnReason {
   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
     let usnjrnl_longflags = f.sign_plus();
-    let flags:Vec<String> = UsnReasonValue::iter()
-        .filter(|x|self.has_flag(*x))
-        .map(|x| if ! usnjrnl_longflags {str::from_utf8(&x.to_string().into_bytes()[11..]).unwrap().to_owned()} else {x.to_string()})
+    let flags:Vec<String> = self.flags()
+        .map(|x| if ! usnjrnl_longflags {x.short_name()} else {x.to_string()})
         .collect();
     write!(f, "{}", flags.join("+"))
   }
 }
 
 #[allow(non_camel_case_types)]
-#[derive(BinRead, EnumIter, strum_macros::Display, Clone, Copy, Debug)]
+#[derive(BinRead, EnumIter, strum_macros::Display, Clone, Copy, Debug, PartialEq, Eq, Hash)]
 #[br(repr=u32, little)]
 #[repr(u32)]
 pub enum UsnReasonValue {
@@ -136,3 +158,74 @@ pub enum UsnReasonValue {
   /// The given stream is modified through a TxF transaction.
   USN_REASON_TRANSACTED_CHANGE = 0x00400000,
 }
+
+impl UsnReasonValue {
+  /// returns the name of the flag without the `USN_REASON_` prefix
+  pub fn short_name(&self) -> String {
+    str::from_utf8(&self.to_string().into_bytes()[11..]).unwrap().to_owned()
+  }
+
+  pub fn class(&self) -> UsnReasonClass {
+    match self {
+      Self::USN_REASON_FILE_DELETE => UsnReasonClass::Delete,
+      Self::USN_REASON_FILE_CREATE => UsnReasonClass::Create,
+      Self::USN_REASON_RENAME_OLD_NAME |
+      Self::USN_REASON_RENAME_NEW_NAME => UsnReasonClass::Rename,
+      Self::USN_REASON_DATA_OVERWRITE |
+      Self::USN_REASON_DATA_EXTEND |
+      Self::USN_REASON_DATA_TRUNCATION |
+      Self::USN_REASON_NAMED_DATA_OVERWRITE |
+      Self::USN_REASON_NAMED_DATA_EXTEND |
+      Self::USN_REASON_NAMED_DATA_TRUNCATION |
+      Self::USN_REASON_TRANSACTED_CHANGE => UsnReasonClass::Data,
+      Self::USN_REASON_CLOSE => UsnReasonClass::Close,
+      _ => UsnReasonClass::Metadata,
+    }
+  }
+}
+
+impl str::FromStr for UsnReasonValue {
+  type Err = String;
+
+  /// parses the name of a flag, with or without the `USN_REASON_` prefix and ignoring case
+  fn from_str(name: &str) -> Result<Self, Self::Err> {
+    Self::iter()
+      .find(|x| x.short_name().eq_ignore_ascii_case(name) || x.to_string().eq_ignore_ascii_case(name))
+      .ok_or_else(|| format!("invalid reason: '{}'", name))
+  }
+}
+
+/// coarse classification of reason flags. The classes are ordered by
+/// significance, starting with the most significant one.
+#[derive(EnumIter, strum_macros::Display, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[strum(serialize_all = "lowercase")]
+pub enum UsnReasonClass {
+  /// the file or directory has been deleted
+  Delete,
+
+  /// the file or directory has been created
+  Create,
+
+  /// the file or directory has been renamed or moved
+  Rename,
+
+  /// the content of the file or one of its streams has been changed
+  Data,
+
+  /// attributes, time stamps, security, links or streams have been changed
+  Metadata,
+
+  /// the file or directory has been closed
+  Close,
+}
+
+impl str::FromStr for UsnReasonClass {
+  type Err = String;
+
+  /// parses the name of a class, ignoring case
+  fn from_str(name: &str) -> Result<Self, Self::Err> {
+    Self::iter()
+      .find(|x| x.to_string().eq_ignore_ascii_case(name))
+      .ok_or_else(|| format!("invalid reason class: '{}'", name))
+  }
+}
@@ -10,6 +10,7 @@ use winstructs::timestamp::WinTimestamp;
 
 use crate::usn_reader_error::*;
 use crate::usn_reason::*;
+use crate::file_attributes::*;
 
 #[derive(Debug)]
 pub struct CommonUsnRecord {
@@ -68,8 +69,8 @@ impl CommonUsnRecord {
     }
 
     fn ignore_bytes<R: Read + Seek>(reader: &mut R, count: usize) -> std::result::Result<(), UsnReaderError> {
-        let mut _buffer = vec![0; count as usize];
-        let bytes = reader.read(&mut _buffer[..])?;
+        /* buffered readers might return less bytes than requested, even if more data are available */
+        let bytes = std::io::copy(&mut reader.by_ref().take(count as u64), &mut std::io::sink())? as usize;
         if bytes != count {
             /* we've read less bytes than expected, why may happen
              * if we reached the end of the file
@@ -90,7 +91,7 @@ impl CommonUsnRecord {
             /* seek back to the original position */
             reader.seek(SeekFrom::Start(current_position))?;
         }
-        assert_eq!(bytes, count as usize);
+        assert_eq!(bytes, count);
         //reader.seek(SeekFrom::Current(count as i64))?;
         Ok(())
     }
@@ -131,6 +132,42 @@ impl UsnRecordData {
         }
     }
 
+    pub fn usn(&self) -> i64 {
+        match self {
+            Self::V2(data) => data.Usn,
+        }
+    }
+
+    pub fn file_reference(&self) -> &MftReference {
+        match self {
+            Self::V2(data) => &data.FileReferenceNumber,
+        }
+    }
+
+    pub fn parent_reference(&self) -> &MftReference {
+        match self {
+            Self::V2(data) => &data.ParentFileReferenceNumber,
+        }
+    }
+
+    pub fn source_info(&self) -> u32 {
+        match self {
+            Self::V2(data) => data.SourceInfo,
+        }
+    }
+
+    pub fn security_id(&self) -> u32 {
+        match self {
+            Self::V2(data) => data.SecurityId,
+        }
+    }
+
+    pub fn file_attributes(&self) -> FileAttributes {
+        match self {
+            Self::V2(data) => FileAttributes::from(data.FileAttributes),
+        }
+    }
+
     pub fn ending_position(&self) -> u64 {
         match self {
             Self::V2(data) => data.EndingPosition,
@@ -153,6 +190,9 @@ impl BinRead for CurPos {
     }
 }
 
+/// size of [`UsnRecordCommonHeader`] in the journal
+const HEADER_SIZE: u64 = 8;
+
 #[derive_binread]
 #[derive(Debug)]
 #[br(little)]
@@ -187,6 +227,13 @@ pub struct UsnRecordCommonHeader {
     pub MinorVersion: u16,
 }
 
+impl UsnRecordCommonHeader {
+    /// returns the offset of the record in the journal
+    pub fn starting_position(&self) -> u64 {
+        self.StartingPosition.0
+    }
+}
+
 /// Contains the information for an update sequence number (USN) common header
 /// which is common through USN_RECORD_V2, USN_RECORD_V3 and USN_RECORD_V4.
 ///
@@ -195,8 +242,8 @@ pub struct UsnRecordCommonHeader {
 #[derive_binread]
 #[br(little)]
 pub struct BinaryUsnRecordV2 {
-    /// the following field is not really part of UsnRecordV2
-    #[br(temp)]
+    /// the following field is not really part of UsnRecordV2. It is the
+    /// position right after the common header, which has already been read
     pub StartingPosition: CurPos,
 
     pub FileReferenceNumber: u64,
@@ -219,27 +266,35 @@ pub struct BinaryUsnRecordV2 {
     /// record, in bytes. The FileName member contains this name. Use this member
     /// to determine file name length, rather than depending on a trailing '\0'
     /// to delimit the file name in FileName.
-    #[br(temp, little)]
     pub FileNameLength: u16,
 
     /// The offset of the FileName member from the beginning of the structure.
     pub FileNameOffset: u16,
 
-    /// The name of the file or directory associated with this record in Unicode
-    /// format. This file or directory name is of variable length.
-    ///
-    /// When working with FileName, do not count on the file name that contains
-    /// a trailing '\0' delimiter, but instead determine the length of the file
-    /// name by using FileNameLength.
-    ///
-    /// Do not perform any compile-time pointer arithmetic using FileName.
-    /// Instead, make necessary calculations at run time by using the value of
-    /// the FileNameOffset member. Doing so helps make your code compatible with
-    /// any future versions of USN_RECORD_V2.
-    #[br(offset=StartingPosition.0 + (FileNameOffset as u64), count=FileNameLength/2)]
-    pub FileName: Vec<u16>,
-
-    EndingPosition: CurPos,
+    /// the following field is not really part of UsnRecordV2. It is the
+    /// position right after the fixed size fields
+    pub EndingPosition: CurPos,
+}
+
+impl BinaryUsnRecordV2 {
+    /// returns the position of FileName in the journal
+    fn file_name_position(&self) -> u64 {
+        self.StartingPosition.0 - HEADER_SIZE + self.FileNameOffset as u64
+    }
+
+    /// reads the name of the file, which follows the fixed size fields of
+    /// the record. Padding before the name is read, not seeked over, like
+    /// the padding after a record.
+    fn read_file_name<R: Read + Seek>(&self, data: &mut R) -> std::result::Result<Vec<u16>, UsnReaderError> {
+        let padding = self.file_name_position().checked_sub(self.EndingPosition.0).ok_or_else(|| {
+            UsnReaderError::SyntaxError(format!("invalid value for FileNameOffset: {}", self.FileNameOffset))
+        })?;
+        CommonUsnRecord::ignore_bytes(data, padding as usize)?;
+
+        let mut name = vec![0; self.FileNameLength as usize];
+        data.read_exact(&mut name)?;
+        Ok(name.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect())
+    }
 }
 
 #[derive(Debug)]
@@ -263,7 +318,8 @@ impl UsnRecordV2 {
     {
         let record: BinaryUsnRecordV2 = data.read_le()?;
 
-        let filename = String::from_utf16_lossy(&record.FileName);
+        /* do not count on a trailing '\0', but use FileNameLength */
+        let filename = String::from_utf16_lossy(&record.read_file_name(data)?);
 
         let file_reference = MftReference::from(record.FileReferenceNumber);
         let parent_reference = MftReference::from(record.ParentFileReferenceNumber);
@@ -280,7 +336,7 @@ impl UsnRecordV2 {
             SecurityId: record.SecurityId,
             FileAttributes: record.FileAttributes,
             FileName: filename,
-            EndingPosition: record.EndingPosition.0,
+            EndingPosition: record.file_name_position() + record.FileNameLength as u64,
         })
     }
 }
@@ -1,31 +1,42 @@
 use crate::{CommonUsnRecord, UsnReaderError};
-use std::io::{Result, Read, Seek, ErrorKind, Error};
+use std::io::{Result, Read, Seek, SeekFrom, BufReader};
+use std::convert::TryFrom;
 use std::fs::File;
 use std::path::PathBuf;
-use buf_stream_reader::BufStreamReader;
+use chrono::{DateTime, Utc};
 
+#[cfg(feature = "gzip")]
+use std::io::{Error, ErrorKind};
+#[cfg(feature = "gzip")]
+use buf_stream_reader::BufStreamReader;
 #[cfg(feature = "gzip")]
 use flate2::read::GzDecoder;
 
+/// size of a page in `$UsnJrnl:$J`. Records never cross a page boundary.
+pub const PAGE_SIZE: u64 = 0x1000;
+
+/// a stream which can be read and (at least forward) seeked
+pub trait ReadSeek: Read + Seek {}
+impl<T> ReadSeek for T where T: Read + Seek {}
+
 pub struct UsnJrnlReader {
-    reader: Box<dyn Read>,
+    reader: Box<dyn ReadSeek>,
+    seekable: bool,
 }
 
 impl UsnJrnlReader  {
     pub fn from(file_path: &PathBuf) -> Result<Self> {
-        Ok(Self {
-            reader: Self::open_file(file_path)?
-        })
-    }
-
-    fn open_file(file_path: &PathBuf) -> Result<Box<dyn Read>> {
         let file = File::open(file_path)?;
 
         #[cfg(feature = "gzip")]
         match file_path.file_name() {
             Some(filename) => {
                 if filename.to_string_lossy().ends_with(".gz") {
-                    return Ok(Box::new(GzDecoder::new(file)));
+                    let stream: Box<dyn Read> = Box::new(GzDecoder::new(file));
+                    return Ok(Self {
+                        reader: Box::new(BufStreamReader::new(stream, PAGE_SIZE as usize)),
+                        seekable: false,
+                    });
                 }
             }
             None => {
@@ -33,16 +44,26 @@ impl UsnJrnlReader  {
             }
         }
 
-        Ok(Box::new(file))
+        Ok(Self {
+            reader: Box::new(BufReader::new(file)),
+            seekable: true,
+        })
+    }
+
+    /// returns `false` if the journal can only be read sequentially, which
+    /// is the case for compressed files. Such journals cannot be used with
+    /// [`UsrJrnlIterator::seek_to_time`].
+    pub fn is_seekable(&self) -> bool {
+        self.seekable
     }
 }
 
 
 impl IntoIterator for UsnJrnlReader {
     type Item = std::result::Result<CommonUsnRecord, UsnReaderError>;
-    type IntoIter = UsrJrnlIterator<BufStreamReader<Box<dyn Read>>>;
+    type IntoIter = UsrJrnlIterator<Box<dyn ReadSeek>>;
     fn into_iter(self) -> Self::IntoIter {
-        Self::IntoIter::from(BufStreamReader::new(self.reader, 4096))
+        Self::IntoIter::from(self.reader)
     }
 }
 
@@ -56,6 +77,98 @@ impl<RS> UsrJrnlIterator<RS> where RS: Read + Seek {
             data
         }
     }
+
+    /// moves the iterator to the first record whose timestamp is at or after
+    /// `timestamp`, and returns the offset of this record.
+    ///
+    /// Records are expected to be in chronological order, so that the page
+    /// which contains this record can be found by bisecting over the page
+    /// boundaries. Only the first record of a few pages needs to be read.
+    /// Pages without records are expected to belong to the sparse beginning
+    /// of the journal, so they count as being before `timestamp`.
+    pub fn seek_to_time(&mut self, timestamp: &DateTime<Utc>) -> std::result::Result<u64, UsnReaderError> {
+        let end = self.data.seek(SeekFrom::End(0))?;
+
+        /* find the first page which starts with a record at or after timestamp */
+        let mut lower = 0;
+        let mut upper = end.div_ceil(PAGE_SIZE);
+        while lower < upper {
+            let page = lower + (upper - lower) / 2;
+            match self.record_at_page_start(page * PAGE_SIZE)? {
+                None => lower = page + 1,
+                Some(record) if record.data.timestamp() < timestamp => lower = page + 1,
+                Some(_) => upper = page,
+            }
+        }
+
+        /* the searched record might be located in the preceding page */
+        let mut position = lower.saturating_sub(1) * PAGE_SIZE;
+        self.data.seek(SeekFrom::Start(position))?;
+        loop {
+            match CommonUsnRecord::from(&mut self.data) {
+                Ok(record) if record.data.timestamp() < timestamp => (),
+                Ok(record) => {
+                    position = record.header.starting_position();
+                    break;
+                }
+                Err(UsnReaderError::NoMoreData) => {
+                    position = end;
+                    break;
+                }
+                Err(why) => return Err(why),
+            }
+        }
+        self.data.seek(SeekFrom::Start(position))?;
+        Ok(position)
+    }
+
+    /// moves the iterator to the record with the given `usn`.
+    ///
+    /// The USN of a record is its offset in `$UsnJrnl:$J`. If the journal is
+    /// only an excerpt, the offset of the excerpt is calculated from the
+    /// first record. Fails with [`UsnReaderError::UsnNotFound`] if there is
+    /// no record which starts at this offset and carries this USN.
+    pub fn seek_to_usn(&mut self, usn: i64) -> std::result::Result<(), UsnReaderError> {
+        let first_record = self.first_record_at(0)?.ok_or(UsnReaderError::UsnNotFound(usn))?;
+        let base = first_record.data.usn() - first_record.header.starting_position() as i64;
+        let offset = u64::try_from(usn - base).map_err(|_| UsnReaderError::UsnNotFound(usn))?;
+
+        match self.first_record_at(offset) {
+            Ok(Some(record)) if record.header.starting_position() == offset && record.data.usn() == usn => {
+                self.data.seek(SeekFrom::Start(offset))?;
+                Ok(())
+            }
+            Err(UsnReaderError::IO(why)) => Err(UsnReaderError::IO(why)),
+
+            /* we are not at the beginning of a record */
+            _ => Err(UsnReaderError::UsnNotFound(usn)),
+        }
+    }
+
+    /// reads the record at the beginning of the page at `offset`. Unlike
+    /// [`Self::first_record_at`], empty pages are not skipped, so that the
+    /// sparse beginning of a journal is not read page by page.
+    fn record_at_page_start(&mut self, offset: u64) -> std::result::Result<Option<CommonUsnRecord>, UsnReaderError> {
+        self.data.seek(SeekFrom::Start(offset))?;
+        let mut record_length = [0; 4];
+        match self.data.read_exact(&mut record_length) {
+            Ok(()) if u32::from_le_bytes(record_length) == 0 => return Ok(None),
+            Ok(()) => (),
+            Err(why) if why.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(why) => return Err(why.into()),
+        }
+        self.first_record_at(offset)
+    }
+
+    /// reads the first record at or after `offset`, skipping empty pages
+    fn first_record_at(&mut self, offset: u64) -> std::result::Result<Option<CommonUsnRecord>, UsnReaderError> {
+        self.data.seek(SeekFrom::Start(offset))?;
+        match CommonUsnRecord::from(&mut self.data) {
+            Ok(record) => Ok(Some(record)),
+            Err(UsnReaderError::NoMoreData) => Ok(None),
+            Err(why) => Err(why),
+        }
+    }
 }
 
 impl<RS> Iterator for UsrJrnlIterator<RS> where RS: Read + Seek {
@@ -68,4 +181,4 @@ impl<RS> Iterator for UsrJrnlIterator<RS> where RS: Read + Seek {
             Some(next_record)
         }
     }
-}
\ No newline at end of file
+}
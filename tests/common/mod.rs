@@ -0,0 +1,170 @@
+#![allow(dead_code)]
+use chrono::{DateTime, TimeZone, Utc};
+use std::io::Cursor;
+use std::path::PathBuf;
+use usnjrnl::{UsnJournalMax, UsnReasonValue};
+
+pub fn get_sample_file(filename: &str) -> PathBuf {
+    let prj_root = env!("CARGO_MANIFEST_DIR");
+    let mut sample_file = PathBuf::from(prj_root);
+    sample_file.push("tests");
+    sample_file.push("data");
+    sample_file.push(filename);
+    sample_file
+}
+
+/// creates the `$UsnJrnl:$Max` of a journal, which has been created at `created`.
+/// The creation time is also the id of the journal.
+pub fn journal_max(created: DateTime<Utc>, lowest_valid_usn: i64) -> UsnJournalMax {
+    let filetime = (created.timestamp_nanos_opt().unwrap() / 100) as u64 + 116_444_736_000_000_000;
+    let mut data = Vec::new();
+    data.extend_from_slice(&0x2000000u64.to_le_bytes());
+    data.extend_from_slice(&0x800000u64.to_le_bytes());
+    data.extend_from_slice(&filetime.to_le_bytes());
+    data.extend_from_slice(&lowest_valid_usn.to_le_bytes());
+    UsnJournalMax::from_reader(&mut Cursor::new(data)).unwrap()
+}
+
+/// creates a file reference from an entry number and a sequence number
+pub fn file_reference(entry: u64, sequence: u16) -> u64 {
+    entry | ((sequence as u64) << 48)
+}
+
+/// describes a single USN_RECORD_V2, which can be serialized by [`Journal`]
+pub struct TestRecord {
+    pub file_reference: u64,
+    pub parent_reference: u64,
+    pub timestamp: DateTime<Utc>,
+    pub reasons: Vec<UsnReasonValue>,
+    pub source_info: u32,
+    pub file_attributes: u32,
+    pub filename: String,
+
+    /// the USN of the record, if it should not be its offset in the journal
+    pub usn: Option<i64>,
+}
+
+impl TestRecord {
+    pub fn new(entry: u64, parent: u64, filename: &str, reasons: &[UsnReasonValue]) -> Self {
+        Self {
+            file_reference: file_reference(entry, 1),
+            parent_reference: file_reference(parent, 1),
+            timestamp: Utc.with_ymd_and_hms(2022, 11, 18, 12, 0, 0).unwrap(),
+            reasons: reasons.to_vec(),
+            source_info: 0,
+            file_attributes: 0x20,
+            filename: filename.to_owned(),
+            usn: None,
+        }
+    }
+
+    pub fn at(mut self, timestamp: DateTime<Utc>) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    pub fn with_attributes(mut self, file_attributes: u32) -> Self {
+        self.file_attributes = file_attributes;
+        self
+    }
+
+    pub fn with_usn(mut self, usn: i64) -> Self {
+        self.usn = Some(usn);
+        self
+    }
+
+    pub fn with_sequence(mut self, sequence: u16) -> Self {
+        self.file_reference = file_reference(self.file_reference & 0xffff_ffff_ffff, sequence);
+        self
+    }
+}
+
+/// builds the binary content of a `$UsnJrnl:$J` stream. The USN of every
+/// record is its offset in the stream, as it would be in a real journal.
+#[derive(Clone, Default)]
+pub struct Journal {
+    data: Vec<u8>,
+}
+
+impl Journal {
+    pub fn with_records(records: Vec<TestRecord>) -> Self {
+        let mut journal = Self::default();
+        for record in records {
+            journal.push(record);
+        }
+        journal
+    }
+
+    /// creates a journal which starts with `count` empty pages, like the
+    /// sparse beginning of a real `$UsnJrnl:$J`
+    pub fn with_empty_pages(count: usize) -> Self {
+        Self {
+            data: vec![0; count * 0x1000],
+        }
+    }
+
+    pub fn push(&mut self, record: TestRecord) -> i64 {
+        let name: Vec<u8> = record.filename.encode_utf16().flat_map(|c| c.to_le_bytes()).collect();
+        let length = (60 + name.len() + 7) & !7;
+
+        /* records never cross a page boundary */
+        if (self.data.len() & 0xfff) + length > 0x1000 {
+            self.pad_to_page();
+        }
+
+        let offset = self.data.len();
+        let usn = record.usn.unwrap_or(offset as i64);
+        let filetime = (record.timestamp.timestamp_nanos_opt().unwrap() / 100) + 116_444_736_000_000_000;
+        let reason = record.reasons.iter().fold(0u32, |r, v| r | *v as u32);
+
+        self.data.extend_from_slice(&(length as u32).to_le_bytes());
+        self.data.extend_from_slice(&2u16.to_le_bytes());
+        self.data.extend_from_slice(&0u16.to_le_bytes());
+        self.data.extend_from_slice(&record.file_reference.to_le_bytes());
+        self.data.extend_from_slice(&record.parent_reference.to_le_bytes());
+        self.data.extend_from_slice(&usn.to_le_bytes());
+        self.data.extend_from_slice(&filetime.to_le_bytes());
+        self.data.extend_from_slice(&reason.to_le_bytes());
+        self.data.extend_from_slice(&record.source_info.to_le_bytes());
+        self.data.extend_from_slice(&0u32.to_le_bytes());
+        self.data.extend_from_slice(&record.file_attributes.to_le_bytes());
+        self.data.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        self.data.extend_from_slice(&60u16.to_le_bytes());
+        self.data.extend_from_slice(&name);
+        self.data.resize(offset + length, 0);
+        usn
+    }
+
+    /// appends `count` empty pages, after filling the current page with zeroes
+    pub fn append_empty_pages(&mut self, count: usize) {
+        self.pad_to_page();
+        self.data.resize(self.data.len() + count * 0x1000, 0);
+    }
+
+    /// fills the rest of the current page with zeroes
+    pub fn pad_to_page(&mut self) {
+        let padded = (self.data.len() + 0xfff) & !0xfff;
+        self.data.resize(padded, 0);
+    }
+
+    pub fn cursor(&self) -> Cursor<Vec<u8>> {
+        Cursor::new(self.data.clone())
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// writes the journal into a file in the temporary directory, whose name
+    /// starts with `name`. The file is padded to a full page, like the
+    /// sample files.
+    pub fn write_to_temp_file(&self, name: &str) -> PathBuf {
+        let mut data = self.data.clone();
+        data.resize((data.len() + 0xfff) & !0xfff, 0);
+
+        let mut path = std::env::temp_dir();
+        path.push(format!("usnjrnl_{}_{}.bin", name, std::process::id()));
+        std::fs::write(&path, &data).unwrap();
+        path
+    }
+}
@@ -0,0 +1,96 @@
+use chrono::{Duration, TimeZone, Utc};
+use usnjrnl::{UsrJrnlIterator, UsnReasonValue::*};
+use usnjrnl::analysis::{AdsAnalyzer, AdsReport, AdsSettings, Analyzer, StreamEventKind::*};
+
+mod common;
+use common::*;
+
+fn analyze(journal: &Journal, settings: AdsSettings) -> AdsReport {
+    let mut analyzer = AdsAnalyzer::new(settings);
+    for record in UsrJrnlIterator::from(journal.cursor()) {
+        analyzer.add(&record.unwrap());
+    }
+    analyzer.finish()
+}
+
+#[test]
+fn test_stream_activity() {
+    let at = |seconds| Utc.with_ymd_and_hms(2022, 11, 18, 12, 0, 0).unwrap() + Duration::seconds(seconds);
+    let mut journal = Journal::with_records(vec![
+        TestRecord::new(100, 5, "Downloads", &[USN_REASON_CLOSE]).with_attributes(0x10).at(at(0)),
+    ]);
+
+    /* downloaded, and unblocked an hour later */
+    journal.push(TestRecord::new(300, 100, "setup.exe", &[USN_REASON_FILE_CREATE]).at(at(0)));
+    journal.push(TestRecord::new(300, 100, "setup.exe", &[USN_REASON_FILE_CREATE, USN_REASON_DATA_EXTEND, USN_REASON_CLOSE]).at(at(5)));
+    let marked = journal.push(TestRecord::new(300, 100, "setup.exe", &[USN_REASON_STREAM_CHANGE]).at(at(6)));
+    journal.push(TestRecord::new(300, 100, "setup.exe", &[USN_REASON_STREAM_CHANGE, USN_REASON_NAMED_DATA_EXTEND, USN_REASON_CLOSE]).at(at(6)));
+    let stripped = journal.push(TestRecord::new(300, 100, "setup.exe", &[USN_REASON_STREAM_CHANGE, USN_REASON_CLOSE]).at(at(3600)));
+
+    /* a stream which is added long after the file has been written */
+    journal.push(TestRecord::new(301, 100, "notes.txt", &[USN_REASON_FILE_CREATE, USN_REASON_DATA_EXTEND, USN_REASON_CLOSE]).at(at(0)));
+    journal.push(TestRecord::new(301, 100, "notes.txt", &[USN_REASON_STREAM_CHANGE, USN_REASON_NAMED_DATA_EXTEND, USN_REASON_CLOSE]).at(at(7200)));
+
+    /* an existing stream which is written again */
+    journal.push(TestRecord::new(302, 100, "report.pdf", &[USN_REASON_FILE_CREATE, USN_REASON_DATA_EXTEND, USN_REASON_CLOSE]).at(at(0)));
+    journal.push(TestRecord::new(302, 100, "report.pdf", &[USN_REASON_STREAM_CHANGE, USN_REASON_NAMED_DATA_EXTEND, USN_REASON_CLOSE]).at(at(1)));
+    journal.push(TestRecord::new(302, 100, "report.pdf", &[USN_REASON_NAMED_DATA_OVERWRITE, USN_REASON_CLOSE]).at(at(60)));
+
+    /* a stream on a directory */
+    journal.push(TestRecord::new(303, 100, "Hidden", &[USN_REASON_FILE_CREATE, USN_REASON_STREAM_CHANGE, USN_REASON_NAMED_DATA_EXTEND, USN_REASON_CLOSE])
+        .with_attributes(0x10).at(at(0)));
+
+    /* no executable, so losing its mark of the web is not remarkable */
+    journal.push(TestRecord::new(304, 100, "letter.docx", &[USN_REASON_FILE_CREATE, USN_REASON_DATA_EXTEND, USN_REASON_CLOSE]).at(at(0)));
+    journal.push(TestRecord::new(304, 100, "letter.docx", &[USN_REASON_STREAM_CHANGE, USN_REASON_NAMED_DATA_EXTEND, USN_REASON_CLOSE]).at(at(1)));
+    journal.push(TestRecord::new(304, 100, "letter.docx", &[USN_REASON_STREAM_CHANGE, USN_REASON_CLOSE]).at(at(60)));
+
+    journal.push(TestRecord::new(305, 100, "plain.txt", &[USN_REASON_FILE_CREATE, USN_REASON_DATA_EXTEND, USN_REASON_CLOSE]).at(at(0)));
+
+    /* still open at the end of the journal */
+    journal.push(TestRecord::new(306, 100, "open.exe", &[USN_REASON_FILE_CREATE, USN_REASON_DATA_EXTEND]).at(at(0)));
+    journal.push(TestRecord::new(306, 100, "open.exe", &[USN_REASON_FILE_CREATE, USN_REASON_DATA_EXTEND, USN_REASON_STREAM_CHANGE, USN_REASON_NAMED_DATA_EXTEND]).at(at(0)));
+
+    let report = analyze(&journal, AdsSettings::default());
+    let summary: Vec<_> = report.files.iter()
+        .map(|f| (f.name.as_str(), f.events.iter().map(|e| e.kind).collect::<Vec<_>>()))
+        .collect();
+    assert_eq!(summary, vec![
+        ("setup.exe", vec![MarkOfTheWeb, MarkOfTheWebRemoved]),
+        ("notes.txt", vec![Added]),
+        ("report.pdf", vec![MarkOfTheWeb, Written]),
+        ("Hidden", vec![Added]),
+        ("letter.docx", vec![MarkOfTheWeb, MarkOfTheWebRemoved]),
+        ("open.exe", vec![MarkOfTheWeb]),
+    ]);
+
+    let setup = &report.files[0];
+    assert_eq!(setup.path.as_deref(), Some("/Downloads/setup.exe"));
+    assert!(setup.is_executable);
+    assert_eq!(setup.events[0].record.usn, marked);
+    assert_eq!(setup.events[1].record.usn, stripped);
+
+    let stripped: Vec<_> = report.stripped_executables().map(|f| f.name.as_str()).collect();
+    assert_eq!(stripped, vec!["setup.exe"]);
+    let unusual: Vec<_> = report.unusual().map(|f| f.name.as_str()).collect();
+    assert_eq!(unusual, vec!["setup.exe", "notes.txt", "report.pdf", "Hidden", "letter.docx"]);
+}
+
+#[test]
+fn test_mark_of_the_web_delay() {
+    let at = |seconds| Utc.with_ymd_and_hms(2022, 11, 18, 12, 0, 0).unwrap() + Duration::seconds(seconds);
+    let journal = Journal::with_records(vec![
+        TestRecord::new(300, 5, "setup.exe", &[USN_REASON_FILE_CREATE, USN_REASON_DATA_EXTEND, USN_REASON_CLOSE]).at(at(0)),
+        TestRecord::new(300, 5, "setup.exe", &[USN_REASON_STREAM_CHANGE, USN_REASON_NAMED_DATA_EXTEND, USN_REASON_CLOSE]).at(at(30)),
+        TestRecord::new(300, 5, "setup.exe", &[USN_REASON_STREAM_CHANGE, USN_REASON_CLOSE]).at(at(60)),
+    ]);
+
+    let report = analyze(&journal, AdsSettings::default());
+    assert_eq!(report.stripped_executables().count(), 1);
+
+    let settings = AdsSettings { mark_of_the_web_delay: Duration::seconds(10), ..Default::default() };
+    let report = analyze(&journal, settings);
+    let kinds: Vec<_> = report.files[0].events.iter().map(|e| e.kind).collect();
+    assert_eq!(kinds, vec![Added, Removed]);
+    assert_eq!(report.stripped_executables().count(), 0);
+}
@@ -0,0 +1,118 @@
+use chrono::{Duration, TimeZone, Utc};
+use usnjrnl::{UsrJrnlIterator, UsnReasonValue::*};
+use usnjrnl::analysis::{AntiForensicsAnalyzer, AntiForensicsFinding, AntiForensicsKind, AntiForensicsSettings, Analyzer};
+
+mod common;
+use common::*;
+
+/// number of empty pages before the first record, so that the journal is not considered to be cleared
+const HISTORY: usize = 0x100;
+
+fn analyze(journal: &Journal, analyzer: AntiForensicsAnalyzer) -> Vec<AntiForensicsFinding> {
+    let mut analyzer = analyzer;
+    for record in UsrJrnlIterator::from(journal.cursor()) {
+        analyzer.add(&record.unwrap());
+    }
+    analyzer.finish()
+}
+
+#[test]
+fn test_timestomping() {
+    let at = |seconds| Utc.with_ymd_and_hms(2022, 11, 18, 12, 0, 0).unwrap() + Duration::seconds(seconds);
+    let mut journal = Journal::with_empty_pages(HISTORY);
+    journal.push(TestRecord::new(300, 5, "payload.exe", &[USN_REASON_FILE_CREATE]).at(at(0)));
+    let written = journal.push(TestRecord::new(300, 5, "payload.exe", &[USN_REASON_FILE_CREATE, USN_REASON_DATA_EXTEND, USN_REASON_CLOSE]).at(at(1)));
+    let stomped = journal.push(TestRecord::new(300, 5, "payload.exe", &[USN_REASON_BASIC_INFO_CHANGE]).at(at(3)));
+    journal.push(TestRecord::new(300, 5, "payload.exe", &[USN_REASON_BASIC_INFO_CHANGE, USN_REASON_CLOSE]).at(at(3)));
+
+    /* not an executable, or changed much later */
+    journal.push(TestRecord::new(301, 5, "notes.txt", &[USN_REASON_DATA_EXTEND, USN_REASON_CLOSE]).at(at(0)));
+    journal.push(TestRecord::new(301, 5, "notes.txt", &[USN_REASON_BASIC_INFO_CHANGE, USN_REASON_CLOSE]).at(at(1)));
+    journal.push(TestRecord::new(302, 5, "tool.dll", &[USN_REASON_DATA_EXTEND, USN_REASON_CLOSE]).at(at(0)));
+    journal.push(TestRecord::new(302, 5, "tool.dll", &[USN_REASON_BASIC_INFO_CHANGE, USN_REASON_CLOSE]).at(at(3600)));
+
+    let findings = analyze(&journal, AntiForensicsAnalyzer::new(AntiForensicsSettings::default()));
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].kind, AntiForensicsKind::Timestomping);
+    assert_eq!(findings[0].timestamp, at(3));
+    assert_eq!(findings[0].description, "the timestamps of 'payload.exe' have been changed 2 seconds after it had been written");
+    let offsets: Vec<_> = findings[0].records.iter().map(|r| r.usn).collect();
+    assert_eq!(offsets, vec![written, stomped]);
+}
+
+#[test]
+fn test_secure_deletion() {
+    let mut journal = Journal::with_empty_pages(HISTORY);
+    let overwritten = journal.push(TestRecord::new(300, 5, "secret.docx", &[USN_REASON_DATA_OVERWRITE]));
+    journal.push(TestRecord::new(300, 5, "secret.docx", &[USN_REASON_DATA_OVERWRITE, USN_REASON_CLOSE]));
+    let mut old_name = "secret.docx".to_owned();
+    for c in ['A', 'B', 'C'] {
+        let new_name = format!("{}.{}", c.to_string().repeat(6), c.to_string().repeat(4));
+        journal.push(TestRecord::new(300, 5, &old_name, &[USN_REASON_RENAME_OLD_NAME]));
+        journal.push(TestRecord::new(300, 5, &new_name, &[USN_REASON_RENAME_NEW_NAME, USN_REASON_CLOSE]));
+        old_name = new_name;
+    }
+    let deleted = journal.push(TestRecord::new(300, 5, &old_name, &[USN_REASON_FILE_DELETE, USN_REASON_CLOSE]));
+
+    let findings = analyze(&journal, AntiForensicsAnalyzer::new(AntiForensicsSettings::default()));
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].kind, AntiForensicsKind::SecureDeletion);
+    assert_eq!(findings[0].description, "'secret.docx' has been renamed 3 times to names like 'AAAAAA.AAAA' before it was deleted");
+    assert_eq!(findings[0].records.len(), 5);
+    assert_eq!(findings[0].records.first().unwrap().usn, overwritten);
+    assert_eq!(findings[0].records.last().unwrap().usn, deleted);
+}
+
+#[test]
+fn test_mass_wipe() {
+    let start = Utc.with_ymd_and_hms(2022, 11, 18, 12, 0, 0).unwrap();
+    let mut journal = Journal::with_empty_pages(HISTORY);
+    for file in 0..12 {
+        let name = format!("file{}.pdf", file);
+        journal.push(TestRecord::new(300 + file, 5, &name, &[USN_REASON_DATA_OVERWRITE, USN_REASON_CLOSE]).at(start));
+        journal.push(TestRecord::new(300 + file, 5, &name, &[USN_REASON_FILE_DELETE, USN_REASON_CLOSE]).at(start + Duration::seconds(1)));
+    }
+    /* deleted without being overwritten */
+    journal.push(TestRecord::new(400, 5, "temp.tmp", &[USN_REASON_DATA_EXTEND, USN_REASON_CLOSE]).at(start));
+    journal.push(TestRecord::new(400, 5, "temp.tmp", &[USN_REASON_FILE_DELETE, USN_REASON_CLOSE]).at(start));
+
+    let findings = analyze(&journal, AntiForensicsAnalyzer::new(AntiForensicsSettings::default()));
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].kind, AntiForensicsKind::MassWipe);
+    assert_eq!(findings[0].description, "12 files have been overwritten and deleted within 60 seconds");
+    assert_eq!(findings[0].records.len(), 24);
+    assert!(findings[0].records.windows(2).all(|w| w[0].usn < w[1].usn));
+
+    let settings = AntiForensicsSettings { min_wiped_files: 13, ..Default::default() };
+    assert!(analyze(&journal, AntiForensicsAnalyzer::new(settings)).is_empty());
+}
+
+#[test]
+fn test_journal_cleared() {
+    let last = Utc.with_ymd_and_hms(2022, 11, 18, 12, 0, 0).unwrap();
+    let journal = Journal::with_records(vec![
+        TestRecord::new(300, 5, "notes.txt", &[USN_REASON_FILE_CREATE, USN_REASON_CLOSE]).at(last),
+    ]);
+
+    let findings = analyze(&journal, AntiForensicsAnalyzer::new(AntiForensicsSettings::default()));
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].kind, AntiForensicsKind::JournalCleared);
+    assert_eq!(findings[0].records[0].usn, 0);
+
+    let created = last - Duration::hours(2);
+    let max = journal_max(created, 0);
+    assert_eq!(max.created(), Some(created));
+    let analyzer = AntiForensicsAnalyzer::new(AntiForensicsSettings::default()).with_journal_max(max);
+    let findings = analyze(&journal, analyzer);
+    assert_eq!(findings[0].timestamp, created);
+    assert_eq!(findings[0].description, "the journal has been created at 2022-11-18 10:00:00 UTC and contains only 1 KiB of records");
+
+    /* a small journal, which has been created long ago */
+    let analyzer = AntiForensicsAnalyzer::new(AntiForensicsSettings::default())
+        .with_journal_max(journal_max(last - Duration::days(30), 0));
+    assert!(analyze(&journal, analyzer).is_empty());
+
+    let mut journal = Journal::with_empty_pages(HISTORY);
+    journal.push(TestRecord::new(300, 5, "notes.txt", &[USN_REASON_FILE_CREATE, USN_REASON_CLOSE]));
+    assert!(analyze(&journal, AntiForensicsAnalyzer::new(AntiForensicsSettings::default())).is_empty());
+}
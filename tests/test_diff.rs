@@ -0,0 +1,101 @@
+use chrono::{Duration, TimeZone, Utc};
+use usnjrnl::{JournalDiff, UsnJournalMax, UsrJrnlIterator, UsnReasonValue::*};
+
+mod common;
+use common::*;
+
+fn diff(a: &Journal, max_a: Option<&UsnJournalMax>, b: &Journal, max_b: Option<&UsnJournalMax>) -> JournalDiff {
+    JournalDiff::compare_journals(
+        UsrJrnlIterator::from(a.cursor()).map(|r| r.unwrap()), max_a,
+        UsrJrnlIterator::from(b.cursor()).map(|r| r.unwrap()), max_b)
+}
+
+/// records of a journal, which are written at the same USN in every snapshot
+fn history() -> Vec<TestRecord> {
+    vec![
+        TestRecord::new(100, 5, "Users", &[USN_REASON_CLOSE]).with_attributes(0x10).with_usn(0x1000),
+        TestRecord::new(300, 100, "old.txt", &[USN_REASON_FILE_CREATE, USN_REASON_CLOSE]).with_usn(0x1100),
+        TestRecord::new(301, 100, "report.docx", &[USN_REASON_FILE_CREATE]).with_usn(0x1200),
+        TestRecord::new(301, 100, "report.docx", &[USN_REASON_FILE_CREATE, USN_REASON_CLOSE]).with_usn(0x1300),
+    ]
+}
+
+#[test]
+fn test_new_records() {
+    let a = Journal::with_records(history());
+    let mut b = Journal::with_records(history().split_off(2));
+    b.push(TestRecord::new(302, 100, "setup.exe", &[USN_REASON_FILE_CREATE]).with_usn(0x1400));
+    b.push(TestRecord::new(302, 100, "setup.exe", &[USN_REASON_FILE_CREATE, USN_REASON_CLOSE]).with_usn(0x1500));
+    b.push(TestRecord::new(301, 100, "report.docx", &[USN_REASON_RENAME_OLD_NAME]).with_usn(0x1600));
+    b.push(TestRecord::new(301, 100, "report.pdf", &[USN_REASON_RENAME_NEW_NAME]).with_usn(0x1700));
+    b.push(TestRecord::new(300, 100, "old.txt", &[USN_REASON_FILE_DELETE, USN_REASON_CLOSE]).with_usn(0x1800));
+
+    let diff = diff(&a, None, &b, None);
+    assert!(!diff.journal_recreated());
+    assert!(!diff.has_gap());
+    assert!(diff.is_consistent());
+    assert_eq!((diff.a.records, diff.b.records), (4, 7));
+    assert_eq!(diff.overwritten.records, 2);
+    assert_eq!(diff.overwritten.last.as_ref().unwrap().usn, 0x1100);
+    assert_eq!(diff.common_records, 2);
+    assert_eq!(diff.new_records.records, 5);
+    assert_eq!(diff.new_records.first.as_ref().unwrap().usn, 0x1400);
+
+    assert_eq!(diff.created.iter().map(|r| r.name.as_str()).collect::<Vec<_>>(), vec!["setup.exe"]);
+    assert_eq!(diff.deleted.iter().map(|r| r.name.as_str()).collect::<Vec<_>>(), vec!["old.txt"]);
+    assert_eq!(diff.renamed.len(), 1);
+    assert_eq!(diff.renamed[0].old_name.as_deref(), Some("report.docx"));
+    assert_eq!(diff.renamed[0].new_name, "report.pdf");
+}
+
+#[test]
+fn test_inconsistent_snapshots() {
+    let a = Journal::with_records(history());
+    let mut records = history();
+    records.remove(2);
+    records[1] = TestRecord::new(300, 100, "renamed.txt", &[USN_REASON_FILE_CREATE, USN_REASON_CLOSE]).with_usn(0x1100);
+    let b = Journal::with_records(records);
+
+    let diff = diff(&a, None, &b, None);
+    assert!(!diff.is_consistent());
+    assert_eq!(diff.common_records, 3);
+    assert_eq!(diff.differing_records.len(), 1);
+    assert_eq!(diff.differing_records[0].0.name, "old.txt");
+    assert_eq!(diff.differing_records[0].1.name, "renamed.txt");
+    assert_eq!(diff.missing_in_b.iter().map(|r| r.usn).collect::<Vec<_>>(), vec![0x1200]);
+    assert!(diff.new_records.is_empty());
+
+    /* nothing in common, as B starts after the end of A */
+    let older = Journal::with_records(history().into_iter().take(2).collect());
+    let newer = Journal::with_records(history().split_off(3));
+    let diff = self::diff(&older, None, &newer, None);
+    assert!(diff.has_gap());
+    assert!(diff.is_consistent());
+
+    /* comparing the snapshots in the wrong order */
+    let diff = self::diff(&newer, None, &older, None);
+    assert!(!diff.has_gap());
+    assert!(!diff.is_consistent());
+    assert_eq!(diff.newer_in_a.records, 1);
+}
+
+#[test]
+fn test_recreated_journal() {
+    let a = Journal::with_records(history());
+    let b = Journal::with_records(vec![
+        TestRecord::new(302, 100, "setup.exe", &[USN_REASON_FILE_CREATE, USN_REASON_CLOSE]).with_usn(0x1100),
+    ]);
+
+    let created = Utc.with_ymd_and_hms(2022, 11, 1, 8, 0, 0).unwrap();
+    let max_a = journal_max(created, 0);
+    let diff = self::diff(&a, Some(&max_a), &b, Some(&journal_max(created, 0)));
+    assert!(!diff.journal_recreated());
+    assert_eq!(diff.differing_records.len(), 1);
+
+    let diff = self::diff(&a, Some(&max_a), &b, Some(&journal_max(created + Duration::days(17), 0)));
+    assert!(diff.journal_recreated());
+    assert!(diff.is_consistent());
+    assert_eq!(diff.overwritten.records, 4);
+    assert_eq!(diff.new_records.records, 1);
+    assert_eq!(diff.created.len(), 1);
+}
@@ -0,0 +1,105 @@
+use usnjrnl::{UsrJrnlIterator, UsnReasonValue::*};
+use usnjrnl::analysis::{Analyzer, DropAlert, DropAnalyzer, DropKind, DropWatchlist};
+
+mod common;
+use common::*;
+
+fn analyze(journal: &Journal, watchlist: DropWatchlist) -> Vec<DropAlert> {
+    let mut analyzer = DropAnalyzer::new(watchlist);
+    for record in UsrJrnlIterator::from(journal.cursor()) {
+        analyzer.add(&record.unwrap());
+    }
+    analyzer.finish()
+}
+
+/// `/Users/bob/{AppData/Local/Temp,Downloads,Documents}`
+fn directories() -> Journal {
+    Journal::with_records(vec![
+        TestRecord::new(100, 5, "Users", &[USN_REASON_CLOSE]).with_attributes(0x10),
+        TestRecord::new(200, 100, "bob", &[USN_REASON_CLOSE]).with_attributes(0x10),
+        TestRecord::new(210, 200, "AppData", &[USN_REASON_CLOSE]).with_attributes(0x10),
+        TestRecord::new(220, 210, "Local", &[USN_REASON_CLOSE]).with_attributes(0x10),
+        TestRecord::new(230, 220, "Temp", &[USN_REASON_CLOSE]).with_attributes(0x10),
+        TestRecord::new(240, 200, "Downloads", &[USN_REASON_CLOSE]).with_attributes(0x10),
+        TestRecord::new(250, 200, "Documents", &[USN_REASON_CLOSE]).with_attributes(0x10),
+    ])
+}
+
+#[test]
+fn test_drops() {
+    let mut journal = directories();
+    let created = journal.push(TestRecord::new(300, 230, "payload.exe", &[USN_REASON_FILE_CREATE]));
+    journal.push(TestRecord::new(300, 230, "payload.exe", &[USN_REASON_FILE_CREATE, USN_REASON_DATA_EXTEND, USN_REASON_CLOSE]));
+
+    /* not in a watched location, or not a watched extension */
+    journal.push(TestRecord::new(301, 250, "report.exe", &[USN_REASON_FILE_CREATE, USN_REASON_CLOSE]));
+    journal.push(TestRecord::new(302, 240, "readme.txt", &[USN_REASON_FILE_CREATE, USN_REASON_CLOSE]));
+
+    journal.push(TestRecord::new(303, 240, "invoice.pdf", &[USN_REASON_FILE_CREATE, USN_REASON_CLOSE]));
+    journal.push(TestRecord::new(303, 240, "invoice.pdf", &[USN_REASON_RENAME_OLD_NAME]));
+    let renamed = journal.push(TestRecord::new(303, 240, "invoice.pdf.js", &[USN_REASON_RENAME_NEW_NAME, USN_REASON_CLOSE]));
+
+    journal.push(TestRecord::new(304, 250, "tool.ps1", &[USN_REASON_FILE_CREATE, USN_REASON_CLOSE]));
+    journal.push(TestRecord::new(304, 250, "tool.ps1", &[USN_REASON_RENAME_OLD_NAME]));
+    let moved = journal.push(TestRecord::new(304, 240, "tool.ps1", &[USN_REASON_RENAME_NEW_NAME, USN_REASON_CLOSE]));
+
+    /* renamed within the location, after it has already been reported */
+    journal.push(TestRecord::new(300, 230, "payload.exe", &[USN_REASON_RENAME_OLD_NAME]));
+    journal.push(TestRecord::new(300, 230, "setup.exe", &[USN_REASON_RENAME_NEW_NAME, USN_REASON_CLOSE]));
+
+    let alerts = analyze(&journal, DropWatchlist::default());
+    let summary: Vec<_> = alerts.iter().map(|a| (a.kind, a.path(), a.location.as_str(), a.record.usn)).collect();
+    assert_eq!(summary, vec![
+        (DropKind::Created, "/Users/bob/AppData/Local/Temp/payload.exe".to_owned(), "Temp", created),
+        (DropKind::Renamed, "/Users/bob/Downloads/invoice.pdf.js".to_owned(), "Downloads", renamed),
+        (DropKind::Moved, "/Users/bob/Downloads/tool.ps1".to_owned(), "Downloads", moved),
+    ]);
+    assert_eq!(alerts[1].previous_name.as_deref(), Some("invoice.pdf"));
+}
+
+#[test]
+fn test_unresolved_paths() {
+    let journal = Journal::with_records(vec![
+        TestRecord::new(410, 400, "Temp", &[USN_REASON_CLOSE]).with_attributes(0x10),
+        TestRecord::new(300, 410, "run.bat", &[USN_REASON_FILE_CREATE, USN_REASON_CLOSE]),
+        TestRecord::new(301, 999, "other.bat", &[USN_REASON_FILE_CREATE, USN_REASON_CLOSE]),
+    ]);
+
+    let alerts = analyze(&journal, DropWatchlist::default());
+    assert_eq!(alerts.len(), 1);
+    assert_eq!(alerts[0].directory, "?/Temp");
+    assert_eq!(alerts[0].path(), "?/Temp/run.bat");
+
+    /* a directory, which is not known at all */
+    let alert = DropAlert { directory: "?".to_owned(), ..alerts[0].clone() };
+    assert_eq!(alert.path(), "?/run.bat");
+}
+
+#[test]
+fn test_watchlists() {
+    let watchlist = DropWatchlist::from_toml(r#"
+        extensions = [".PDF"]
+        locations = ['bob\Documents']
+    "#).unwrap();
+    assert!(watchlist.is_watched_name("invoice.pdf"));
+    assert!(!watchlist.is_watched_name("payload.exe"));
+    assert_eq!(watchlist.location_of("/Users/Bob/documents/2022"), Some(r"bob\Documents"));
+    assert_eq!(watchlist.location_of("/Users/bob/Downloads"), None);
+
+    let watchlist = DropWatchlist::from_yaml("locations:\n  - Desktop\n").unwrap();
+    assert_eq!(watchlist.extensions, DropWatchlist::default().extensions);
+    assert_eq!(watchlist.locations, vec!["Desktop"]);
+
+    assert!(DropWatchlist::from_toml("extension = [\"exe\"]").is_err());
+
+    let mut journal = directories();
+    journal.push(TestRecord::new(300, 250, "invoice.pdf", &[USN_REASON_FILE_CREATE, USN_REASON_CLOSE]));
+    journal.push(TestRecord::new(301, 230, "payload.exe", &[USN_REASON_FILE_CREATE, USN_REASON_CLOSE]));
+    let watchlist = DropWatchlist {
+        extensions: vec!["pdf".to_owned()],
+        locations: vec!["Documents".to_owned()],
+    };
+    let alerts = analyze(&journal, watchlist);
+    assert_eq!(alerts.len(), 1);
+    assert_eq!(alerts[0].path(), "/Users/bob/Documents/invoice.pdf");
+}
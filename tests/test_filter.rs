@@ -0,0 +1,130 @@
+use usnjrnl::{CommonUsnRecord, UsrJrnlIterator, FileAttributeValue::*, UsnReasonValue::*};
+use usnjrnl::filter::*;
+
+mod common;
+use common::*;
+
+fn records() -> Vec<CommonUsnRecord> {
+    let journal = Journal::with_records(vec![
+        TestRecord::new(100, 5, "Temp", &[USN_REASON_CLOSE]).with_attributes(0x10),
+        TestRecord::new(200, 100, "a.txt", &[USN_REASON_FILE_CREATE]),
+        TestRecord::new(200, 100, "a.txt", &[USN_REASON_FILE_CREATE, USN_REASON_CLOSE]),
+        TestRecord::new(201, 100, "b.txt", &[USN_REASON_RENAME_OLD_NAME]).with_attributes(0x22),
+        TestRecord::new(202, 100, "c.txt", &[USN_REASON_FILE_DELETE, USN_REASON_CLOSE]).with_attributes(0x2022),
+    ]);
+    UsrJrnlIterator::from(journal.cursor()).collect::<Result<_, _>>().unwrap()
+}
+
+fn matching_names<F: RecordFilter>(filter: &F) -> Vec<String> {
+    records().iter()
+        .filter(|r| filter.matches(r))
+        .map(|r| r.data.filename().to_owned())
+        .collect()
+}
+
+#[test]
+fn test_reason_filter() {
+    assert_eq!(matching_names(&ReasonFilter::any(&[USN_REASON_FILE_DELETE, USN_REASON_RENAME_OLD_NAME])), ["b.txt", "c.txt"]);
+    assert_eq!(matching_names(&ReasonFilter::all(&[USN_REASON_FILE_CREATE, USN_REASON_CLOSE])), ["a.txt"]);
+    assert_eq!(matching_names(&Not::new(ReasonFilter::any(&[USN_REASON_CLOSE]))), ["a.txt", "b.txt"]);
+}
+
+#[test]
+fn test_attribute_filter() {
+    assert_eq!(matching_names(&AttributeFilter::any(&[FILE_ATTRIBUTE_DIRECTORY, FILE_ATTRIBUTE_HIDDEN])), ["Temp", "b.txt", "c.txt"]);
+    assert_eq!(matching_names(&AttributeFilter::all(&[FILE_ATTRIBUTE_HIDDEN, FILE_ATTRIBUTE_NOT_CONTENT_INDEXED])), ["c.txt"]);
+}
+
+#[test]
+fn test_combined_filters() {
+    let filter = AllOf::default()
+        .with(ReasonFilter::any(&[USN_REASON_CLOSE]))
+        .with(Not::new(AttributeFilter::any(&[FILE_ATTRIBUTE_DIRECTORY])));
+    assert_eq!(matching_names(&filter), ["a.txt", "c.txt"]);
+
+    let filter = AnyOf::default()
+        .with(AttributeFilter::any(&[FILE_ATTRIBUTE_DIRECTORY]))
+        .with(|r: &CommonUsnRecord| r.data.filename().starts_with('b'));
+    assert_eq!(matching_names(&filter), ["Temp", "b.txt"]);
+
+    assert_eq!(matching_names(&AllOf::default()).len(), 5);
+    assert!(matching_names(&AnyOf::default()).is_empty());
+}
+
+#[test]
+fn test_ntfs_upcase() {
+    assert_eq!(ntfs_upcase("Straße.txt"), "STRAßE.TXT");
+    assert_eq!(ntfs_upcase("ÄöÜ-ǆ"), "ÄÖÜ-Ǆ");
+    assert_eq!(ntfs_upcase("😀a"), "😀A");
+}
+
+#[test]
+fn test_name_glob() {
+    let glob = NameGlob::new("*.PS1").unwrap();
+    assert!(glob.is_match("invoke.ps1"));
+    assert!(!glob.is_match("invoke.ps1.txt"));
+    assert!(NameGlob::new("mimikatz*").unwrap().is_match("MimiKatz.exe"));
+    assert!(NameGlob::new("file[?].txt").unwrap().is_match("file[1].txt"));
+    assert!(!NameGlob::new("file[?].txt").unwrap().is_match("file1.txt"));
+    assert_eq!(matching_names(&NameGlob::new("?.TXT").unwrap()), ["a.txt", "a.txt", "b.txt", "c.txt"]);
+}
+
+#[test]
+fn test_name_regex() {
+    let regex = NameRegex::new(r"\.(locked|encrypted)$").unwrap();
+    assert!(regex.is_match("report.docx.LOCKED"));
+    assert!(!regex.is_match("report.locked.docx"));
+    assert!(NameRegex::new("(").is_err());
+    assert_eq!(matching_names(&NameRegex::new("^[bc]").unwrap()), ["b.txt", "c.txt"]);
+}
+
+#[test]
+fn test_name_regex_case_folding() {
+    /* NTFS does not treat these names as equal, but Unicode case folding does */
+    assert!(NameRegex::new("^kelvin").unwrap().is_match("\u{212a}elvin.txt"));
+    assert!(!NameGlob::new("kelvin*").unwrap().is_match("\u{212a}elvin.txt"));
+    assert!(NameRegex::new("^\u{10400}").unwrap().is_match("\u{10428}.txt"));
+    assert!(!NameGlob::new("\u{10400}*").unwrap().is_match("\u{10428}.txt"));
+
+    /* letters which NTFS converts to upper case match either way */
+    assert!(NameRegex::new("^ǆ").unwrap().is_match("Ǆ.txt"));
+    assert!(NameGlob::new("ǆ*").unwrap().is_match("Ǆ.txt"));
+}
+
+#[test]
+fn test_name_list() {
+    let list = NameList::new([
+        "# comment",
+        "mimikatz.exe",
+        "",
+        "psexesvc.exe",
+        "*.locked",
+        "  procdump64.exe  ",
+    ]).unwrap();
+    assert_eq!(list.len(), 4);
+    assert!(list.is_match("Mimikatz.EXE"));
+    assert!(list.is_match("procdump64.exe"));
+    assert!(list.is_match("invoice.pdf.locked"));
+    assert!(!list.is_match("mimikatz.exe.txt"));
+    assert!(!list.is_match("my_mimikatz.exe"));
+    assert!(!list.is_match("# comment"));
+
+    let list = NameList::new((0..5000).map(|i| format!("ioc{}.exe", i))).unwrap();
+    assert!(list.is_match("IOC4711.exe"));
+    assert!(!list.is_match("ioc5000.exe"));
+}
+
+#[test]
+fn test_reference_filters() {
+    assert_eq!("12345".parse(), Ok(EntryReference::new(12345, None)));
+    assert_eq!("12345-3".parse(), Ok(EntryReference::new(12345, Some(3))));
+    assert!("12345-".parse::<EntryReference>().is_err());
+    assert!("abc".parse::<EntryReference>().is_err());
+    assert_eq!(EntryReference::new(7, Some(2)).to_string(), "7-2");
+
+    assert_eq!(matching_names(&FileReferenceFilter::new("200".parse().unwrap())), ["a.txt", "a.txt"]);
+    assert_eq!(matching_names(&FileReferenceFilter::new("200-1".parse().unwrap())), ["a.txt", "a.txt"]);
+    assert!(matching_names(&FileReferenceFilter::new("200-2".parse().unwrap())).is_empty());
+    assert_eq!(matching_names(&ParentFilter::new("100".parse().unwrap())).len(), 4);
+    assert_eq!(matching_names(&ParentFilter::new("5".parse().unwrap())), ["Temp"]);
+}
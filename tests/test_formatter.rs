@@ -0,0 +1,72 @@
+use std::io::{self, Write};
+use usnjrnl::{CommonUsnRecord, UsrJrnlIterator, UsnReasonValue::*};
+use usnjrnl::formatter::{FormatOptions, FormatRegistry, RecordWriter, write_records};
+
+mod common;
+use common::*;
+
+/// counts the records and writes the total after the last record
+#[derive(Default)]
+struct CountingWriter {
+    records: usize,
+}
+
+impl RecordWriter for CountingWriter {
+    fn write_header(&mut self, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "names:")
+    }
+
+    fn write_record(&mut self, out: &mut dyn Write, record: &CommonUsnRecord) -> io::Result<()> {
+        self.records += 1;
+        writeln!(out, "{}", record.data.filename())
+    }
+
+    fn finish(&mut self, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "{} records", self.records)
+    }
+}
+
+#[test]
+fn test_custom_format() {
+    let journal = Journal::with_records(vec![
+        TestRecord::new(100, 5, "first.txt", &[USN_REASON_FILE_CREATE]),
+        TestRecord::new(100, 5, "first.txt", &[USN_REASON_FILE_CREATE, USN_REASON_CLOSE]),
+        TestRecord::new(200, 5, "second.txt", &[USN_REASON_FILE_DELETE, USN_REASON_CLOSE]),
+    ]);
+
+    let mut registry = FormatRegistry::default();
+    let builtin_formats = registry.names().count();
+    registry.register("count", |_| Box::<CountingWriter>::default());
+    assert_eq!(registry.names().count(), builtin_formats + 1);
+    assert_eq!(registry.names().last(), Some("count"));
+
+    let mut writer = registry.create("count", &FormatOptions::default()).unwrap();
+    let mut output = Vec::new();
+    write_records(UsrJrnlIterator::from(journal.cursor()), writer.as_mut(), &mut output).unwrap();
+    assert_eq!(String::from_utf8(output).unwrap(), "names:\nfirst.txt\nfirst.txt\nsecond.txt\n3 records\n");
+}
+
+#[test]
+fn test_builtin_formats() {
+    let journal = Journal::with_records(vec![
+        TestRecord::new(100, 5, "notes.txt", &[USN_REASON_FILE_CREATE, USN_REASON_CLOSE]),
+    ]);
+
+    let mut registry = FormatRegistry::default();
+    assert!(registry.create("unknown", &FormatOptions::default()).is_none());
+
+    let mut writer = registry.create("tln", &FormatOptions::default()).unwrap();
+    let mut output = Vec::new();
+    write_records(UsrJrnlIterator::from(journal.cursor()), writer.as_mut(), &mut output).unwrap();
+    assert_eq!(String::from_utf8(output).unwrap(),
+        "1668772800|USN|||/notes.txt: CLOSE+FILE_CREATE (FileRef: 100/1, USN: 0)\n");
+
+    /* registering an existing name replaces the builtin format */
+    let formats = registry.names().count();
+    registry.register("tln", |_| Box::<CountingWriter>::default());
+    assert_eq!(registry.names().count(), formats);
+    let mut writer = registry.create("tln", &FormatOptions::default()).unwrap();
+    let mut output = Vec::new();
+    write_records(UsrJrnlIterator::from(journal.cursor()), writer.as_mut(), &mut output).unwrap();
+    assert_eq!(String::from_utf8(output).unwrap(), "names:\nnotes.txt\n1 records\n");
+}
@@ -0,0 +1,115 @@
+use chrono::{Duration, TimeZone, Utc};
+use usnjrnl::analysis::window_start;
+use usnjrnl::{Heatmap, HeatmapCollector, HeatmapSettings, UsnReasonClass, UsrJrnlIterator, UsnReasonValue::*};
+
+mod common;
+use common::*;
+
+fn collect(journal: &Journal, settings: HeatmapSettings) -> Heatmap {
+    let mut collector = HeatmapCollector::new(settings);
+    for record in UsrJrnlIterator::from(journal.cursor()) {
+        collector.add(&record.unwrap());
+    }
+    collector.finish()
+}
+
+#[test]
+fn test_rollup() {
+    let at = |minute, second| Utc.with_ymd_and_hms(2022, 11, 18, 2, minute, second).unwrap();
+    let mut journal = Journal::with_records(vec![
+        TestRecord::new(100, 5, "Users", &[USN_REASON_CLOSE]).with_attributes(0x10).at(at(0, 0)),
+        TestRecord::new(200, 100, "bob", &[USN_REASON_CLOSE]).with_attributes(0x10).at(at(0, 0)),
+        TestRecord::new(210, 200, "Temp", &[USN_REASON_CLOSE]).with_attributes(0x10).at(at(0, 0)),
+        TestRecord::new(220, 100, "alice", &[USN_REASON_CLOSE]).with_attributes(0x10).at(at(0, 0)),
+    ]);
+    for (entry, second) in (300..310).zip(0..) {
+        journal.push(TestRecord::new(entry, 210, "x9f.tmp", &[USN_REASON_DATA_OVERWRITE, USN_REASON_CLOSE]).at(at(13, second)));
+    }
+    journal.push(TestRecord::new(310, 210, "new.tmp", &[USN_REASON_FILE_CREATE, USN_REASON_DATA_EXTEND]).at(at(14, 0)));
+    journal.push(TestRecord::new(311, 220, "notes.txt", &[USN_REASON_FILE_DELETE, USN_REASON_CLOSE]).at(at(20, 0)));
+    journal.push(TestRecord::new(312, 100, "desktop.ini", &[USN_REASON_BASIC_INFO_CHANGE]).at(at(20, 0)));
+    journal.push(TestRecord::new(313, 999, "orphan.txt", &[USN_REASON_FILE_CREATE]).at(at(20, 0)));
+
+    let heatmap = collect(&journal, HeatmapSettings::default());
+    let tree: Vec<_> = heatmap.root.iter()
+        .map(|(depth, node)| (depth, node.path.as_str(), node.own.total(), node.total.total()))
+        .collect();
+    assert_eq!(tree, vec![
+        (0, "/", 0, 14),
+        (1, "/Users", 1, 14),
+        (2, "/Users/bob", 0, 12),
+        (3, "/Users/bob/Temp", 12, 12),
+        (2, "/Users/alice", 1, 1),
+    ]);
+
+    let temp = &heatmap.root.children[0].children[0].children[0];
+    assert_eq!(temp.total.get(UsnReasonClass::Data), 11);
+    assert_eq!(temp.total.get(UsnReasonClass::Create), 1);
+    assert_eq!(temp.total.get(UsnReasonClass::Close), 0);
+    assert_eq!(temp.total.to_string(), "create 1, data 11");
+    let (start, counts) = temp.peak().unwrap();
+    assert_eq!((*start, counts.total()), (at(13, 0), 10));
+    assert_eq!(heatmap.root.buckets.len(), 3);
+
+    assert_eq!(heatmap.hotspots[0].path, "/Users/bob/Temp");
+    assert_eq!(heatmap.hotspots[0].start, at(13, 0));
+    assert_eq!(heatmap.hotspots[0].counts.total(), 10);
+
+    assert_eq!(heatmap.unresolved.total.total(), 1);
+    assert_eq!(heatmap.unresolved.children[0].path, "?/[999-1]");
+
+    let mut heatmap = heatmap;
+    heatmap.prune(2);
+    assert_eq!(heatmap.root.iter().count(), 4);
+    heatmap.root.truncate(1);
+    assert_eq!(heatmap.root.iter().count(), 2);
+    assert_eq!(heatmap.root.total.total(), 14);
+}
+
+#[test]
+fn test_settings() {
+    let start = Utc.with_ymd_and_hms(2022, 11, 18, 2, 0, 0).unwrap();
+    let journal = Journal::with_records(vec![
+        TestRecord::new(300, 5, "a.txt", &[USN_REASON_FILE_CREATE, USN_REASON_CLOSE]).at(start),
+        TestRecord::new(301, 5, "b.txt", &[USN_REASON_DATA_EXTEND, USN_REASON_CLOSE]).at(start + Duration::minutes(59)),
+        TestRecord::new(302, 5, "c.txt", &[USN_REASON_FILE_DELETE, USN_REASON_CLOSE]).at(start + Duration::minutes(60)),
+    ]);
+
+    let settings = HeatmapSettings {
+        bucket: Duration::hours(1),
+        classes: vec![UsnReasonClass::Create, UsnReasonClass::Close],
+    };
+    let heatmap = collect(&journal, settings);
+    let buckets: Vec<_> = heatmap.root.buckets.iter().map(|(start, counts)| (*start, counts.to_string())).collect();
+    assert_eq!(buckets, vec![
+        (start, "create 1, close 2".to_owned()),
+        (start + Duration::hours(1), "close 1".to_owned()),
+    ]);
+    assert_eq!(heatmap.root.own.total(), 4);
+}
+
+#[test]
+fn test_reference_loop() {
+    /* two directories, which are each other's parent */
+    let journal = Journal::with_records(vec![
+        TestRecord::new(200, 201, "a", &[USN_REASON_CLOSE]).with_attributes(0x10),
+        TestRecord::new(201, 200, "b", &[USN_REASON_CLOSE]).with_attributes(0x10),
+        TestRecord::new(300, 200, "loop.txt", &[USN_REASON_FILE_CREATE]),
+    ]);
+    let heatmap = collect(&journal, HeatmapSettings::default());
+    assert_eq!(heatmap.root.total.total(), 0);
+    assert_eq!(heatmap.unresolved.total.total(), 1);
+    let paths: Vec<_> = heatmap.unresolved.iter().map(|(_, node)| node.path.as_str()).collect();
+    assert_eq!(paths, vec!["?", "?/b", "?/b/a"]);
+}
+
+#[test]
+fn test_window_start() {
+    let timestamp = Utc.with_ymd_and_hms(2022, 11, 18, 14, 59, 30).unwrap();
+    assert_eq!(window_start(&timestamp, Duration::hours(1)), Utc.with_ymd_and_hms(2022, 11, 18, 14, 0, 0).unwrap());
+    assert_eq!(window_start(&timestamp, Duration::zero()), timestamp);
+
+    let before_epoch = Utc.with_ymd_and_hms(1969, 12, 31, 23, 59, 30).unwrap();
+    assert_eq!(window_start(&before_epoch, Duration::minutes(1)), before_epoch - Duration::seconds(30));
+    assert_eq!(window_start(&before_epoch, Duration::MAX), chrono::DateTime::<Utc>::MIN_UTC);
+}
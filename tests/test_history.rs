@@ -0,0 +1,56 @@
+use usnjrnl::{FileAction, FileHistory, UsrJrnlIterator, UsnReasonValue::*};
+use usnjrnl::filter::EntryReference;
+
+mod common;
+use common::*;
+
+fn journal() -> Journal {
+    Journal::with_records(vec![
+        TestRecord::new(300, 100, "invoice.pdf", &[USN_REASON_FILE_CREATE]),
+        TestRecord::new(300, 100, "invoice.pdf", &[USN_REASON_FILE_CREATE, USN_REASON_DATA_EXTEND]),
+        TestRecord::new(300, 100, "invoice.pdf", &[USN_REASON_FILE_CREATE, USN_REASON_DATA_EXTEND, USN_REASON_CLOSE]),
+        TestRecord::new(301, 100, "other.txt", &[USN_REASON_FILE_CREATE, USN_REASON_CLOSE]),
+        TestRecord::new(300, 100, "invoice.pdf", &[USN_REASON_RENAME_OLD_NAME]),
+        TestRecord::new(300, 100, "invoice.pdf.locked", &[USN_REASON_RENAME_NEW_NAME]),
+        TestRecord::new(300, 100, "invoice.pdf.locked", &[USN_REASON_RENAME_NEW_NAME, USN_REASON_BASIC_INFO_CHANGE]),
+        TestRecord::new(300, 100, "invoice.pdf.locked", &[USN_REASON_RENAME_NEW_NAME, USN_REASON_BASIC_INFO_CHANGE, USN_REASON_CLOSE]),
+        TestRecord::new(300, 100, "invoice.pdf.locked", &[USN_REASON_FILE_DELETE, USN_REASON_CLOSE]),
+        TestRecord::new(300, 100, "reused.tmp", &[USN_REASON_FILE_CREATE, USN_REASON_CLOSE]).with_sequence(2),
+    ])
+}
+
+fn history_of(reference: &str) -> FileHistory {
+    let mut history = FileHistory::new(reference.parse::<EntryReference>().unwrap());
+    for record in UsrJrnlIterator::from(journal().cursor()) {
+        history.add(&record.unwrap());
+    }
+    history
+}
+
+#[test]
+fn test_file_history() {
+    let history = history_of("300-1");
+    let actions: Vec<_> = history.events().iter().map(|e| e.action.clone()).collect();
+    assert_eq!(actions, vec![
+        FileAction::Created,
+        FileAction::Written(vec![USN_REASON_DATA_EXTEND]),
+        FileAction::Renamed { old_name: Some("invoice.pdf".to_owned()), new_name: "invoice.pdf.locked".to_owned() },
+        FileAction::AttributesChanged(vec![USN_REASON_BASIC_INFO_CHANGE]),
+        FileAction::Deleted,
+    ]);
+    assert!(history.events().windows(2).all(|w| w[0].usn < w[1].usn));
+    assert_eq!(history.events()[2].action.to_string(), "renamed: invoice.pdf -> invoice.pdf.locked");
+}
+
+#[test]
+fn test_history_of_reused_entry() {
+    let history = history_of("300");
+    let events = history.events();
+    assert_eq!(events.len(), 6);
+    assert!(events[..5].iter().all(|e| e.file_reference.sequence == 1));
+    assert_eq!(events[5].file_reference.sequence, 2);
+    assert_eq!(events[5].name, "reused.tmp");
+    assert_eq!(events[5].action, FileAction::Created);
+
+    assert!(history_of("300-3").events().is_empty());
+}
@@ -0,0 +1,85 @@
+use chrono::{Duration, TimeZone, Utc};
+use usnjrnl::{UsrJrnlIterator, UsnReasonValue::*};
+use usnjrnl::analysis::{Analyzer, IntegrityAnomaly, IntegrityReport, IntegritySettings, IntegrityValidator};
+
+mod common;
+use common::*;
+
+fn validate(journal: &Journal) -> IntegrityReport {
+    let mut validator = IntegrityValidator::new(IntegritySettings::default());
+    for record in UsrJrnlIterator::from(journal.cursor()) {
+        validator.add(&record.unwrap());
+    }
+    validator.finish()
+}
+
+fn kinds(report: &IntegrityReport) -> Vec<&'static str> {
+    report.anomalies.iter().map(|a| a.kind()).collect()
+}
+
+#[test]
+fn test_valid_journal() {
+    /* enough records to cross a page boundary */
+    let mut journal = Journal::with_empty_pages(2);
+    for entry in 0..100 {
+        journal.push(TestRecord::new(300 + entry, 5, "notes.txt", &[USN_REASON_DATA_EXTEND]));
+    }
+
+    let report = validate(&journal);
+    assert_eq!(report.records, 100);
+    assert_eq!(report.usn_offset, Some(0));
+    assert_eq!(report.first_usn, Some(0x2000));
+    assert!(report.last_usn.unwrap() > 0x3000);
+    assert!(report.anomalies.is_empty());
+}
+
+#[test]
+fn test_timestamps() {
+    let start = Utc.with_ymd_and_hms(2022, 11, 18, 12, 0, 0).unwrap();
+    let mut journal = Journal::default();
+    journal.push(TestRecord::new(300, 5, "a.txt", &[USN_REASON_DATA_EXTEND]).at(start));
+    /* small regressions are tolerated */
+    journal.push(TestRecord::new(300, 5, "a.txt", &[USN_REASON_DATA_EXTEND]).at(start - Duration::milliseconds(500)));
+    let regression = journal.push(TestRecord::new(300, 5, "a.txt", &[USN_REASON_DATA_EXTEND]).at(start - Duration::hours(1)));
+    let gap = journal.push(TestRecord::new(300, 5, "a.txt", &[USN_REASON_DATA_EXTEND, USN_REASON_CLOSE]).at(start + Duration::days(3)));
+
+    let report = validate(&journal);
+    assert_eq!(kinds(&report), vec!["timestamp_regression", "time_gap"]);
+    assert_eq!(report.anomalies[0].record().usn, regression);
+    assert_eq!(report.anomalies[0].to_string(),
+        format!("the timestamp of USN {} is 3599 seconds older than the timestamp of USN {}", regression, regression - 0x48));
+    assert_eq!(report.anomalies[1].record().usn, gap);
+    assert!(matches!(&report.anomalies[1], IntegrityAnomaly::TimeGap { previous, .. } if previous.usn == regression));
+}
+
+#[test]
+fn test_usn_gap() {
+    let mut journal = Journal::default();
+    let first = journal.push(TestRecord::new(300, 5, "a.txt", &[USN_REASON_DATA_EXTEND]));
+    journal.append_empty_pages(3);
+    let second = journal.push(TestRecord::new(300, 5, "a.txt", &[USN_REASON_DATA_EXTEND, USN_REASON_CLOSE]));
+
+    let report = validate(&journal);
+    assert_eq!(kinds(&report), vec!["usn_gap"]);
+    assert_eq!(second, 0x4000);
+    assert!(matches!(&report.anomalies[0],
+        IntegrityAnomaly::UsnGap { previous, record, expected_usn } if previous.usn == first && record.usn == second && *expected_usn == 0x48));
+}
+
+#[test]
+fn test_manipulated_usn() {
+    let mut journal = Journal::default();
+    journal.push(TestRecord::new(300, 5, "a.txt", &[USN_REASON_DATA_EXTEND]));
+    journal.push(TestRecord::new(300, 5, "a.txt", &[USN_REASON_DATA_EXTEND]).with_usn(0));
+    journal.push(TestRecord::new(300, 5, "a.txt", &[USN_REASON_DATA_EXTEND, USN_REASON_CLOSE]));
+
+    let report = validate(&journal);
+    assert_eq!(kinds(&report), vec!["offset_mismatch", "usn_regression", "usn_gap"]);
+    assert!(matches!(&report.anomalies[0],
+        IntegrityAnomaly::OffsetMismatch { offset, expected_usn, .. } if *offset == 0x48 && *expected_usn == 0x48));
+
+    let json = report.to_json();
+    assert_eq!(json["anomalies"][0]["kind"], "offset_mismatch");
+    assert_eq!(json["anomalies"][0]["record"]["usn"], 0);
+    assert_eq!(json["anomalies"][0]["offset"], 0x48);
+}
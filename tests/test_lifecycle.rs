@@ -0,0 +1,131 @@
+use chrono::{TimeZone, Utc};
+use usnjrnl::{LifecycleEngine, Lifecycles, OperationCollector, UsrJrnlIterator, UsnReasonValue::*};
+use usnjrnl::filter::EntryReference;
+
+mod common;
+use common::*;
+
+fn journal() -> Journal {
+    let at = |minute| Utc.with_ymd_and_hms(2022, 11, 18, 12, minute, 0).unwrap();
+    Journal::with_records(vec![
+        TestRecord::new(300, 100, "invoice.pdf", &[USN_REASON_FILE_CREATE]).at(at(0)),
+        TestRecord::new(300, 100, "invoice.pdf", &[USN_REASON_FILE_CREATE, USN_REASON_DATA_EXTEND]).at(at(1)),
+        TestRecord::new(301, 100, "other.txt", &[USN_REASON_DATA_OVERWRITE]).at(at(2)),
+        TestRecord::new(300, 100, "invoice.pdf", &[USN_REASON_FILE_CREATE, USN_REASON_DATA_EXTEND, USN_REASON_CLOSE]).at(at(3)),
+        TestRecord::new(300, 100, "invoice.pdf", &[USN_REASON_RENAME_OLD_NAME]).at(at(10)),
+        TestRecord::new(300, 101, "Invoice.pdf.locked", &[USN_REASON_RENAME_NEW_NAME]).at(at(10)),
+        TestRecord::new(300, 101, "Invoice.pdf.locked", &[USN_REASON_RENAME_NEW_NAME, USN_REASON_CLOSE]).at(at(11)),
+        TestRecord::new(300, 101, "Invoice.pdf.locked", &[USN_REASON_FILE_DELETE, USN_REASON_CLOSE]).at(at(20)),
+        TestRecord::new(300, 100, "reused.tmp", &[USN_REASON_FILE_CREATE, USN_REASON_CLOSE]).with_sequence(2).at(at(30)),
+    ])
+}
+
+fn lifecycles() -> Lifecycles {
+    let mut engine = LifecycleEngine::default();
+    for record in UsrJrnlIterator::from(journal().cursor()) {
+        engine.add(&record.unwrap());
+    }
+    engine.finish()
+}
+
+#[test]
+fn test_operations() {
+    let mut collector = OperationCollector::default();
+    let mut operations = Vec::new();
+    for record in UsrJrnlIterator::from(journal().cursor()) {
+        operations.extend(collector.add(&record.unwrap()));
+    }
+    assert_eq!(operations.len(), 4);
+
+    let created = &operations[0];
+    assert_eq!(created.file_reference.entry, 300);
+    assert_eq!(created.records, 3);
+    assert_eq!(created.reasons.to_string(), "CLOSE+DATA_EXTEND+FILE_CREATE");
+    assert_eq!(created.start, Utc.with_ymd_and_hms(2022, 11, 18, 12, 0, 0).unwrap());
+    assert_eq!(created.end, Utc.with_ymd_and_hms(2022, 11, 18, 12, 3, 0).unwrap());
+    assert!(created.closed && created.is_write() && !created.is_rename());
+
+    let renamed = &operations[1];
+    assert_eq!((renamed.name_before.as_str(), renamed.name_after.as_str()), ("invoice.pdf", "Invoice.pdf.locked"));
+    assert_eq!((renamed.parent_before.entry, renamed.parent_after.entry), (100, 101));
+    assert!(renamed.is_rename() && !renamed.is_write());
+
+    /* the journal ends before other.txt is closed */
+    let open = collector.finish();
+    assert_eq!(open.len(), 1);
+    assert_eq!(open[0].name_after, "other.txt");
+    assert!(!open[0].closed);
+    assert!(collector.finish().is_empty());
+}
+
+#[test]
+fn test_lifecycles() {
+    let lifecycles = lifecycles();
+    assert_eq!(lifecycles.len(), 3);
+    let names: Vec<_> = lifecycles.iter().map(|l| l.name()).collect();
+    assert_eq!(names, vec!["Invoice.pdf.locked", "other.txt", "reused.tmp"]);
+
+    let invoice = lifecycles.iter().next().unwrap();
+    assert_eq!(invoice.operations.len(), 3);
+    assert_eq!(invoice.names(), vec!["invoice.pdf", "Invoice.pdf.locked"]);
+    assert_eq!(invoice.created(), Some(&Utc.with_ymd_and_hms(2022, 11, 18, 12, 0, 0).unwrap()));
+    assert_eq!(invoice.deleted(), Some(&Utc.with_ymd_and_hms(2022, 11, 18, 12, 20, 0).unwrap()));
+    assert_eq!(invoice.renames().count(), 1);
+    assert_eq!(invoice.writes().count(), 1);
+    assert!(invoice.is_complete());
+    assert!(std::ptr::eq(lifecycles.get(&invoice.file_reference).unwrap(), invoice));
+
+    let other = lifecycles.by_name("OTHER.TXT").next().unwrap();
+    assert!(!other.operations[0].closed);
+    assert!(!other.is_complete());
+}
+
+#[test]
+fn test_lifecycle_queries() {
+    let lifecycles = lifecycles();
+    let entry: EntryReference = "300".parse().unwrap();
+    assert_eq!(lifecycles.by_entry(&entry).count(), 2);
+    let entry: EntryReference = "300-2".parse().unwrap();
+    assert_eq!(lifecycles.by_entry(&entry).map(|l| l.name()).collect::<Vec<_>>(), vec!["reused.tmp"]);
+
+    assert_eq!(lifecycles.by_name("invoice.PDF").count(), 1);
+    assert_eq!(lifecycles.by_name("missing.txt").count(), 0);
+
+    let from = Utc.with_ymd_and_hms(2022, 11, 18, 12, 15, 0).unwrap();
+    let to = Utc.with_ymd_and_hms(2022, 11, 18, 12, 25, 0).unwrap();
+    let active: Vec<_> = lifecycles.active_between(&from, &to).map(|l| l.name()).collect();
+    assert_eq!(active, vec!["Invoice.pdf.locked"]);
+}
+
+#[test]
+fn test_unordered_timestamps() {
+    let at = |minute| Utc.with_ymd_and_hms(2022, 11, 18, 12, minute, 0).unwrap();
+    /* the clock was set back between both operations */
+    let journal = Journal::with_records(vec![
+        TestRecord::new(400, 100, "skewed.txt", &[USN_REASON_FILE_CREATE, USN_REASON_CLOSE]).at(at(40)),
+        TestRecord::new(400, 100, "skewed.txt", &[USN_REASON_DATA_EXTEND, USN_REASON_CLOSE]).at(at(35)),
+    ]);
+    let mut engine = LifecycleEngine::default();
+    for record in UsrJrnlIterator::from(journal.cursor()) {
+        engine.add(&record.unwrap());
+    }
+    let lifecycles = engine.finish();
+    let skewed = lifecycles.iter().next().unwrap();
+    assert_eq!(skewed.first_seen(), &at(35));
+    assert_eq!(skewed.last_seen(), &at(40));
+    assert_eq!(lifecycles.active_between(&at(38), &at(50)).count(), 1);
+    assert_eq!(lifecycles.active_between(&at(30), &at(36)).count(), 1);
+}
+
+#[test]
+fn test_lifecycle_json() {
+    let json = lifecycles().to_json();
+    let invoice = &json[0];
+    assert_eq!(invoice["file_reference"], "300-1");
+    assert_eq!(invoice["created"], "2022-11-18T12:00:00.000000Z");
+    assert_eq!(invoice["deleted"], "2022-11-18T12:20:00.000000Z");
+    assert_eq!(invoice["names"], serde_json::json!(["invoice.pdf", "Invoice.pdf.locked"]));
+    assert_eq!(invoice["operations"][1]["reasons"], serde_json::json!(["CLOSE", "RENAME_NEW_NAME", "RENAME_OLD_NAME"]));
+    assert_eq!(invoice["operations"][1]["parent_after"], "101-1");
+    assert_eq!(json[1]["operations"][0]["closed"], false);
+}
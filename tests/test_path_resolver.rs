@@ -0,0 +1,52 @@
+use usnjrnl::{PathResolver, UsrJrnlIterator, UsnReasonValue::*};
+
+mod common;
+use common::*;
+
+#[test]
+fn test_resolve_paths() {
+    let journal = Journal::with_records(vec![
+        TestRecord::new(100, 5, "Users", &[USN_REASON_CLOSE]),
+        TestRecord::new(200, 100, "alice", &[USN_REASON_CLOSE]),
+        TestRecord::new(300, 200, "notes.txt", &[USN_REASON_FILE_CREATE]),
+        TestRecord::new(400, 999, "orphan.txt", &[USN_REASON_FILE_CREATE]),
+    ]);
+
+    let records: Vec<_> = UsrJrnlIterator::from(journal.cursor())
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(records.len(), 4);
+
+    let mut resolver = PathResolver::default();
+    for record in records.iter() {
+        resolver.learn(record);
+    }
+    assert_eq!(resolver.len(), 4);
+
+    assert_eq!(resolver.full_path(&records[0]).unwrap(), "/Users");
+    assert_eq!(resolver.full_path(&records[2]).unwrap(), "/Users/alice/notes.txt");
+    assert!(resolver.full_path(&records[3]).is_none());
+    assert_eq!(resolver.path_of(records[0].data.parent_reference(), "Windows").unwrap(), "/Windows");
+    assert_eq!(resolver.path_of(records[2].data.parent_reference(), "todo.txt").unwrap(), "/Users/alice/todo.txt");
+
+    assert_eq!(resolver.partial_directory_path(records[2].data.parent_reference()), "/Users/alice");
+    assert_eq!(resolver.partial_directory_path(records[3].data.parent_reference()), "?");
+}
+
+#[test]
+fn test_partial_paths() {
+    let journal = Journal::with_records(vec![
+        TestRecord::new(200, 100, "alice", &[USN_REASON_CLOSE]),
+        TestRecord::new(300, 200, "Temp", &[USN_REASON_CLOSE]),
+        TestRecord::new(400, 300, "payload.exe", &[USN_REASON_FILE_CREATE]),
+    ]);
+
+    let mut resolver = PathResolver::default();
+    let records: Vec<_> = UsrJrnlIterator::from(journal.cursor()).map(Result::unwrap).collect();
+    for record in records.iter() {
+        resolver.learn(record);
+    }
+
+    assert!(resolver.full_path(&records[2]).is_none());
+    assert_eq!(resolver.partial_directory_path(records[2].data.parent_reference()), "?/alice/Temp");
+}
@@ -0,0 +1,100 @@
+use std::rc::Rc;
+use chrono::{TimeZone, Utc};
+use usnjrnl::{CommonUsnRecord, PathResolver, UsrJrnlIterator, UsnReasonValue::*};
+use usnjrnl::filter::{Query, RecordFilter};
+
+mod common;
+use common::*;
+
+fn records() -> Vec<CommonUsnRecord> {
+    let journal = Journal::with_records(vec![
+        TestRecord::new(100, 5, "Temp", &[USN_REASON_CLOSE]).with_attributes(0x10),
+        TestRecord::new(200, 100, "dropper.EXE", &[USN_REASON_FILE_CREATE, USN_REASON_CLOSE])
+            .at(Utc.with_ymd_and_hms(2026, 3, 2, 8, 0, 0).unwrap()),
+        TestRecord::new(201, 100, "payload.exe", &[USN_REASON_FILE_DELETE, USN_REASON_CLOSE])
+            .at(Utc.with_ymd_and_hms(2026, 3, 2, 9, 0, 0).unwrap()),
+        TestRecord::new(202, 100, "old.exe", &[USN_REASON_FILE_DELETE, USN_REASON_CLOSE]).with_sequence(4),
+        TestRecord::new(203, 5, "notes.txt", &[USN_REASON_DATA_EXTEND]).with_attributes(0x22),
+    ]);
+    UsrJrnlIterator::from(journal.cursor()).collect::<Result<_, _>>().unwrap()
+}
+
+fn matching_names(query: &str) -> Vec<String> {
+    let records = records();
+    let mut resolver = PathResolver::default();
+    records.iter().for_each(|r| resolver.learn(r));
+    let query = Query::parse(query).unwrap().with_resolver(Rc::new(resolver));
+    records.iter()
+        .filter(|r| query.matches(r))
+        .map(|r| r.data.filename().to_owned())
+        .collect()
+}
+
+#[test]
+fn test_example_query() {
+    assert_eq!(
+        matching_names(r#"reason has FILE_DELETE and name ~ "\.exe$" and time > 2026-03-01 or parent = 5"#),
+        ["Temp", "payload.exe", "notes.txt"]);
+}
+
+#[test]
+fn test_fields() {
+    assert_eq!(matching_names("entry >= 201 and entry < 203"), ["payload.exe", "old.exe"]);
+    assert_eq!(matching_names("seq = 4"), ["old.exe"]);
+    assert_eq!(matching_names("usn = 0"), ["Temp"]);
+    assert_eq!(matching_names("sourceinfo != 0x0").len(), 0);
+    assert_eq!(matching_names("parent = 100-1").len(), 3);
+    assert_eq!(matching_names("parent != 100").len(), 2);
+    assert_eq!(matching_names("time = \"2026-03-02 09:00\""), ["payload.exe"]);
+    assert_eq!(matching_names("time >= 2026-03-02T08:30:00+00:00"), ["payload.exe"]);
+    assert_eq!(matching_names("reason = CLOSE"), ["Temp"]);
+    assert_eq!(matching_names("reason has file_create+close"), ["dropper.EXE"]);
+    assert_eq!(matching_names("attributes has DIRECTORY"), ["Temp"]);
+    assert_eq!(matching_names("attributes = ARCHIVE,HIDDEN"), ["notes.txt"]);
+    assert_eq!(matching_names("name = DROPPER.exe"), ["dropper.EXE"]);
+    assert_eq!(matching_names("name like '*.exe' and name !~ ^old"), ["dropper.EXE", "payload.exe"]);
+    assert_eq!(matching_names("path = /Temp/old.exe"), ["old.exe"]);
+    assert_eq!(matching_names("path like \"/temp/*\"").len(), 3);
+}
+
+#[test]
+fn test_precedence() {
+    assert_eq!(matching_names("name = Temp or name = notes.txt and seq = 4"), ["Temp"]);
+    assert_eq!(matching_names("(name = Temp or name = notes.txt) and seq = 1"), ["Temp", "notes.txt"]);
+    assert_eq!(matching_names("not reason has CLOSE"), ["notes.txt"]);
+    assert_eq!(matching_names("NOT (parent = 100 OR parent = 5)").len(), 0);
+}
+
+#[test]
+fn test_timezone() {
+    let records = records();
+    let timezone = "+02:00".parse().unwrap();
+    let query = Query::parse_in_timezone("time = \"2026-03-02 11:00\"", timezone).unwrap();
+    assert_eq!(records.iter().filter(|r| query.matches(r)).map(|r| r.data.filename()).collect::<Vec<_>>(), ["payload.exe"]);
+
+    /* timestamps with offset do not depend on the timezone */
+    let query = Query::parse_in_timezone("time = 2026-03-02T09:00:00Z", timezone).unwrap();
+    assert_eq!(records.iter().filter(|r| query.matches(r)).count(), 1);
+}
+
+#[test]
+fn test_uses_path() {
+    assert!(Query::parse("name = a or not path ~ b").unwrap().uses_path());
+    assert!(!Query::parse("name = a").unwrap().uses_path());
+}
+
+#[test]
+fn test_errors() {
+    let error = |query: &str| Query::parse(query).err().unwrap();
+    assert_eq!(error("size > 5").position, 0);
+    assert_eq!(error("usn > abc").position, 6);
+    assert_eq!(error("usn ~ 5").position, 4);
+    assert_eq!(error("name = a and").position, 12);
+    assert_eq!(error("(name = a").message, "expected ')'");
+    assert_eq!(error("name = \"a").message, "unterminated string");
+    assert_eq!(error("name = a b").message, "unexpected 'b'");
+    assert_eq!(error("reason has FOO").message, "invalid reason: 'FOO'");
+    assert_eq!(error("name ~ \"(\"").position, 7);
+    assert_eq!(error("time > yesterday").message, "invalid timestamp: 'yesterday'");
+    assert_eq!(error("usn > abc").to_string(), "invalid query at position 6: invalid number: 'abc'");
+}
@@ -0,0 +1,115 @@
+use chrono::{Duration, TimeZone, Utc};
+use usnjrnl::{UsrJrnlIterator, UsnReasonValue::*};
+use usnjrnl::analysis::{Analyzer, RansomwareAnalyzer, RansomwareIndicator, RansomwareReport, RansomwareSettings};
+
+mod common;
+use common::*;
+
+const DIRECTORIES: u64 = 5;
+const FILES_PER_DIRECTORY: u64 = 6;
+
+/// creates the directories 100 to 104, which contain some harmless activity
+fn journal_with_directories() -> Journal {
+    let midnight = Utc.with_ymd_and_hms(2022, 11, 18, 0, 0, 0).unwrap();
+    let mut journal = Journal::default();
+    for directory in 0..DIRECTORIES {
+        journal.push(TestRecord::new(100 + directory, 5, &format!("folder{}", directory), &[USN_REASON_CLOSE]).with_attributes(0x10).at(midnight));
+    }
+    journal.push(TestRecord::new(900, 100, "notes.txt", &[USN_REASON_DATA_EXTEND, USN_REASON_CLOSE]).at(midnight));
+    journal
+}
+
+fn analyze(journal: &Journal) -> RansomwareReport {
+    let mut analyzer = RansomwareAnalyzer::new(RansomwareSettings::default());
+    for record in UsrJrnlIterator::from(journal.cursor()) {
+        analyzer.add(&record.unwrap());
+    }
+    analyzer.finish()
+}
+
+#[test]
+fn test_encryption_in_place() {
+    let start = Utc.with_ymd_and_hms(2022, 11, 18, 2, 13, 0).unwrap();
+    let mut journal = journal_with_directories();
+    for directory in 0..DIRECTORIES {
+        let parent = 100 + directory;
+        for file in 0..FILES_PER_DIRECTORY {
+            let entry = 1000 + directory * FILES_PER_DIRECTORY + file;
+            let at = start + Duration::seconds((entry - 1000) as i64);
+            let name = format!("document{}.docx", file);
+            let locked = format!("{}.locked", name);
+            journal.push(TestRecord::new(entry, parent, &name, &[USN_REASON_DATA_OVERWRITE]).at(at));
+            journal.push(TestRecord::new(entry, parent, &name, &[USN_REASON_DATA_OVERWRITE, USN_REASON_CLOSE]).at(at));
+            journal.push(TestRecord::new(entry, parent, &name, &[USN_REASON_RENAME_OLD_NAME]).at(at));
+            journal.push(TestRecord::new(entry, parent, &locked, &[USN_REASON_RENAME_NEW_NAME]).at(at));
+            journal.push(TestRecord::new(entry, parent, &locked, &[USN_REASON_RENAME_NEW_NAME, USN_REASON_CLOSE]).at(at));
+        }
+        journal.push(TestRecord::new(2000 + directory, parent, "HOW_TO_DECRYPT.txt", &[USN_REASON_FILE_CREATE, USN_REASON_DATA_EXTEND, USN_REASON_CLOSE]).at(start + Duration::seconds(40)));
+    }
+
+    let report = analyze(&journal);
+    assert!(report.is_suspicious());
+    assert_eq!(report.windows.len(), 1);
+    let window = &report.windows[0];
+    assert_eq!(window.score, 80);
+    assert_eq!(window.start, Utc.with_ymd_and_hms(2022, 11, 18, 2, 10, 0).unwrap());
+    assert_eq!(window.indicators, vec![
+        RansomwareIndicator::MassWrites { files: 30 },
+        RansomwareIndicator::CommonExtension { extension: "locked".to_owned(), files: 30 },
+        RansomwareIndicator::ManyDirectories { directories: 5 },
+        RansomwareIndicator::RansomNotes { names: vec!["HOW_TO_DECRYPT.txt".to_owned()] },
+    ]);
+
+    assert_eq!(report.suspected_start, Some(start));
+    let first = report.first_affected.as_ref().unwrap();
+    assert_eq!((first.file_reference.entry, first.name.as_str()), (1000, "document0.docx"));
+    assert_eq!(report.new_extensions, vec![("locked".to_owned(), 30)]);
+    assert_eq!(report.affected_extensions, vec![("docx".to_owned(), 30)]);
+    assert_eq!(report.directories.len(), 5);
+    assert_eq!(report.directories[0].path.as_deref(), Some("/folder0"));
+    assert_eq!(report.directories[0].files, 6);
+    assert_eq!(report.notes, vec!["HOW_TO_DECRYPT.txt"]);
+}
+
+#[test]
+fn test_replaced_files() {
+    let start = Utc.with_ymd_and_hms(2022, 11, 18, 2, 0, 0).unwrap();
+    let mut journal = journal_with_directories();
+    for directory in 0..DIRECTORIES {
+        let parent = 100 + directory;
+        for file in 0..FILES_PER_DIRECTORY {
+            let entry = 1000 + directory * FILES_PER_DIRECTORY + file;
+            let name = format!("photo{}.jpg", file);
+            journal.push(TestRecord::new(entry + 1000, parent, &format!("{}.crypt", name), &[USN_REASON_FILE_CREATE, USN_REASON_DATA_EXTEND, USN_REASON_CLOSE]).at(start));
+            journal.push(TestRecord::new(entry, parent, &name, &[USN_REASON_FILE_DELETE, USN_REASON_CLOSE]).at(start));
+        }
+    }
+
+    let report = analyze(&journal);
+    assert_eq!(report.windows.len(), 1);
+    assert_eq!(report.windows[0].indicators, vec![
+        RansomwareIndicator::CommonExtension { extension: "crypt".to_owned(), files: 30 },
+        RansomwareIndicator::ReplacedFiles { files: 30 },
+        RansomwareIndicator::ManyDirectories { directories: 5 },
+    ]);
+    assert_eq!(report.windows[0].score, 70);
+    assert_eq!(report.first_affected.as_ref().unwrap().name, "photo0.jpg.crypt");
+    assert_eq!(report.new_extensions, vec![("crypt".to_owned(), 30)]);
+    assert_eq!(report.affected_extensions, vec![("jpg".to_owned(), 30)]);
+}
+
+#[test]
+fn test_spread_over_windows() {
+    /* the same number of renames, but one file every minute */
+    let start = Utc.with_ymd_and_hms(2022, 11, 18, 2, 0, 0).unwrap();
+    let mut journal = journal_with_directories();
+    for file in 0..30 {
+        let at = start + Duration::minutes(file);
+        let name = format!("document{}.docx", file);
+        journal.push(TestRecord::new(1000 + file as u64, 100, &name, &[USN_REASON_RENAME_OLD_NAME]).at(at));
+        journal.push(TestRecord::new(1000 + file as u64, 100, &format!("{}.locked", name), &[USN_REASON_RENAME_NEW_NAME, USN_REASON_CLOSE]).at(at));
+    }
+
+    assert!(!analyze(&journal).is_suspicious());
+    assert!(!analyze(&journal_with_directories()).is_suspicious());
+}
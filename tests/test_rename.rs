@@ -0,0 +1,78 @@
+use usnjrnl::{RenameChains, Renames, UsnReaderError, UsrJrnlIterator, UsnReasonValue::*};
+
+mod common;
+use common::*;
+
+fn journal() -> Journal {
+    Journal::with_records(vec![
+        /* the journal starts after the old name of this file has been written */
+        TestRecord::new(200, 100, "report.docx", &[USN_REASON_RENAME_NEW_NAME]),
+        TestRecord::new(200, 100, "report.docx", &[USN_REASON_RENAME_NEW_NAME, USN_REASON_CLOSE]),
+
+        TestRecord::new(300, 100, "invoice.pdf", &[USN_REASON_FILE_CREATE, USN_REASON_CLOSE]),
+        TestRecord::new(300, 100, "invoice.pdf", &[USN_REASON_RENAME_OLD_NAME]),
+        TestRecord::new(301, 100, "tmp123.dat", &[USN_REASON_RENAME_OLD_NAME]),
+        TestRecord::new(300, 100, "invoice.pdf.locked", &[USN_REASON_RENAME_NEW_NAME]),
+        TestRecord::new(301, 5, "svchost.exe", &[USN_REASON_RENAME_NEW_NAME]),
+        TestRecord::new(300, 100, "invoice.pdf.locked", &[USN_REASON_RENAME_NEW_NAME, USN_REASON_CLOSE]),
+
+        /* two renames while the file is open */
+        TestRecord::new(301, 5, "svchost.exe", &[USN_REASON_RENAME_NEW_NAME, USN_REASON_RENAME_OLD_NAME]),
+        TestRecord::new(301, 5, "svch0st.exe", &[USN_REASON_RENAME_NEW_NAME]),
+        TestRecord::new(301, 5, "svch0st.exe", &[USN_REASON_RENAME_NEW_NAME, USN_REASON_CLOSE]),
+    ])
+}
+
+fn renames() -> Vec<usnjrnl::Rename> {
+    Renames::new(UsrJrnlIterator::from(journal().cursor()))
+        .collect::<Result<_, _>>()
+        .unwrap()
+}
+
+#[test]
+fn test_renames() {
+    let renames = renames();
+    let pairs: Vec<_> = renames.iter().map(|r| r.to_string()).collect();
+    assert_eq!(pairs, vec![
+        "? -> report.docx",
+        "invoice.pdf -> invoice.pdf.locked",
+        "tmp123.dat -> svchost.exe",
+        "svchost.exe -> svch0st.exe",
+    ]);
+
+    assert_eq!(renames[0].old_parent, None);
+    assert!(!renames[0].is_move());
+    assert!(!renames[1].is_move());
+    assert_eq!(renames[2].old_parent.unwrap().entry, 100);
+    assert_eq!(renames[2].new_parent.entry, 5);
+    assert!(renames[2].is_move());
+    assert!(renames.windows(2).all(|w| w[0].usn < w[1].usn));
+}
+
+#[test]
+fn test_rename_chains() {
+    let chains: RenameChains = renames().into_iter().collect();
+    assert_eq!(chains.len(), 3);
+    let chains: Vec<_> = chains.iter().map(|c| c.to_string()).collect();
+    assert_eq!(chains, vec![
+        "? -> report.docx",
+        "invoice.pdf -> invoice.pdf.locked",
+        "tmp123.dat -> svchost.exe -> svch0st.exe",
+    ]);
+
+    let chains: RenameChains = renames().into_iter().collect();
+    let dropper = chains.get(&renames()[2].file_reference).unwrap();
+    assert_eq!(dropper.names(), vec!["tmp123.dat", "svchost.exe", "svch0st.exe"]);
+    assert_eq!(dropper.first_name(), "tmp123.dat");
+    assert_eq!(dropper.last_name(), "svch0st.exe");
+}
+
+#[test]
+fn test_renames_pass_errors() {
+    let records = vec![
+        Err(UsnReaderError::NoMoreData),
+    ];
+    let mut renames = Renames::new(records.into_iter().chain(UsrJrnlIterator::from(journal().cursor())));
+    assert!(matches!(renames.next(), Some(Err(UsnReaderError::NoMoreData))));
+    assert_eq!(renames.count(), 4);
+}
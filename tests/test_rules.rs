@@ -0,0 +1,126 @@
+use chrono::{Duration, TimeZone, Utc};
+use usnjrnl::{UsrJrnlIterator, UsnReasonValue::*};
+use usnjrnl::rules::{GroupBy, Rule, RuleAlert, RuleAlerts, RuleEngine};
+
+mod common;
+use common::*;
+
+fn evaluate(journal: &Journal, rules: &str) -> Vec<RuleAlert> {
+    let rules = Rule::from_yaml(rules).unwrap();
+    RuleAlerts::new(RuleEngine::new(rules), UsrJrnlIterator::from(journal.cursor()))
+        .collect::<Result<_, _>>()
+        .unwrap()
+}
+
+const RULES: &str = r#"
+id: script-in-temp
+title: script dropped into a temporary directory
+level: high
+detection:
+  reason: FILE_CREATE
+  name: ["*.ps1", "*.vbs"]
+  path|startswith: '\Users\'
+exclude:
+  name|re: "^__PSScriptPolicyTest_"
+---
+- id: hidden-executable
+  detection:
+    reason|all: [usn_reason_file_create, CLOSE]
+    attributes: HIDDEN
+    name|re: \.(exe|dll)$
+- id: night-shift
+  detection:
+    reason: DATA_OVERWRITE
+    time|between: ["22:00", "06:00"]
+"#;
+
+#[test]
+fn test_rules() {
+    let at = |hour, minute| Utc.with_ymd_and_hms(2022, 11, 18, hour, minute, 0).unwrap();
+    let mut journal = Journal::with_records(vec![
+        TestRecord::new(100, 5, "Users", &[USN_REASON_CLOSE]).with_attributes(0x10),
+        TestRecord::new(200, 100, "bob", &[USN_REASON_CLOSE]).with_attributes(0x10),
+        TestRecord::new(210, 200, "Temp", &[USN_REASON_CLOSE]).with_attributes(0x10),
+        TestRecord::new(220, 5, "Scripts", &[USN_REASON_CLOSE]).with_attributes(0x10),
+    ]);
+    let script = journal.push(TestRecord::new(300, 210, "run.PS1", &[USN_REASON_FILE_CREATE]));
+    let closed = journal.push(TestRecord::new(300, 210, "run.PS1", &[USN_REASON_FILE_CREATE, USN_REASON_CLOSE]));
+    journal.push(TestRecord::new(301, 210, "__PSScriptPolicyTest_abc.ps1", &[USN_REASON_FILE_CREATE]));
+    journal.push(TestRecord::new(302, 220, "backup.ps1", &[USN_REASON_FILE_CREATE]));
+    journal.push(TestRecord::new(303, 999, "orphan.vbs", &[USN_REASON_FILE_CREATE]));
+
+    journal.push(TestRecord::new(304, 210, "svc.exe", &[USN_REASON_FILE_CREATE]).with_attributes(0x02));
+    let hidden = journal.push(TestRecord::new(304, 210, "svc.exe", &[USN_REASON_FILE_CREATE, USN_REASON_CLOSE]).with_attributes(0x02));
+    journal.push(TestRecord::new(305, 210, "visible.exe", &[USN_REASON_FILE_CREATE, USN_REASON_CLOSE]));
+
+    journal.push(TestRecord::new(306, 210, "day.txt", &[USN_REASON_DATA_OVERWRITE, USN_REASON_CLOSE]).at(at(12, 0)));
+    let late = journal.push(TestRecord::new(307, 210, "late.txt", &[USN_REASON_DATA_OVERWRITE, USN_REASON_CLOSE]).at(at(23, 30)));
+    let early = journal.push(TestRecord::new(308, 210, "early.txt", &[USN_REASON_DATA_OVERWRITE, USN_REASON_CLOSE]).at(at(5, 59)));
+    journal.push(TestRecord::new(309, 210, "morning.txt", &[USN_REASON_DATA_OVERWRITE, USN_REASON_CLOSE]).at(at(6, 0)));
+
+    let alerts = evaluate(&journal, RULES);
+    let summary: Vec<_> = alerts.iter()
+        .map(|a| (a.rule_id.as_str(), a.records.iter().map(|r| r.usn).collect::<Vec<_>>()))
+        .collect();
+    assert_eq!(summary, vec![
+        ("script-in-temp", vec![script]),
+        ("script-in-temp", vec![closed]),
+        ("hidden-executable", vec![hidden]),
+        ("night-shift", vec![late]),
+        ("night-shift", vec![early]),
+    ]);
+    assert_eq!(alerts[0].title.as_deref(), Some("script dropped into a temporary directory"));
+    assert_eq!(alerts[0].level.as_deref(), Some("high"));
+    assert_eq!(alerts[0].records[0].name, "run.PS1");
+}
+
+#[test]
+fn test_correlation() {
+    let start = Utc.with_ymd_and_hms(2022, 11, 18, 12, 0, 0).unwrap();
+    let rules = Rule::from_yaml("
+id: mass-delete
+detection:
+  reason: FILE_DELETE
+correlation:
+  count: 3
+  within: 10
+  group_by: parent
+").unwrap();
+    let correlation = rules[0].correlation.unwrap();
+    assert_eq!(correlation.group_by, GroupBy::Parent);
+    assert_eq!(correlation.within, Duration::seconds(10));
+
+    let mut journal = Journal::default();
+    let mut deleted = Vec::new();
+    /* two deletions in each directory, then one more in the first directory, which is too late */
+    for (entry, parent, seconds) in [(300, 100, 0), (301, 101, 1), (302, 100, 2), (303, 101, 3), (304, 100, 15)] {
+        deleted.push(journal.push(TestRecord::new(entry, parent, "file.txt", &[USN_REASON_FILE_DELETE, USN_REASON_CLOSE])
+            .at(start + Duration::seconds(seconds))));
+    }
+    /* enough deletions within 10 seconds */
+    for (entry, seconds) in [(305, 20), (306, 22)] {
+        deleted.push(journal.push(TestRecord::new(entry, 100, "file.txt", &[USN_REASON_FILE_DELETE, USN_REASON_CLOSE])
+            .at(start + Duration::seconds(seconds))));
+    }
+    journal.push(TestRecord::new(307, 100, "file.txt", &[USN_REASON_FILE_DELETE, USN_REASON_CLOSE]).at(start + Duration::seconds(23)));
+
+    let mut engine = RuleEngine::new(rules);
+    let alerts: Vec<_> = UsrJrnlIterator::from(journal.cursor())
+        .flat_map(|record| engine.process(&record.unwrap()))
+        .collect();
+    assert_eq!(alerts.len(), 1);
+    assert_eq!(alerts[0].records.iter().map(|r| r.usn).collect::<Vec<_>>(), vec![deleted[4], deleted[5], deleted[6]]);
+    assert_eq!(alerts[0].timestamp, start + Duration::seconds(22));
+}
+
+#[test]
+fn test_invalid_rules() {
+    let error = |text: &str| Rule::from_yaml(text).err().unwrap();
+    assert!(error("id: a\ndetection:\n  reason: NO_SUCH_REASON\n").contains("invalid rule 'a': invalid reason: 'NO_SUCH_REASON'"));
+    assert!(error("id: a\ndetection:\n  name|re: '('\n").contains("invalid regular expression"));
+    assert!(error("id: a\ndetection:\n  size: 12\n").contains("unknown field `size`"));
+    assert!(error("id: a\ndetection:\n  time|between: [night, day]\n").contains("invalid time of day: 'night'"));
+    assert!(error("id: a\ndetection: {}\ncorrelation: {count: 2, within: 1, group_by: volume}\n").contains("invalid group_by: 'volume'"));
+    assert_eq!(error("id: a\ndetection: {}\n---\nid: a\ndetection: {}\n"), "duplicate rule id: 'a'");
+    assert!(Rule::from_yaml("").unwrap().is_empty());
+}
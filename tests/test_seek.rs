@@ -0,0 +1,130 @@
+use std::cell::Cell;
+use std::io::{Cursor, Read, Seek, SeekFrom};
+use std::rc::Rc;
+use chrono::{Duration, TimeZone, Utc};
+use usnjrnl::{UsnJrnlReader, UsnReaderError, UsrJrnlIterator, UsnReasonValue::*};
+
+mod common;
+use common::*;
+
+/// creates a journal with one record per minute, which spans multiple pages
+fn timeline(records: i64) -> (Journal, Vec<i64>) {
+    let start = Utc.with_ymd_and_hms(2022, 11, 18, 12, 0, 0).unwrap();
+    let mut journal = Journal::with_empty_pages(3);
+    let usns = (0..records).map(|i| {
+        journal.push(TestRecord::new(100 + i as u64, 5, &format!("file{}.txt", i), &[USN_REASON_DATA_EXTEND])
+            .at(start + Duration::minutes(i)))
+    }).collect();
+    (journal, usns)
+}
+
+#[test]
+fn test_seek_to_time() {
+    let (journal, usns) = timeline(300);
+    let start = Utc.with_ymd_and_hms(2022, 11, 18, 12, 0, 0).unwrap();
+
+    let mut iter = UsrJrnlIterator::from(journal.cursor());
+    assert_eq!(iter.seek_to_time(&(start - Duration::days(1))).unwrap(), 0x3000);
+    assert_eq!(iter.next().unwrap().unwrap().data.filename(), "file0.txt");
+
+    for minute in [1, 50, 51, 52, 150, 299] {
+        let offset = iter.seek_to_time(&(start + Duration::minutes(minute))).unwrap();
+        assert_eq!(offset as i64, usns[minute as usize]);
+        assert_eq!(iter.next().unwrap().unwrap().data.usn(), usns[minute as usize]);
+    }
+
+    /* timestamps between two records lead to the later one */
+    iter.seek_to_time(&(start + Duration::seconds(100 * 60 + 30))).unwrap();
+    assert_eq!(iter.next().unwrap().unwrap().data.filename(), "file101.txt");
+
+    iter.seek_to_time(&(start + Duration::days(1))).unwrap();
+    assert!(iter.next().is_none());
+}
+
+/// counts the bytes which are read from a journal
+struct CountingReader {
+    data: Cursor<Vec<u8>>,
+    bytes_read: Rc<Cell<u64>>,
+}
+
+impl Read for CountingReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let bytes = self.data.read(buf)?;
+        self.bytes_read.set(self.bytes_read.get() + bytes as u64);
+        Ok(bytes)
+    }
+}
+
+impl Seek for CountingReader {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.data.seek(pos)
+    }
+}
+
+#[test]
+fn test_seek_over_sparse_beginning() {
+    /* the beginning of a real journal consists of many empty pages */
+    let start = Utc.with_ymd_and_hms(2022, 11, 18, 12, 0, 0).unwrap();
+    let mut journal = Journal::with_empty_pages(0x2000);
+    let usns: Vec<_> = (0..300).map(|i| {
+        journal.push(TestRecord::new(100 + i as u64, 5, &format!("file{}.txt", i), &[USN_REASON_DATA_EXTEND])
+            .at(start + Duration::minutes(i)))
+    }).collect();
+
+    let bytes_read = Rc::new(Cell::new(0));
+    let mut iter = UsrJrnlIterator::from(CountingReader { data: journal.cursor(), bytes_read: bytes_read.clone() });
+    assert_eq!(iter.seek_to_time(&(start + Duration::minutes(200))).unwrap() as i64, usns[200]);
+    assert_eq!(iter.seek_to_time(&(start - Duration::minutes(1))).unwrap() as i64, usns[0]);
+
+    /* only a few pages have been read, not the empty pages one by one */
+    assert!(bytes_read.get() < 0x40 * 0x1000, "{} bytes have been read", bytes_read.get());
+}
+
+#[test]
+fn test_seek_in_file() {
+    let (journal, usns) = timeline(100);
+    let path = std::env::temp_dir().join(format!("usnjrnl_seek_{}.bin", std::process::id()));
+
+    /* the journal ends directly after the last record, without padding */
+    std::fs::write(&path, journal.bytes()).unwrap();
+    let reader = UsnJrnlReader::from(&path).unwrap();
+    assert!(reader.is_seekable());
+
+    let mut iter = reader.into_iter();
+    let start = Utc.with_ymd_and_hms(2022, 11, 18, 13, 0, 0).unwrap();
+    assert_eq!(iter.seek_to_time(&start).unwrap() as i64, usns[60]);
+    let names: Vec<_> = iter.map(|r| r.unwrap().data.filename().to_owned()).collect();
+    assert_eq!(names.len(), 40);
+    assert_eq!(names.last().unwrap(), "file99.txt");
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_seek_to_usn() {
+    let (journal, usns) = timeline(100);
+    let mut iter = UsrJrnlIterator::from(journal.cursor());
+    iter.seek_to_usn(usns[70]).unwrap();
+    assert_eq!(iter.next().unwrap().unwrap().data.filename(), "file70.txt");
+    iter.seek_to_usn(usns[0]).unwrap();
+    assert_eq!(iter.next().unwrap().unwrap().data.filename(), "file0.txt");
+
+    /* offsets which are not the beginning of a record */
+    for usn in [usns[70] + 8, 0x1000, usns[99] + 0x1000, -1] {
+        assert!(matches!(iter.seek_to_usn(usn), Err(UsnReaderError::UsnNotFound(u)) if u == usn));
+    }
+}
+
+#[test]
+fn test_seek_to_usn_in_excerpt() {
+    /* this sample starts at USN 92274688, but at offset 0 */
+    let reader = UsnJrnlReader::from(&get_sample_file("sample1.bin")).unwrap();
+    let mut iter = reader.into_iter();
+    iter.seek_to_usn(92282448).unwrap();
+    let record = iter.next().unwrap().unwrap();
+    assert_eq!(record.data.usn(), 92282448);
+    assert_eq!(record.data.filename(), "1337787f0652b945a4c25c1f21b6800b.tmp");
+    assert_eq!(iter.count(), 53);
+
+    let mut iter = UsnJrnlReader::from(&get_sample_file("sample1.bin")).unwrap().into_iter();
+    assert!(iter.seek_to_usn(92274688 + 4).is_err());
+}
@@ -0,0 +1,29 @@
+use usnjrnl::{FileAttributes, FileAttributeValue, UsnReason, UsnReasonClass, UsnReasonValue, UsnReasonValue::*};
+
+#[test]
+fn test_reason_classes() {
+    let reason = UsnReason::from(
+        USN_REASON_CLOSE as u32 | USN_REASON_DATA_EXTEND as u32 | USN_REASON_FILE_CREATE as u32);
+    assert_eq!(reason.to_string(), "CLOSE+DATA_EXTEND+FILE_CREATE");
+    assert_eq!(reason.classes(), vec![UsnReasonClass::Create, UsnReasonClass::Data, UsnReasonClass::Close]);
+    assert_eq!(reason.primary_class(), Some(UsnReasonClass::Create));
+    assert_eq!(UsnReason::from(0).primary_class(), None);
+    assert_eq!("Data".parse::<UsnReasonClass>(), Ok(UsnReasonClass::Data));
+    assert!("write".parse::<UsnReasonClass>().is_err());
+}
+
+#[test]
+fn test_file_attributes() {
+    let attributes = FileAttributes::from(0x2012);
+    assert_eq!(attributes.to_string(), "HIDDEN+DIRECTORY+NOT_CONTENT_INDEXED");
+    assert_eq!(format!("{:+}", attributes), "FILE_ATTRIBUTE_HIDDEN+FILE_ATTRIBUTE_DIRECTORY+FILE_ATTRIBUTE_NOT_CONTENT_INDEXED");
+}
+
+#[test]
+fn test_parse_flags() {
+    assert_eq!("FILE_DELETE".parse::<UsnReasonValue>(), Ok(USN_REASON_FILE_DELETE));
+    assert_eq!("usn_reason_close".parse::<UsnReasonValue>(), Ok(USN_REASON_CLOSE));
+    assert!("DELETE".parse::<UsnReasonValue>().is_err());
+    assert_eq!("hidden".parse::<FileAttributeValue>(), Ok(FileAttributeValue::FILE_ATTRIBUTE_HIDDEN));
+    assert_eq!("FILE_ATTRIBUTE_DIRECTORY".parse::<FileAttributeValue>(), Ok(FileAttributeValue::FILE_ATTRIBUTE_DIRECTORY));
+}
@@ -0,0 +1,41 @@
+use std::convert::TryInto;
+use std::io::Cursor;
+use usnjrnl::{UsrJrnlIterator, UsnReasonValue::*};
+
+mod common;
+use common::*;
+
+#[test]
+fn test_file_name_offset() {
+    let journal = Journal::with_records(vec![
+        TestRecord::new(300, 5, "report.docx", &[USN_REASON_FILE_CREATE]),
+    ]);
+    let record = journal.bytes();
+    let length = u32::from_le_bytes(record[0..4].try_into().unwrap()) as usize;
+
+    /* move the name 8 bytes further back, as a future version of the record might do */
+    let mut data = Vec::new();
+    data.extend_from_slice(&((length + 8) as u32).to_le_bytes());
+    data.extend_from_slice(&record[4..58]);
+    data.extend_from_slice(&68u16.to_le_bytes());
+    data.extend_from_slice(&[0xff; 8]);
+    data.extend_from_slice(&record[60..length]);
+
+    let mut records = UsrJrnlIterator::from(Cursor::new(data));
+    let record = records.next().unwrap().unwrap();
+    assert_eq!(record.data.filename(), "report.docx");
+    assert!(records.next().is_none());
+}
+
+#[test]
+fn test_invalid_file_name_offset() {
+    let journal = Journal::with_records(vec![
+        TestRecord::new(300, 5, "report.docx", &[USN_REASON_FILE_CREATE]),
+    ]);
+    let mut data = journal.bytes().to_vec();
+
+    /* the name cannot start within the fixed size fields */
+    data[58..60].copy_from_slice(&40u16.to_le_bytes());
+    let error = UsrJrnlIterator::from(Cursor::new(data)).next().unwrap().unwrap_err();
+    assert_eq!(error.to_string(), "Syntax Error: invalid value for FileNameOffset: 40");
+}
@@ -0,0 +1,522 @@
+use std::io::{BufRead, BufReader};
+use std::net::{TcpListener, UdpSocket};
+use std::process::Command;
+use std::thread;
+use chrono::{Duration, TimeZone, Utc};
+use usnjrnl::UsnReasonValue::*;
+
+mod common;
+use common::*;
+
+fn usnjrnl_dump(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_usnjrnl_dump"))
+        .args(args)
+        .output()
+        .expect("unable to run usnjrnl_dump");
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_l2tcsv() {
+    let sample = get_sample_file("sample1.bin");
+    let output = usnjrnl_dump(&["-F", "l2tcsv", "--host-name", "WS01", "--timezone", "+02:00", sample.to_str().unwrap()]);
+    let mut lines = output.lines();
+    assert!(lines.next().unwrap().starts_with("date,time,timezone,MACB,"));
+    let first = lines.next().unwrap();
+    assert_eq!(first.split(',').count(), 17);
+    assert!(first.starts_with("07/03/2018,16:06:24,+02:00,..C.,FILE,"));
+    assert!(lines.all(|l| l.split(',').count() == 17));
+}
+
+#[test]
+fn test_tln() {
+    let sample = get_sample_file("sample1.bin");
+    let output = usnjrnl_dump(&["-F", "tln", "--host-name", "WS01", sample.to_str().unwrap()]);
+    for line in output.lines() {
+        let fields: Vec<_> = line.split('|').collect();
+        assert_eq!(fields.len(), 5);
+        assert_eq!(fields[1], "USN");
+        assert_eq!(fields[2], "WS01");
+    }
+    assert!(output.starts_with("1530626784|"));
+}
+
+#[test]
+fn test_bodyfile_macb() {
+    let journal = Journal::with_records(vec![
+        TestRecord::new(100, 5, "Temp", &[USN_REASON_CLOSE]),
+        TestRecord::new(300, 100, "a.exe", &[USN_REASON_FILE_CREATE]),
+        TestRecord::new(300, 100, "a.exe", &[USN_REASON_FILE_CREATE, USN_REASON_DATA_EXTEND]),
+        TestRecord::new(300, 100, "a.exe", &[USN_REASON_FILE_CREATE, USN_REASON_DATA_EXTEND, USN_REASON_CLOSE]),
+    ]);
+    let journal_file = journal.write_to_temp_file("bodyfile_macb");
+    let output = usnjrnl_dump(&["-F", "bodyfile-macb", "-r", journal_file.to_str().unwrap()]);
+    let lines: Vec<_> = output.lines().collect();
+    assert_eq!(lines, vec![
+        "0|/Temp/a.exe ($UsnJrnl: FILE_CREATE)|300-1||0|0|0|-1|-1|-1|1668772800",
+        "0|/Temp/a.exe ($UsnJrnl: DATA_EXTEND)|300-1||0|0|0|-1|1668772800|-1|-1",
+    ]);
+}
+
+#[test]
+fn test_table() {
+    let sample = get_sample_file("sample1.bin");
+    let output = usnjrnl_dump(&["-F", "table", "--width", "100", "--color", "never", sample.to_str().unwrap()]);
+    let mut lines = output.lines();
+    assert!(lines.next().unwrap().starts_with("Time (UTC) "));
+    for line in lines {
+        assert!(line.chars().count() <= 100);
+        assert!(line.starts_with("2018-07-03 14:06:"));
+    }
+}
+
+#[test]
+fn test_cef_over_tcp() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let destination = format!("tcp://{}", listener.local_addr().unwrap());
+    let receiver = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        BufReader::new(stream).lines().map(|l| l.unwrap()).collect::<Vec<_>>()
+    });
+
+    let sample = get_sample_file("sample1.bin");
+    let output = usnjrnl_dump(&["-F", "cef", "-o", &destination, sample.to_str().unwrap()]);
+    assert!(output.is_empty());
+
+    let lines = receiver.join().unwrap();
+    assert!(!lines.is_empty());
+    assert!(lines.iter().all(|l| l.starts_with("CEF:0|usnjrnl|usnjrnl_dump|")));
+    assert!(lines[1].contains("|create|File created|3|rt=1530626784720 fname=3b81550ce37be64298706e19ebaf66bf.tmp "));
+}
+
+#[test]
+fn test_syslog_over_udp() {
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let destination = format!("udp://{}", socket.local_addr().unwrap());
+
+    let sample = get_sample_file("sample1.bin");
+    usnjrnl_dump(&["-F", "syslog", "--host-name", "WS01", "-o", &destination, sample.to_str().unwrap()]);
+
+    let mut buffer = [0; 4096];
+    let length = socket.recv(&mut buffer).unwrap();
+    let message = std::str::from_utf8(&buffer[..length]).unwrap();
+    assert!(message.starts_with("<134>1 2018-07-03T14:06:24.720695Z WS01 usnjrnl_dump - USN [usnjrnl@32473 usn=\"92274688\""));
+    assert!(!message.ends_with('\n'));
+}
+
+#[test]
+fn test_html_report() {
+    let journal = Journal::with_records(vec![
+        TestRecord::new(100, 5, "Temp", &[USN_REASON_CLOSE]),
+        TestRecord::new(300, 100, "payload.exe", &[USN_REASON_FILE_CREATE]),
+        TestRecord::new(300, 100, "payload.exe", &[USN_REASON_FILE_CREATE, USN_REASON_CLOSE]),
+        TestRecord::new(301, 100, "<evil>.txt", &[USN_REASON_FILE_DELETE, USN_REASON_CLOSE]),
+        TestRecord::new(302, 100, "invoice.pdf", &[USN_REASON_RENAME_OLD_NAME]),
+        TestRecord::new(302, 100, "invoice.pdf.exe", &[USN_REASON_RENAME_NEW_NAME]),
+        TestRecord::new(302, 100, "invoice.pdf.exe", &[USN_REASON_RENAME_NEW_NAME, USN_REASON_CLOSE]),
+        /* renamed before it was closed for the first time, so this is a created executable */
+        TestRecord::new(303, 100, "setup.tmp", &[USN_REASON_FILE_CREATE]),
+        TestRecord::new(303, 100, "setup.tmp", &[USN_REASON_FILE_CREATE, USN_REASON_RENAME_OLD_NAME]),
+        TestRecord::new(303, 100, "setup.exe", &[USN_REASON_FILE_CREATE, USN_REASON_RENAME_NEW_NAME]),
+        TestRecord::new(303, 100, "setup.exe", &[USN_REASON_FILE_CREATE, USN_REASON_RENAME_NEW_NAME, USN_REASON_CLOSE]),
+    ]);
+    let journal_file = journal.write_to_temp_file("html_report");
+    let mut report_file = std::env::temp_dir();
+    report_file.push(format!("usnjrnl_report_{}.html", std::process::id()));
+
+    usnjrnl_dump(&["report", "--html", report_file.to_str().unwrap(), journal_file.to_str().unwrap()]);
+    let report = std::fs::read_to_string(&report_file).unwrap();
+    assert!(report.contains("<tr><th>records</th><td>11</td></tr>"));
+    assert!(report.contains("<h2>Deletions (1)</h2>"));
+    assert!(report.contains("<td>/Temp/&lt;evil&gt;.txt</td>"));
+    assert!(report.contains("<h2>Renames (2)</h2>"));
+    assert!(report.contains("<h2>New executables (3)</h2>"));
+    assert!(report.contains("<td>/Temp/payload.exe</td>"));
+    assert!(report.contains("<td>/Temp/invoice.pdf \u{2192} /Temp/invoice.pdf.exe</td>"));
+    assert!(report.contains("<td>/Temp/setup.exe</td>"));
+    assert!(!report.contains("only the first"));
+
+    usnjrnl_dump(&["report", "--max-records", "3", "--html", report_file.to_str().unwrap(), journal_file.to_str().unwrap()]);
+    let report = std::fs::read_to_string(&report_file).unwrap();
+    assert!(report.contains("only the first 3 of 11 records"));
+    assert!(report.contains("<h2>New executables (3)</h2>"));
+}
+
+#[test]
+fn test_time_range() {
+    let start = Utc.with_ymd_and_hms(2022, 11, 18, 14, 0, 0).unwrap();
+    let mut journal = Journal::default();
+    for minute in 0..120 {
+        journal.push(TestRecord::new(100 + minute, 5, &format!("file{}.txt", minute), &[USN_REASON_DATA_EXTEND])
+            .at(start + Duration::minutes(minute as i64)));
+    }
+    let path = journal.write_to_temp_file("time_range");
+
+    let output = usnjrnl_dump(&["-F", "tln", "--since", "2022-11-18 14:02", "--until", "2022-11-18T14:30:00Z", path.to_str().unwrap()]);
+    let lines: Vec<_> = output.lines().collect();
+    assert_eq!(lines.len(), 29);
+    assert!(lines[0].contains("|/file2.txt: "));
+    assert!(lines[28].contains("|/file30.txt: "));
+
+    /* timestamps without offset are interpreted in the display timezone */
+    let output = usnjrnl_dump(&["-F", "tln", "--timezone", "+02:00", "--since", "2022-11-18 16:10:00", path.to_str().unwrap()]);
+    assert_eq!(output.lines().count(), 110);
+
+    /* the same applies to times in the query */
+    let output = usnjrnl_dump(&["-F", "tln", "--timezone", "+02:00", "--where", "time >= \"2022-11-18 16:10\"", path.to_str().unwrap()]);
+    assert_eq!(output.lines().count(), 110);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_usn_range() {
+    let sample = get_sample_file("sample1.bin");
+    let output = usnjrnl_dump(&["-F", "tln", "--start-usn", "92274864", "--end-usn", "92282448", sample.to_str().unwrap()]);
+    let lines: Vec<_> = output.lines().collect();
+    assert_eq!(lines.len(), 50);
+    assert!(lines[0].ends_with("USN: 92274864)"));
+    assert!(lines[49].ends_with("USN: 92282448)"));
+
+    let status = Command::new(env!("CARGO_BIN_EXE_usnjrnl_dump"))
+        .args(["--start-usn", "92274865", sample.to_str().unwrap()])
+        .output()
+        .unwrap()
+        .status;
+    assert!(!status.success());
+}
+
+#[test]
+fn test_reason_and_attribute_filters() {
+    let sample = get_sample_file("sample1.bin");
+    let output = usnjrnl_dump(&["-F", "tln", "--reason", "FILE_DELETE,rename_old_name", "--exclude-reason", "CLOSE", sample.to_str().unwrap()]);
+    assert!(!output.is_empty());
+    assert!(output.lines().all(|l| (l.contains("FILE_DELETE") || l.contains("RENAME_OLD_NAME")) && !l.contains("CLOSE")));
+
+    let output = usnjrnl_dump(&["-F", "tln", "--reason", "FILE_CREATE,CLOSE", "--all-reasons", sample.to_str().unwrap()]);
+    assert!(!output.is_empty());
+    assert!(output.lines().all(|l| l.contains("FILE_CREATE") && l.contains("CLOSE")));
+
+    let output = usnjrnl_dump(&["-F", "json", "--attributes", "ARCHIVE,NOT_CONTENT_INDEXED", sample.to_str().unwrap()]);
+    assert_eq!(output.lines().count(), 81);
+}
+
+#[test]
+fn test_name_filters() {
+    let journal = Journal::with_records(vec![
+        TestRecord::new(200, 5, "Invoke-Mimikatz.PS1", &[USN_REASON_FILE_CREATE]),
+        TestRecord::new(201, 5, "report.docx.locked", &[USN_REASON_RENAME_NEW_NAME]),
+        TestRecord::new(202, 5, "notes.txt", &[USN_REASON_DATA_EXTEND]),
+        TestRecord::new(203, 5, "PsExeSvc.exe", &[USN_REASON_FILE_CREATE]),
+    ]);
+    let path = journal.write_to_temp_file("name_filters");
+    let path = path.to_str().unwrap();
+
+    let output = usnjrnl_dump(&["-F", "tln", "--name-glob", "*.ps1", path]);
+    assert_eq!(output.lines().count(), 1);
+    assert!(output.contains("Invoke-Mimikatz.PS1"));
+
+    let output = usnjrnl_dump(&["-F", "tln", "--name-regex", r"\.(locked|encrypted)$", path]);
+    assert_eq!(output.lines().count(), 1);
+    assert!(output.contains("report.docx.locked"));
+
+    let list = std::env::temp_dir().join(format!("usnjrnl_names_{}.txt", std::process::id()));
+    std::fs::write(&list, "# IOCs\npsexesvc.exe\nmimikatz*\n").unwrap();
+    let output = usnjrnl_dump(&["-F", "tln", "--name-list", list.to_str().unwrap(), path]);
+    assert_eq!(output.lines().count(), 1);
+    assert!(output.contains("PsExeSvc.exe"));
+    std::fs::remove_file(&list).unwrap();
+}
+
+#[test]
+fn test_history() {
+    let journal = Journal::with_records(vec![
+        TestRecord::new(100, 5, "Temp", &[USN_REASON_CLOSE]),
+        TestRecord::new(300, 100, "tmp123.dat", &[USN_REASON_FILE_CREATE, USN_REASON_CLOSE]),
+        TestRecord::new(300, 100, "tmp123.dat", &[USN_REASON_RENAME_OLD_NAME]),
+        TestRecord::new(300, 100, "svchost.exe", &[USN_REASON_RENAME_NEW_NAME, USN_REASON_CLOSE]),
+        TestRecord::new(301, 100, "other.txt", &[USN_REASON_FILE_CREATE, USN_REASON_CLOSE]),
+    ]);
+    let path = journal.write_to_temp_file("history");
+    let path = path.to_str().unwrap();
+
+    let output = usnjrnl_dump(&["history", "--entry", "300-1", "-r", path]);
+    let lines: Vec<_> = output.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0], "MFT entry 300, sequence number 1");
+    assert!(lines[1].contains("created") && lines[1].ends_with("/Temp/tmp123.dat"));
+    assert!(lines[2].contains("renamed: tmp123.dat -> svchost.exe") && lines[2].ends_with("/Temp/svchost.exe"));
+
+    let output = usnjrnl_dump(&["-F", "tln", "--parent", "100", "--entry", "301", path]);
+    assert_eq!(output.lines().count(), 1);
+    assert!(output.contains("other.txt"));
+}
+
+#[test]
+fn test_where() {
+    let journal = Journal::with_records(vec![
+        TestRecord::new(100, 5, "Temp", &[USN_REASON_CLOSE]),
+        TestRecord::new(200, 100, "dropper.exe", &[USN_REASON_FILE_CREATE, USN_REASON_CLOSE]),
+        TestRecord::new(201, 100, "payload.exe", &[USN_REASON_FILE_DELETE, USN_REASON_CLOSE]),
+        TestRecord::new(202, 5, "notes.txt", &[USN_REASON_FILE_DELETE, USN_REASON_CLOSE]),
+    ]);
+    let path = journal.write_to_temp_file("where");
+    let path = path.to_str().unwrap();
+
+    let output = usnjrnl_dump(&["-F", "tln", "--where", r#"reason has FILE_DELETE and path like "/temp/*.exe""#, path]);
+    let lines: Vec<_> = output.lines().collect();
+    assert_eq!(lines.len(), 1);
+    assert!(lines[0].contains("|/Temp/payload.exe: "));
+
+    let status = Command::new(env!("CARGO_BIN_EXE_usnjrnl_dump"))
+        .args(["--where", "name =", path])
+        .output()
+        .unwrap()
+        .status;
+    assert!(!status.success());
+}
+
+#[test]
+fn test_stats() {
+    let day1 = Utc.with_ymd_and_hms(2022, 11, 18, 12, 30, 0).unwrap();
+    let day2 = Utc.with_ymd_and_hms(2022, 11, 19, 8, 15, 0).unwrap();
+    let journal = Journal::with_records(vec![
+        TestRecord::new(100, 5, "Temp", &[USN_REASON_CLOSE]).at(day1),
+        TestRecord::new(200, 100, "a.tmp", &[USN_REASON_FILE_CREATE]).at(day1),
+        TestRecord::new(200, 100, "a.tmp", &[USN_REASON_FILE_CREATE, USN_REASON_CLOSE]).at(day1),
+        TestRecord::new(201, 100, "b.TMP", &[USN_REASON_FILE_DELETE, USN_REASON_CLOSE]).at(day2),
+        TestRecord::new(202, 5, "notes.txt", &[USN_REASON_DATA_EXTEND]).at(day2),
+    ]);
+    let path = journal.write_to_temp_file("stats");
+    let path = path.to_str().unwrap();
+
+    let output = usnjrnl_dump(&["stats", "--json", "--top", "1", path]);
+    let statistics: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(statistics["records"], 5);
+    assert_eq!(statistics["distinct_files"], 4);
+    assert_eq!(statistics["first_usn"], 0);
+    assert_eq!(statistics["first_timestamp"], "2022-11-18T12:30:00.000000Z");
+    assert_eq!(statistics["last_timestamp"], "2022-11-19T08:15:00.000000Z");
+    assert_eq!(statistics["reasons"]["CLOSE"], 3);
+    assert_eq!(statistics["per_day"]["2022-11-18"], 3);
+    assert_eq!(statistics["per_hour"]["2022-11-19 08:00"], 2);
+    assert_eq!(statistics["top_directories"][0]["path"], "/Temp");
+    assert_eq!(statistics["top_directories"][0]["records"], 3);
+    assert_eq!(statistics["top_directories"].as_array().unwrap().len(), 1);
+    assert_eq!(statistics["top_extensions"][0]["extension"], "tmp");
+    assert_eq!(statistics["top_extensions"][0]["records"], 3);
+
+    let output = usnjrnl_dump(&["stats", path]);
+    assert!(output.starts_with("records:            5\n"));
+    assert!(output.contains("\n  2022-11-18                 3 ##################################################\n"));
+    assert!(output.contains("\n           3  /Temp (100-1)\n"));
+}
+
+#[test]
+fn test_analyze_ransomware() {
+    let mut records = vec![
+        TestRecord::new(100, 5, "Documents", &[USN_REASON_CLOSE]).with_attributes(0x10),
+        TestRecord::new(101, 5, "Pictures", &[USN_REASON_CLOSE]).with_attributes(0x10),
+    ];
+    for (entry, parent, name) in [(300, 100, "a.docx"), (301, 100, "b.xlsx"), (302, 101, "c.jpg")] {
+        let locked = format!("{}.enc", name);
+        records.push(TestRecord::new(entry, parent, name, &[USN_REASON_RENAME_OLD_NAME]));
+        records.push(TestRecord::new(entry, parent, &locked, &[USN_REASON_RENAME_NEW_NAME, USN_REASON_CLOSE]));
+    }
+    let path = Journal::with_records(records).write_to_temp_file("ransomware");
+    let path = path.to_str().unwrap();
+
+    let output = usnjrnl_dump(&["analyze", "ransomware", path]);
+    assert_eq!(output, "no signs of ransomware found\n");
+
+    let output = usnjrnl_dump(&["analyze", "ransomware", "--min-files", "3", "--min-directories", "2", "--threshold", "40", "--json", path]);
+    let report: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(report["suspicious"], true);
+    assert_eq!(report["suspected_start"], "2022-11-18T12:00:00.000000Z");
+    assert_eq!(report["first_affected"]["file_reference"], "300-1");
+    assert_eq!(report["new_extensions"][0]["extension"], "enc");
+    assert_eq!(report["directories"][0]["path"], "/Documents");
+    assert_eq!(report["directories"][0]["files"], 2);
+    assert_eq!(report["windows"][0]["score"], 40);
+    assert_eq!(report["windows"][0]["indicators"][0], "3 files got the extension '.enc'");
+}
+
+#[test]
+fn test_analyze_drops() {
+    let path = Journal::with_records(vec![
+        TestRecord::new(100, 5, "Temp", &[USN_REASON_CLOSE]).with_attributes(0x10),
+        TestRecord::new(101, 5, "Tools", &[USN_REASON_CLOSE]).with_attributes(0x10),
+        TestRecord::new(300, 100, "payload.exe", &[USN_REASON_FILE_CREATE, USN_REASON_CLOSE]),
+        TestRecord::new(301, 101, "helper.ps1", &[USN_REASON_FILE_CREATE, USN_REASON_CLOSE]),
+    ]).write_to_temp_file("drops");
+    let path = path.to_str().unwrap();
+
+    let output = usnjrnl_dump(&["analyze", "drops", path]);
+    assert_eq!(output, "2022-11-18T12:00:00.000000Z  created: /Temp/payload.exe (USN 144)\n");
+
+    let watchlist = std::env::temp_dir().join(format!("usnjrnl_watchlist_{}.yaml", std::process::id()));
+    std::fs::write(&watchlist, "locations: [Tools]\n").unwrap();
+    let output = usnjrnl_dump(&["analyze", "drops", "--watchlist", watchlist.to_str().unwrap(), "--json", path]);
+    let alerts: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(alerts.as_array().unwrap().len(), 1);
+    assert_eq!(alerts[0]["kind"], "created");
+    assert_eq!(alerts[0]["path"], "/Tools/helper.ps1");
+    assert_eq!(alerts[0]["location"], "Tools");
+    assert_eq!(alerts[0]["record"]["file_reference"], "301-1");
+    std::fs::remove_file(&watchlist).unwrap();
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_analyze_ads() {
+    let later = Utc.with_ymd_and_hms(2022, 11, 18, 13, 0, 0).unwrap();
+    let path = Journal::with_records(vec![
+        TestRecord::new(100, 5, "Downloads", &[USN_REASON_CLOSE]).with_attributes(0x10),
+        TestRecord::new(300, 100, "setup.exe", &[USN_REASON_FILE_CREATE, USN_REASON_DATA_EXTEND, USN_REASON_CLOSE]),
+        TestRecord::new(300, 100, "setup.exe", &[USN_REASON_STREAM_CHANGE, USN_REASON_NAMED_DATA_EXTEND, USN_REASON_CLOSE]),
+        TestRecord::new(301, 100, "notes.txt", &[USN_REASON_FILE_CREATE, USN_REASON_DATA_EXTEND, USN_REASON_CLOSE]),
+        TestRecord::new(301, 100, "notes.txt", &[USN_REASON_STREAM_CHANGE, USN_REASON_NAMED_DATA_EXTEND, USN_REASON_CLOSE]).at(later),
+        TestRecord::new(300, 100, "setup.exe", &[USN_REASON_STREAM_CHANGE, USN_REASON_CLOSE]).at(later),
+    ]).write_to_temp_file("ads");
+    let path = path.to_str().unwrap();
+
+    let output = usnjrnl_dump(&["analyze", "ads", path]);
+    assert!(output.starts_with("executables which lost their mark of the web:\n  /Downloads/setup.exe (300-1)\n"));
+
+    let output = usnjrnl_dump(&["analyze", "ads", "--motw-delay", "0", "--unusual", "--json", path]);
+    let report: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(report["stripped_executables"][0], "300-1");
+    let files = report["files"].as_array().unwrap();
+    assert_eq!(files.len(), 2);
+    assert_eq!(files[1]["path"], "/Downloads/notes.txt");
+    assert_eq!(files[1]["events"][0]["kind"], "stream added");
+    assert_eq!(files[1]["events"][0]["benign"], false);
+}
+
+#[test]
+fn test_analyze_rules() {
+    let path = Journal::with_records(vec![
+        TestRecord::new(100, 5, "Temp", &[USN_REASON_CLOSE]).with_attributes(0x10),
+        TestRecord::new(300, 100, "run.ps1", &[USN_REASON_FILE_CREATE]),
+        TestRecord::new(301, 100, "notes.txt", &[USN_REASON_FILE_CREATE]),
+    ]).write_to_temp_file("rules");
+    let path = path.to_str().unwrap();
+    let rules_file = std::env::temp_dir().join(format!("usnjrnl_rules_{}.yaml", std::process::id()));
+    std::fs::write(&rules_file, "id: script-in-temp\ntitle: script in Temp\ndetection:\n  name: '*.ps1'\n  path|startswith: /temp/\n").unwrap();
+    let rules = rules_file.to_str().unwrap();
+
+    let output = usnjrnl_dump(&["analyze", "rules", "--rules", rules, path]);
+    assert_eq!(output, "2022-11-18T12:00:00.000000Z  [script-in-temp] script in Temp (USN 72)\n");
+
+    let output = usnjrnl_dump(&["analyze", "rules", "--rules", rules, "--json", path]);
+    let alert: serde_json::Value = serde_json::from_str(output.lines().next().unwrap()).unwrap();
+    assert_eq!(output.lines().count(), 1);
+    assert_eq!(alert["rule"], "script-in-temp");
+    assert_eq!(alert["usns"][0], 72);
+    std::fs::remove_file(&rules_file).unwrap();
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_diff() {
+    let a = Journal::with_records(vec![
+        TestRecord::new(100, 5, "Temp", &[USN_REASON_CLOSE]).with_attributes(0x10),
+        TestRecord::new(300, 100, "notes.txt", &[USN_REASON_FILE_CREATE, USN_REASON_CLOSE]),
+    ]);
+    let mut b = a.clone();
+    let created = b.push(TestRecord::new(301, 100, "run.ps1", &[USN_REASON_FILE_CREATE, USN_REASON_CLOSE]));
+    let a = a.write_to_temp_file("diff_a");
+    let b = b.write_to_temp_file("diff_b");
+    let (a, b) = (a.to_str().unwrap(), b.to_str().unwrap());
+
+    let output = usnjrnl_dump(&["diff", a, b]);
+    assert!(output.contains("common records:     2\n"));
+    assert!(output.contains(&format!("(show them with '--start-usn {} {}')", created, b)));
+    assert!(output.contains(&format!("created files:\n  2022-11-18T12:00:00.000000Z  run.ps1 (301-1, USN {})\n", created)));
+
+    let output = usnjrnl_dump(&["diff", "--json", a, b]);
+    let diff: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(diff["new_records"]["first_usn"], created);
+    assert_eq!(diff["consistent"], true);
+    assert_eq!(diff["created"][0]["name"], "run.ps1");
+}
+
+#[test]
+fn test_heatmap() {
+    let path = Journal::with_records(vec![
+        TestRecord::new(100, 5, "Temp", &[USN_REASON_CLOSE]).with_attributes(0x10),
+        TestRecord::new(300, 100, "a.tmp", &[USN_REASON_DATA_OVERWRITE, USN_REASON_CLOSE]),
+        TestRecord::new(301, 100, "b.tmp", &[USN_REASON_FILE_CREATE, USN_REASON_CLOSE]),
+    ]).write_to_temp_file("heatmap");
+    let path = path.to_str().unwrap();
+
+    let output = usnjrnl_dump(&["heatmap", path]);
+    assert_eq!(output, "\
+hotspots (records in a directory within 60 seconds):
+  2022-11-18 12:00:00 UTC         2  /Temp (create 1, data 1)
+
+/  2 (create 1, data 1), peak 2022-11-18 12:00:00 UTC: 2
+  Temp  2 (create 1, data 1), peak 2022-11-18 12:00:00 UTC: 2
+");
+
+    let output = usnjrnl_dump(&["heatmap", "--json", "--classes", "create,close", path]);
+    let heatmap: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(heatmap["root"]["total"], 4);
+    assert_eq!(heatmap["root"]["children"][0]["name"], "Temp");
+    assert_eq!(heatmap["root"]["children"][0]["value"], 3);
+    assert_eq!(heatmap["root"]["children"][0]["classes"]["close"], 2);
+    assert_eq!(heatmap["hotspots"][0]["path"], "/Temp");
+
+    /* durations which chrono cannot represent are rejected instead of panicking */
+    for args in [vec!["heatmap", "--bucket", "9223372036854775807", path],
+                 vec!["analyze", "ransomware", "--window", "9223372036854775807", path]] {
+        let output = Command::new(env!("CARGO_BIN_EXE_usnjrnl_dump")).args(&args).output().unwrap();
+        assert!(!output.status.success());
+        assert!(String::from_utf8_lossy(&output.stderr).contains("duration out of range: 9223372036854775807 seconds"));
+    }
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_stop_before_filters() {
+    let start = Utc.with_ymd_and_hms(2022, 11, 18, 14, 0, 0).unwrap();
+    let mut journal = Journal::default();
+    journal.push(TestRecord::new(100, 5, "report.docx", &[USN_REASON_DATA_EXTEND]).at(start));
+    journal.push(TestRecord::new(101, 5, "later.docx", &[USN_REASON_DATA_EXTEND]).at(start + Duration::seconds(45)));
+    /* timestamps in the journal are only roughly ordered */
+    journal.push(TestRecord::new(102, 5, "earlier.docx", &[USN_REASON_DATA_EXTEND]).at(start + Duration::seconds(20)));
+    let last = journal.push(TestRecord::new(103, 5, "notes.txt", &[USN_REASON_DATA_EXTEND]).at(start + Duration::minutes(5)));
+    let path = journal.write_to_temp_file("stop_before_filters");
+
+    /* a broken record, which must not be read, as the dump stops before it */
+    let mut data = std::fs::read(&path).unwrap();
+    data.extend_from_slice(&0x40u32.to_le_bytes());
+    data.extend_from_slice(&9u16.to_le_bytes());
+    data.resize(data.len() + 0x3a, 0);
+    std::fs::write(&path, data).unwrap();
+
+    let dump = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_usnjrnl_dump"))
+            .args(args)
+            .arg(&path)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        (String::from_utf8(output.stdout).unwrap(), String::from_utf8(output.stderr).unwrap())
+    };
+
+    let (_, errors) = dump(&["-F", "tln"]);
+    assert!(errors.contains("invalid value for MajorVersion: 9"));
+
+    let (output, errors) = dump(&["-F", "tln", "--name-glob", "*.docx", "--until", "2022-11-18T14:00:30Z"]);
+    let lines: Vec<_> = output.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].contains("|/report.docx: "));
+    assert!(lines[1].contains("|/earlier.docx: "));
+    assert!(!errors.contains("MajorVersion"), "{}", errors);
+
+    let (output, errors) = dump(&["-F", "tln", "--name-glob", "*.docx", "--end-usn", &last.to_string()]);
+    assert_eq!(output.lines().count(), 3);
+    assert!(!errors.contains("MajorVersion"), "{}", errors);
+    std::fs::remove_file(&path).unwrap();
+}